@@ -0,0 +1,183 @@
+use crate::audio::decode::probe_duration;
+use crate::id3_parser::chapters::{
+    format_chapters_json, format_chapters_text, has_valid_times, parse_chapter_time, parse_chapters_json,
+    parse_chapters_text,
+};
+use crate::id3_parser::{AudioMetadata, Chapter};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, Space, button, column, container, row, text, text_input, image
+    }
+};
+use iced::widget::image::Handle;
+use std::path::PathBuf;
+use super::{Message, Sizes};
+
+/// Async function to open a chapter list file dialog
+pub(super) async fn open_chapters_dialog() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("Chapter Lists", &["txt", "json"])
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Async function to choose where to export a chapter list
+pub(super) async fn save_chapters_dialog(file_name: String) -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("Plain Text Chapters", &["txt"])
+        .add_filter("Podlove Simple Chapters (JSON)", &["json"])
+        .set_file_name(file_name)
+        .save_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Whether a chapter list path refers to a Podlove JSON file
+fn is_json_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Async function to read and parse a chapter list (plain text or Podlove JSON)
+///
+/// The last chapter ends at the track end, taken from the tag or else from the audio file.
+pub(super) async fn import_chapters_async(
+    path: PathBuf,
+    audio_path: Option<PathBuf>,
+    duration_ms: Option<u32>,
+) -> Result<Vec<Chapter>, String> {
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let duration_ms = duration_ms.or_else(|| {
+        let seconds = probe_duration(audio_path?).ok().flatten()?;
+        Some(seconds.saturating_mul(1000))
+    });
+    if is_json_path(&path) {
+        parse_chapters_json(&content, duration_ms)
+    } else {
+        Ok(parse_chapters_text(&content, duration_ms))
+    }
+}
+
+/// Async function to write a chapter list (plain text or Podlove JSON)
+pub(super) async fn export_chapters_async(path: PathBuf, chapters: Vec<Chapter>) -> Result<PathBuf, String> {
+    let content = if is_json_path(&path) {
+        format_chapters_json(&chapters)?
+    } else {
+        format_chapters_text(&chapters)
+    };
+    std::fs::write(&path, content)
+        .map(|_| path)
+        .map_err(|e| e.to_string())
+}
+
+/// Build the chapter (CHAP/CTOC) editor view
+pub(super) fn build_chapters_view<'a>(metadata: &'a AudioMetadata, time_inputs: &'a [(String, String)], zoom: f32) -> Element<'a, Message> {
+    let Sizes { title: title_size, text: text_size, spacing, padding, .. } = Sizes::new(zoom);
+
+    let actions = row![
+        button("Add Chapter")
+            .on_press(Message::AddChapter)
+            .padding(8.0 * zoom),
+        button("Import Chapters...")
+            .on_press(Message::ImportChaptersDialog)
+            .padding(8.0 * zoom),
+        button("Export Chapters...")
+            .on_press_maybe((!metadata.chapters.is_empty()).then_some(Message::ExportChaptersDialog))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing);
+
+    let mut chapters_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+
+    for (index, chapter) in metadata.chapters.iter().enumerate() {
+        let (start_input, end_input) = time_inputs
+            .get(index)
+            .map(|(start, end)| (start.as_str(), end.as_str()))
+            .unwrap_or_default();
+
+        // Show the embedded chapter image (APIC sub-frame) as a thumbnail
+        let thumbnail: Element<'a, Message> = match chapter.image {
+            Some(ref data) => image(Handle::from_bytes(data.clone()))
+                .width(Length::Fixed(40.0 * zoom))
+                .height(Length::Fixed(40.0 * zoom))
+                .into(),
+            None => Space::new().width(40.0 * zoom).height(40.0 * zoom).into(),
+        };
+
+        let chapter_row = row![
+            thumbnail,
+            time_input("Start", start_input, zoom)
+                .on_input(move |value| Message::ChapterStartChanged(index, value)),
+            time_input("End", end_input, zoom)
+                .on_input(move |value| Message::ChapterEndChanged(index, value)),
+            text_input("Title", chapter.title.as_deref().unwrap_or_default())
+                .on_input(move |value| Message::ChapterTitleChanged(index, value))
+                .size(text_size)
+                .width(Length::Fill),
+            button("✕")
+                .on_press(Message::RemoveChapter(index))
+                .padding(5.0 * zoom),
+        ]
+        .spacing(5.0 * zoom)
+        .align_y(Vertical::Center);
+        chapters_column = chapters_column.push(chapter_row);
+        if !has_valid_times(chapter) {
+            chapters_column = chapters_column.push(
+                text("The chapter must end after it starts")
+                    .size(((12.0 * zoom) as u32).max(1))
+                    .style(|_theme| {
+                        iced::widget::text::Style {
+                            color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                        }
+                    }),
+            );
+        }
+    }
+
+    let chapters_list: Element<'a, Message> = if metadata.chapters.is_empty() {
+        text("No chapters in this file.")
+            .size(text_size)
+            .into()
+    } else {
+        chapters_column.into()
+    };
+
+    container(
+        column![
+            text(format!("Chapters ({})", metadata.chapters.len()))
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            actions,
+            chapters_list,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Create a chapter time input, highlighted in red while its value can't be parsed
+fn time_input<'a>(placeholder: &str, value: &'a str, zoom: f32) -> iced::widget::TextInput<'a, Message> {
+    let is_valid = parse_chapter_time(value).is_some();
+    text_input(placeholder, value)
+        .size((14.0 * zoom) as u32)
+        .width(Length::Fixed(120.0 * zoom))
+        .style(move |theme, status| {
+            let style = iced::widget::text_input::default(theme, status);
+            if is_valid {
+                style
+            } else {
+                iced::widget::text_input::Style {
+                    value: iced::Color::from_rgb(1.0, 0.3, 0.3),
+                    ..style
+                }
+            }
+        })
+}
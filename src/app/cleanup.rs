@@ -0,0 +1,206 @@
+use crate::id3_parser::cleanup::{CleanupRules, CLEANUP_FIELDS};
+use crate::id3_parser::fields::{FieldChange, MetadataField};
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator, ParseError};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, text, text_input, checkbox
+    }
+};
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, display_name, update_tags};
+
+/// Apply clean up rules to the given fields of a track
+pub(super) fn apply_cleanup(rules: &CleanupRules, fields: &[MetadataField], metadata: &mut AudioMetadata) {
+    for change in rules.changes(metadata, fields) {
+        change.field.set_value(metadata, &change.after);
+    }
+}
+
+/// Async function to compute the changes clean up rules make to several files
+///
+/// Files the rules don't change are left out.
+pub(super) async fn preview_cleanup_async(
+    files: Vec<PathBuf>,
+    rules: CleanupRules,
+    fields: Vec<MetadataField>,
+    separator: ListSeparator,
+) -> Result<Vec<(PathBuf, Vec<FieldChange>)>, String> {
+    let mut preview = Vec::new();
+    for path in files {
+        let metadata = match parse_id3(&path, separator) {
+            Ok(metadata) => metadata,
+            Err(ParseError::NoId3Tag) => continue,
+            Err(e) => return Err(format!("{}: {}", display_name(&path), e)),
+        };
+        let changes = rules.changes(&metadata, &fields);
+        if !changes.is_empty() {
+            preview.push((path, changes));
+        }
+    }
+    Ok(preview)
+}
+
+/// Async function to apply clean up rules to several files
+pub(super) async fn apply_cleanup_async(files: Vec<PathBuf>, rules: CleanupRules, fields: Vec<MetadataField>, separator: ListSeparator) -> BatchSummary {
+    update_tags(&files, separator, |_, metadata| {
+        apply_cleanup(&rules, &fields, metadata);
+        true
+    })
+}
+
+/// Build the clean up view with the rules, fields and a before/after preview
+///
+/// The loaded file is previewed live from its metadata; other files need a preview
+/// that reads their tags, and only the previewed changes are written.
+pub(super) fn build_cleanup_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let rules = &state.cleanup_rules;
+    let rule_checkbox = |label: &'static str, checked: bool, change: fn(&mut CleanupRules, bool)| {
+        let rules = rules.clone();
+        checkbox(checked)
+            .label(label)
+            .text_size(text_size)
+            .on_toggle(move |value| {
+                let mut rules = rules.clone();
+                change(&mut rules, value);
+                Message::CleanupRulesChanged(rules)
+            })
+    };
+    let rule_toggles = row![
+        rule_checkbox("Unicode NFC", rules.unicode_nfc, |rules, value| rules.unicode_nfc = value),
+        rule_checkbox("Trim whitespace", rules.collapse_whitespace, |rules, value| rules.collapse_whitespace = value),
+        rule_checkbox("Normalize feat.", rules.normalize_featuring, |rules, value| rules.normalize_featuring = value),
+        rule_checkbox("Title Case", rules.title_case, |rules, value| rules.title_case = value),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let replace_rules = rules.clone();
+    let with_rules = rules.clone();
+    let replace = row![
+        text("Replace").size(text_size),
+        text_input("characters", &rules.replace_chars)
+            .on_input(move |value| Message::CleanupRulesChanged(CleanupRules {
+                replace_chars: value,
+                ..replace_rules.clone()
+            }))
+            .size(text_size)
+            .padding(5.0 * zoom)
+            .width(Length::Fixed(120.0 * zoom)),
+        text("with").size(text_size),
+        text_input("nothing", &rules.replace_with)
+            .on_input(move |value| Message::CleanupRulesChanged(CleanupRules {
+                replace_with: value,
+                ..with_rules.clone()
+            }))
+            .size(text_size)
+            .padding(5.0 * zoom)
+            .width(Length::Fixed(120.0 * zoom)),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let exceptions = row![
+        text("Title Case exceptions").size(text_size),
+        text_input("a, the, of, DJ", &state.cleanup_exceptions)
+            .on_input(Message::CleanupExceptionsChanged)
+            .size(text_size)
+            .padding(5.0 * zoom)
+            .width(Length::Fill),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let mut fields = row![text("Fields:").size(text_size)]
+        .spacing(spacing)
+        .align_y(Vertical::Center);
+    for &field in CLEANUP_FIELDS {
+        fields = fields.push(
+            checkbox(state.cleanup_fields.contains(&field))
+                .label(field.label())
+                .text_size(small_size)
+                .on_toggle(move |enabled| Message::ToggleCleanupField(field, enabled)),
+        );
+    }
+
+    let targets = state.target_files();
+    let in_memory = state.targets_current_file_only(&targets);
+    let preview_files: Vec<(PathBuf, Vec<FieldChange>)> = if in_memory {
+        state
+            .metadata
+            .as_ref()
+            .map(|metadata| (targets[0].clone(), rules.changes(metadata, &state.cleanup_fields)))
+            .filter(|(_, changes)| !changes.is_empty())
+            .into_iter()
+            .collect()
+    } else {
+        state.cleanup_preview.clone()
+    };
+
+    let mut preview = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    for (path, changes) in &preview_files {
+        preview = preview.push(text(display_name(path)).size(text_size));
+        for change in changes {
+            preview = preview.push(
+                row![
+                    text(change.field.label()).size(small_size).width(Length::Fixed(110.0 * zoom)),
+                    text(change.before.clone())
+                        .size(small_size)
+                        .width(Length::Fill)
+                        .style(|_theme| iced::widget::text::Style {
+                            color: Some(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+                        }),
+                    text("→").size(small_size),
+                    text(change.after.clone()).size(small_size).width(Length::Fill),
+                ]
+                .spacing(spacing),
+            );
+        }
+    }
+    if in_memory && preview_files.is_empty() {
+        preview = preview.push(text("Nothing to clean up").size(small_size));
+    }
+
+    let actions = if in_memory {
+        row![
+            button("Apply")
+                .on_press_maybe((!preview_files.is_empty()).then_some(Message::ApplyCleanup))
+                .padding(8.0 * zoom),
+        ]
+    } else {
+        let idle = !state.batch_busy && state.can_write_files();
+        row![
+            button("Preview")
+                .on_press_maybe((idle && !state.cleanup_fields.is_empty()).then_some(Message::PreviewCleanup))
+                .padding(8.0 * zoom),
+            button(text(format!("Apply to {} files", preview_files.len())))
+                .on_press_maybe((idle && !preview_files.is_empty()).then_some(Message::ApplyCleanup))
+                .padding(8.0 * zoom),
+        ]
+    }
+    .spacing(spacing);
+
+    container(
+        column![
+            text("Clean Up")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            rule_toggles,
+            replace,
+            exceptions,
+            fields.wrap(),
+            preview,
+            actions,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
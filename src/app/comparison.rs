@@ -0,0 +1,177 @@
+use crate::report::image_dimensions;
+use crate::id3_parser::snapshot::TagItem;
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator, ParseError};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, Space, button, column, container, row, scrollable, text, image, checkbox
+    }
+};
+use iced::widget::button as button_widget;
+use iced::widget::image::Handle;
+use std::path::PathBuf;
+use super::{Message, Sizes, display_name};
+
+/// Two files whose tags are compared side by side
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub(super) files: [ComparedFile; 2],
+    pub(super) only_differences: bool, // Whether items with equal values are hidden
+}
+
+/// A file of a comparison, with its tags as edited there
+#[derive(Debug, Clone)]
+pub struct ComparedFile {
+    pub(super) path: PathBuf,
+    pub(super) metadata: AudioMetadata,
+    pub(super) unsaved_changes: bool,
+}
+
+/// Async function to read the tags of two files to compare (untagged files compare as empty)
+pub(super) async fn load_comparison_async(left: PathBuf, right: PathBuf, separator: ListSeparator) -> Result<Comparison, String> {
+    let load = |path: PathBuf| -> Result<ComparedFile, String> {
+        let metadata = match parse_id3(&path, separator) {
+            Ok(metadata) => metadata,
+            Err(ParseError::NoId3Tag) => AudioMetadata::default(),
+            Err(e) => return Err(format!("{}: {}", display_name(&path), e)),
+        };
+        Ok(ComparedFile {
+            path,
+            metadata,
+            unsaved_changes: false,
+        })
+    };
+    Ok(Comparison {
+        files: [load(left)?, load(right)?],
+        only_differences: false,
+    })
+}
+
+/// Build the side-by-side comparison of two files' tags, with buttons to copy values across
+pub(super) fn build_comparison_view<'a>(comparison: &'a Comparison, zoom: f32) -> Element<'a, Message> {
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+    let label_width = Length::Fixed(140.0 * zoom);
+    let copy_width = Length::Fixed(80.0 * zoom);
+
+    let [left, right] = &comparison.files;
+    let differences = TagItem::all()
+        .filter(|item| !item.same(&left.metadata, &right.metadata))
+        .count();
+    let controls = row![
+        text(format!("{} differences", differences)).size(text_size),
+        checkbox(comparison.only_differences)
+            .label("Only differences")
+            .text_size(text_size)
+            .on_toggle(Message::ToggleOnlyDifferences),
+        Space::new().width(Length::Fill),
+        button(text("Close").size(text_size))
+            .on_press(Message::CloseComparison)
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    // The file name, folder, tag version and duration of each side, and its save button
+    let side_header = |index: usize, file: &'a ComparedFile| -> Element<'a, Message> {
+        let metadata = &file.metadata;
+        let folder = file.path.parent().map(|folder| folder.to_string_lossy().to_string()).unwrap_or_default();
+        let tag = metadata.tag_version.map_or_else(|| "No tag".to_string(), |version| version.to_string());
+        // TLEN is in milliseconds
+        let duration = metadata
+            .duration
+            .map(|ms| ms / 1000)
+            .map(|seconds| format!(", {}:{:02}", seconds / 60, seconds % 60))
+            .unwrap_or_default();
+        column![
+            text(display_name(&file.path)).size(text_size),
+            text(folder).size(small_size),
+            text(format!("{}{}", tag, duration)).size(small_size),
+            button(text(if file.unsaved_changes { "Save *" } else { "Save" }).size(small_size))
+                .on_press_maybe(file.unsaved_changes.then_some(Message::SaveComparedFile(index)))
+                .padding(4.0 * zoom),
+        ]
+        .spacing(4.0 * zoom)
+        .width(Length::FillPortion(1))
+        .into()
+    };
+    let header = row![
+        Space::new().width(label_width),
+        side_header(0, left),
+        Space::new().width(copy_width),
+        side_header(1, right),
+    ]
+    .spacing(spacing);
+
+    let mut rows = Column::new().spacing(4.0 * zoom).width(Length::Fill);
+    for item in TagItem::all() {
+        let same = item.same(&left.metadata, &right.metadata);
+        if same && comparison.only_differences {
+            continue;
+        }
+        let value = |metadata: &'a AudioMetadata| -> Element<'a, Message> {
+            let value: Element<'a, Message> = match item {
+                TagItem::CoverArt => compared_cover(metadata, zoom),
+                _ => text(item.value(metadata)).size(small_size).into(),
+            };
+            container(value).width(Length::FillPortion(1)).into()
+        };
+        let copy_button = |label: &'static str, from: usize| {
+            button(text(label).size(small_size))
+                .on_press_maybe((!same).then_some(Message::CopyComparedItem(item, from)))
+                .padding(4.0 * zoom)
+                .style(button_widget::secondary)
+        };
+        // Highlight the items the files disagree on
+        let label = text(item.label()).size(small_size).width(label_width).style(move |_theme| {
+            iced::widget::text::Style {
+                color: (!same).then(|| iced::Color::from_rgb(1.0, 0.7, 0.3)),
+            }
+        });
+        rows = rows.push(
+            row![
+                label,
+                value(&left.metadata),
+                container(row![copy_button("→", 0), copy_button("←", 1)].spacing(4.0 * zoom))
+                    .width(copy_width)
+                    .align_x(Horizontal::Center),
+                value(&right.metadata),
+            ]
+            .spacing(spacing)
+            .align_y(Vertical::Center),
+        );
+    }
+
+    container(
+        column![
+            text("Compare Tags").size(title_size),
+            controls,
+            header,
+            scrollable(rows).height(Length::Fixed(400.0 * zoom)).width(Length::Fill),
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
+
+/// A cover art thumbnail with its size and format, for the comparison
+fn compared_cover(metadata: &AudioMetadata, zoom: f32) -> Element<'_, Message> {
+    let small_size = ((12.0 * zoom) as u32).max(1);
+    let Some(ref data) = metadata.cover_art else {
+        return text("No cover art").size(small_size).into();
+    };
+    let dimensions = image_dimensions(data)
+        .map_or_else(|| "unknown size".to_string(), |(width, height)| format!("{}×{}", width, height));
+    let format = metadata.cover_art_format.as_deref().unwrap_or("unknown format");
+    column![
+        image(Handle::from_bytes(data.clone()))
+            .width(Length::Fixed(120.0 * zoom))
+            .height(Length::Fixed(120.0 * zoom)),
+        text(format!("{}, {}, {} KB", dimensions, format, data.len() / 1024)).size(small_size),
+    ]
+    .spacing(4.0 * zoom)
+    .into()
+}
@@ -0,0 +1,87 @@
+use crate::id3_parser::{AudioMetadata, Credit, TagVersion};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        button, column, container, row, text, text_input
+    }
+};
+use super::{Message, Sizes};
+
+/// The credit lists of a track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreditList {
+    InvolvedPeople, // TIPL (production credits)
+    Musicians,      // TMCL (instruments)
+}
+
+impl CreditList {
+    fn credits(self, metadata: &AudioMetadata) -> &Vec<Credit> {
+        match self {
+            CreditList::InvolvedPeople => &metadata.involved_people,
+            CreditList::Musicians => &metadata.musician_credits,
+        }
+    }
+
+    pub(super) fn credits_mut(self, metadata: &mut AudioMetadata) -> &mut Vec<Credit> {
+        match self {
+            CreditList::InvolvedPeople => &mut metadata.involved_people,
+            CreditList::Musicians => &mut metadata.musician_credits,
+        }
+    }
+}
+
+/// Build the credits editor: a table of role and name per credit list
+///
+/// ID3v2.3 has a single list (IPLS); musician credits are merged into it when saving.
+pub(super) fn build_credits_view<'a>(metadata: &'a AudioMetadata, zoom: f32) -> Element<'a, Message> {
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let table = |list: CreditList, heading: &'static str, role_placeholder: &'static str| {
+        let mut rows = column![text(heading).size(text_size)].spacing(5.0 * zoom);
+        for (index, credit) in list.credits(metadata).iter().enumerate() {
+            rows = rows.push(
+                row![
+                    text_input(role_placeholder, &credit.role)
+                        .on_input(move |value| Message::CreditRoleChanged(list, index, value))
+                        .size(text_size)
+                        .width(Length::FillPortion(1)),
+                    text_input("Name", &credit.name)
+                        .on_input(move |value| Message::CreditNameChanged(list, index, value))
+                        .size(text_size)
+                        .width(Length::FillPortion(2)),
+                    button("✕")
+                        .on_press(Message::RemoveCredit(list, index))
+                        .padding(5.0 * zoom),
+                ]
+                .spacing(5.0 * zoom)
+                .align_y(Vertical::Center),
+            );
+        }
+        rows.push(
+            button(text("Add Credit").size(text_size))
+                .on_press(Message::AddCredit(list))
+                .padding(5.0 * zoom),
+        )
+    };
+
+    let mut column = column![
+        text("Credits")
+            .size(title_size)
+            .align_x(Horizontal::Center),
+        table(CreditList::InvolvedPeople, "Involved people (TIPL)", "Role (e.g. producer)"),
+        table(CreditList::Musicians, "Musicians (TMCL)", "Instrument (e.g. bass)"),
+    ]
+    .spacing(spacing)
+    .width(Length::Fill);
+    if matches!(metadata.tag_version, Some(TagVersion::Id3v22 | TagVersion::Id3v23)) {
+        column = column.push(
+            text("ID3v2.3 stores all credits in one involved people list (IPLS); musicians are saved there too")
+                .size(small_size),
+        );
+    }
+
+    container(column)
+        .padding(padding)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+        .into()
+}
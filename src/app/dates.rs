@@ -0,0 +1,122 @@
+use crate::id3_parser::fields::MetadataField;
+use crate::id3_parser::{AudioMetadata, TagVersion, Timestamp};
+use crate::id3_parser::timestamp::{from_year, parse_timestamp, TimestampParts, TIMESTAMP_PARTS};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, text, text_input
+    }
+};
+use super::{Message, Sizes, State, create_element_row};
+
+/// Build the date editor, with an input per part of each timestamp
+///
+/// A part can only be entered once the coarser parts are, and emptying a part clears the
+/// finer ones as well.
+pub(super) fn build_dates_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    // ID3v2.3 stores the recording time as TYER + TDAT + TIME and the original release as TORY
+    let id3v23 = matches!(metadata.tag_version, Some(TagVersion::Id3v22 | TagVersion::Id3v23));
+
+    let mut rows = Column::new()
+        .spacing(spacing)
+        .width(Length::Fill);
+    for field in MetadataField::ALL.into_iter().filter(|field| field.is_timestamp()) {
+        let editable = !(id3v23 && field.is_id3v24_only());
+        let timestamp = field.value(metadata).and_then(|value| parse_timestamp(&value));
+        let precision = timestamp.map_or(0, |timestamp| timestamp.precision());
+        let parts = timestamp.map(|timestamp| timestamp.parts()).unwrap_or_default();
+
+        let mut inputs = row![].spacing(5.0 * zoom).align_y(Vertical::Center);
+        for (index, name) in TIMESTAMP_PARTS.into_iter().enumerate() {
+            if index > 0 {
+                inputs = inputs.push(text(match index {
+                    1 | 2 => "-",
+                    3 => " ",
+                    _ => ":",
+                }).size(text_size));
+            }
+            let value = parts[index].map(|part| part.to_string()).unwrap_or_default();
+            let width = if index == 0 { 70.0 } else { 45.0 };
+            inputs = inputs.push(
+                text_input(name, &value)
+                    .on_input_maybe((editable && index <= precision).then_some(move |input: String| {
+                        let edited = edit_timestamp_part(timestamp, index, &input).unwrap_or(timestamp);
+                        Message::FieldChanged(field, edited.map(|timestamp| timestamp.to_string()).unwrap_or_default())
+                    }))
+                    .size(text_size)
+                    .width(Length::Fixed(width * zoom)),
+            );
+        }
+        inputs = inputs.push(
+            button(text("Clear").size(text_size))
+                .on_press_maybe((editable && timestamp.is_some()).then(|| Message::FieldChanged(field, String::new())))
+                .padding(5.0 * zoom),
+        );
+        if let Some(timestamp) = timestamp {
+            inputs = inputs.push(text(timestamp.to_string()).size(small_size));
+        }
+        rows = rows.push(create_element_row(format!("{}:", field.label()), inputs.into(), zoom, state.theme));
+    }
+
+    let mut column = column![
+        text("Dates")
+            .size(title_size)
+            .align_x(Horizontal::Center),
+        rows,
+    ]
+    .spacing(spacing)
+    .width(Length::Fill);
+    if id3v23 {
+        column = column.push(
+            text(
+                "ID3v2.3 keeps the recording time to the minute and the original release year only; \
+                 release, encoding and tagging dates are ID3v2.4 frames, convert the tag to set them",
+            )
+            .size(small_size),
+        );
+        // Presets, pasted tags or the comparison can still set them on the loaded file
+        let unwritable: Vec<&str> = MetadataField::ALL
+            .into_iter()
+            .filter(|field| field.is_id3v24_only() && field.value(metadata).is_some())
+            .map(MetadataField::label)
+            .collect();
+        if state.unsaved_changes && !unwritable.is_empty() {
+            column = column.push(
+                text(format!("Not saved to this ID3v2.3 tag: {}", unwritable.join(", ")))
+                    .size(small_size)
+                    .style(|_theme| iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                    }),
+            );
+        }
+    }
+
+    container(column)
+        .padding(padding)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Apply the text of a part input to a timestamp
+///
+/// Returns None for input that isn't a valid value of the part, the new timestamp (None =
+/// cleared) otherwise.
+fn edit_timestamp_part(timestamp: Option<Timestamp>, index: usize, input: &str) -> Option<Option<Timestamp>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Some(timestamp.and_then(|timestamp| timestamp.truncated(index)));
+    }
+    if !input.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let value = input.parse().ok()?;
+    match timestamp {
+        Some(timestamp) => timestamp.with_part(index, value).map(Some),
+        // Only the year can start a timestamp
+        None if index == 0 => from_year(0).with_part(0, value).map(Some),
+        None => None,
+    }
+}
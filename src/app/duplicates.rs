@@ -0,0 +1,207 @@
+use crate::duplicates::{find_duplicate_tracks, DuplicateAction, DuplicateFile, DuplicateGroup, DuplicateOptions};
+use crate::rename::{MoveReport, PlannedRename};
+use crate::id3_parser::fields::MetadataField;
+use crate::id3_parser::ListSeparator;
+use iced::{
+    Element, Length, alignment::Vertical, widget::{
+        Column, Space, button, column, container, row, scrollable, text, text_input, pick_list, checkbox
+    }
+};
+use iced::widget::button as button_widget;
+use std::path::PathBuf;
+use super::rename::apply_renames_async;
+use super::{Message, Sizes, State, display_name, list_audio_files_recursive};
+
+/// Most duplicate groups listed on screen at once
+const MAX_DUPLICATE_GROUPS: usize = 100;
+
+/// Properties of duplicate tracks compared side by side (field labels or file properties)
+const DUPLICATE_PROPERTIES: [&str; 12] = [
+    "Folder", "Title", "Artist", "Album", "Album Artist", "Year", "Track", "ISRC", "Duration", "Size", "Tag", "Cover Art",
+];
+
+/// Async function to group the audio files below a folder that are likely the same track
+pub(super) async fn find_duplicate_tracks_async(folder: PathBuf, options: DuplicateOptions, separator: ListSeparator) -> Result<Vec<DuplicateGroup>, String> {
+    let files = list_audio_files_recursive(&folder)?;
+    if files.is_empty() {
+        return Err("no audio files found".to_string());
+    }
+    Ok(find_duplicate_tracks(&files, &options, separator))
+}
+
+/// Async function to move duplicates away (recorded in the undo log) and trash others
+pub(super) async fn resolve_duplicates_async(moves: Vec<PlannedRename>, trash: Vec<PathBuf>) -> Result<(MoveReport, Vec<PathBuf>), String> {
+    let mut report = apply_renames_async(moves).await?;
+    let mut trashed = Vec::new();
+    for path in trash {
+        match trash::delete(&path) {
+            Ok(()) => {
+                log::info!("Moved {:?} to the trash", path);
+                trashed.push(path);
+            }
+            Err(e) => {
+                log::warn!("Could not move {:?} to the trash: {}", path, e);
+                report.failed.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok((report, trashed))
+}
+
+/// Build the duplicate track finder: its criteria and the groups found, side by side
+pub(super) fn build_duplicates_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let options = &state.duplicate_options;
+    let option_checkbox = |label: &'static str, checked: bool, change: fn(&mut DuplicateOptions, bool)| {
+        let options = options.clone();
+        checkbox(checked)
+            .label(label)
+            .text_size(text_size)
+            .on_toggle(move |value| {
+                let mut options = options.clone();
+                change(&mut options, value);
+                Message::DuplicateOptionsChanged(options)
+            })
+    };
+    let tolerance_options = options.clone();
+    let criteria = row![
+        option_checkbox("ISRC", options.match_isrc, |options, value| options.match_isrc = value),
+        option_checkbox("Artist + title", options.match_artist_title, |options, value| {
+            options.match_artist_title = value
+        }),
+        option_checkbox("Durations within", options.check_duration, |options, value| options.check_duration = value),
+        text_input("s", &options.duration_tolerance.to_string())
+            .on_input(move |value| {
+                let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+                Message::DuplicateOptionsChanged(DuplicateOptions {
+                    duration_tolerance: digits.parse().unwrap_or(0),
+                    ..tolerance_options.clone()
+                })
+            })
+            .size(text_size)
+            .padding(5.0 * zoom)
+            .width(Length::Fixed(50.0 * zoom)),
+        text("s").size(text_size),
+        option_checkbox("Audio data (slow)", options.match_content, |options, value| options.match_content = value),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center)
+    .wrap()
+    .vertical_spacing(5.0 * zoom);
+
+    let busy = state.duplicates_busy;
+    let rescan = state.duplicate_folder.clone().map(|folder| Message::DuplicateFolderSelected(Some(folder)));
+    let marked = state.duplicate_actions.len();
+    let destination = state
+        .duplicate_destination
+        .as_ref()
+        .map_or_else(|| "No folder chosen".to_string(), |folder| folder.to_string_lossy().to_string());
+    let actions = row![
+        button(text(if busy { "Searching..." } else { "Scan Folder..." }).size(text_size))
+            .on_press_maybe((!busy).then_some(Message::DuplicateFolderDialog))
+            .padding(8.0 * zoom),
+        button(text("Rescan").size(text_size))
+            .on_press_maybe(rescan.filter(|_| !busy))
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+        button(text("Move To...").size(text_size))
+            .on_press(Message::ChooseDuplicateDestination)
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+        text(destination).size(small_size),
+        button(text(format!("Apply ({} marked)", marked)).size(text_size))
+            .on_press_maybe((marked > 0 && !busy).then_some(Message::ApplyDuplicateActions))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let mut duplicates_column = column![text("Duplicate Tracks").size(title_size), criteria, actions]
+        .spacing(spacing)
+        .width(Length::Fill);
+
+    let label_width = Length::Fixed(90.0 * zoom);
+    let mut groups_column = Column::new().spacing(spacing).width(Length::Fill);
+    for (index, group) in state.duplicate_tracks.iter().take(MAX_DUPLICATE_GROUPS).enumerate() {
+        let reasons: Vec<&str> = group.reasons.iter().map(|reason| reason.label()).collect();
+        let mut group_column = column![text(format!("Group {}: {}", index + 1, reasons.join(", "))).size(text_size)]
+            .spacing(4.0 * zoom)
+            .width(Length::Fill);
+
+        // One column per file: its name, the action and then one row per property
+        let mut header = row![Space::new().width(label_width)].spacing(spacing).align_y(Vertical::Center);
+        for file in &group.files {
+            let path = file.path.clone();
+            let action = state.duplicate_actions.get(&file.path).copied().unwrap_or_default();
+            header = header.push(
+                column![
+                    button(text(display_name(&file.path)).size(small_size))
+                        .on_press(Message::FileSelected(Some(file.path.clone())))
+                        .padding(4.0 * zoom)
+                        .style(button_widget::text),
+                    pick_list(DuplicateAction::ALL, Some(action), move |action| {
+                        Message::DuplicateActionChanged(path.clone(), action)
+                    })
+                    .text_size(small_size),
+                ]
+                .spacing(4.0 * zoom)
+                .width(Length::FillPortion(1)),
+            );
+        }
+        group_column = group_column.push(header);
+
+        for property in DUPLICATE_PROPERTIES {
+            let values: Vec<String> = group.files.iter().map(|file| duplicate_property(file, property)).collect();
+            // Highlight the properties the copies disagree on
+            let differs = values.iter().any(|value| *value != values[0]);
+            let mut property_row = row![text(property).size(small_size).width(label_width).style(move |_theme| {
+                iced::widget::text::Style {
+                    color: differs.then(|| iced::Color::from_rgb(1.0, 0.7, 0.3)),
+                }
+            })]
+            .spacing(spacing);
+            for value in values {
+                property_row = property_row.push(text(value).size(small_size).width(Length::FillPortion(1)));
+            }
+            group_column = group_column.push(property_row);
+        }
+        groups_column = groups_column.push(container(group_column).padding(8.0 * zoom).style(container::bordered_box));
+    }
+    if state.duplicate_tracks.len() > MAX_DUPLICATE_GROUPS {
+        groups_column = groups_column.push(
+            text(format!("...and {} more groups", state.duplicate_tracks.len() - MAX_DUPLICATE_GROUPS))
+                .size(small_size),
+        );
+    }
+    if !state.duplicate_tracks.is_empty() {
+        duplicates_column =
+            duplicates_column.push(scrollable(groups_column).height(Length::Fixed(400.0 * zoom)).width(Length::Fill));
+    }
+
+    container(duplicates_column)
+        .padding(padding)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+        .into()
+}
+
+/// A property of a duplicate track, as shown side by side
+fn duplicate_property(file: &DuplicateFile, property: &str) -> String {
+    let field = |field: MetadataField| file.metadata.as_ref().and_then(|metadata| field.value(metadata));
+    match property {
+        "Folder" => file.path.parent().map(display_name),
+        "Duration" => file.duration.map(|seconds| format!("{}:{:02}", seconds / 60, seconds % 60)),
+        "Size" => Some(format!("{:.1} MB", file.size as f64 / 1_000_000.0)),
+        "Tag" => Some(
+            file.metadata
+                .as_ref()
+                .and_then(|metadata| metadata.tag_version)
+                .map_or_else(|| "None".to_string(), |version| version.to_string()),
+        ),
+        "Cover Art" => Some(if file.has_cover_art { "Yes" } else { "No" }.to_string()),
+        _ => MetadataField::from_name(property).and_then(field),
+    }
+    .unwrap_or_default()
+}
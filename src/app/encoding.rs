@@ -0,0 +1,114 @@
+use crate::id3_parser::encoding::{guess_encoding, repair, repair_changes, suspicious_fields, LegacyEncoding};
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, text, pick_list
+    }
+};
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, update_tags};
+
+/// Async function to re-decode the mis-encoded text of several files
+///
+/// Files are repaired under the given code page, or the one guessed from each file's own
+/// text without one. Files without text to repair are left untouched. The repaired frames
+/// are written as new frames, which the tag writer encodes as UTF-16 (ID3v2.3) or UTF-8
+/// (ID3v2.4).
+pub(super) async fn repair_encodings_async(files: Vec<PathBuf>, encoding: Option<LegacyEncoding>, separator: ListSeparator) -> BatchSummary {
+    let encoding_of = |metadata: &AudioMetadata| encoding.or_else(|| guess_encoding(metadata));
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|path| {
+            parse_id3(path, separator).is_ok_and(|metadata| {
+                encoding_of(&metadata).is_some_and(|encoding| !repair_changes(&metadata, encoding).is_empty())
+            })
+        })
+        .collect();
+    update_tags(&files, separator, |_, metadata| {
+        encoding_of(metadata).is_some_and(|encoding| repair(metadata, encoding))
+    })
+}
+
+/// Build the text encoding repair view with a live preview of the re-decoded fields
+pub(super) fn build_encoding_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let guess = guess_encoding(metadata);
+    let encoding = state.legacy_encoding.or(guess);
+    let mut code_page = row![
+        text("Code page").size(text_size),
+        pick_list(LegacyEncoding::ALL, encoding, Message::LegacyEncodingSelected)
+            .placeholder("Choose...")
+            .text_size(text_size),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+    if let Some(guess) = guess {
+        code_page = code_page.push(text(format!("Suggested: {}", guess)).size(small_size));
+    }
+
+    let changes = encoding.map(|encoding| repair_changes(metadata, encoding)).unwrap_or_default();
+    let mut preview = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    for field in suspicious_fields(metadata) {
+        let before = field.value(metadata).unwrap_or_default();
+        let change = changes.iter().find(|change| change.field == field);
+        let after = change.map_or_else(|| "Not valid in this code page".to_string(), |change| change.after.clone());
+        let valid = change.is_some();
+        preview = preview.push(
+            row![
+                text(field.label()).size(small_size).width(Length::Fixed(110.0 * zoom)),
+                text(before)
+                    .size(small_size)
+                    .width(Length::Fill)
+                    .style(|_theme| iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+                    }),
+                text("→").size(small_size),
+                text(after)
+                    .size(small_size)
+                    .width(Length::Fill)
+                    .style(move |_theme| iced::widget::text::Style {
+                        color: (!valid).then(|| iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                    }),
+            ]
+            .spacing(spacing),
+        );
+    }
+
+    let mut actions = row![
+        button("Repair")
+            .on_press_maybe((!changes.is_empty()).then_some(Message::RepairEncoding))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing);
+    if !state.selected_files.is_empty() {
+        let ready = !state.batch_busy && state.can_write_files();
+        actions = actions.push(
+            button(text(format!("Repair {} selected files", state.selected_files.len())))
+                .on_press_maybe(ready.then_some(Message::RepairSelectedEncodings))
+                .padding(8.0 * zoom),
+        );
+    }
+
+    container(
+        column![
+            text("Text Encoding")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            text("These fields look like legacy code page text read as ISO-8859-1. Repaired text is saved as Unicode.")
+                .size(small_size),
+            code_page,
+            preview,
+            actions,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
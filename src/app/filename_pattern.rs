@@ -0,0 +1,123 @@
+use crate::id3_parser::filename::{apply_values, FilenamePattern};
+use crate::id3_parser::ListSeparator;
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, text, text_input, pick_list
+    }
+};
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, display_name, update_tags};
+
+/// Async function to guess the tags of several files from their paths
+pub(super) async fn apply_filename_pattern_async(files: Vec<PathBuf>, pattern: FilenamePattern, separator: ListSeparator) -> BatchSummary {
+    update_tags(&files, separator, |path, metadata| match pattern.extract(path) {
+        Some(values) if !values.is_empty() => {
+            apply_values(metadata, &values);
+            true
+        }
+        _ => false,
+    })
+}
+
+/// Build the tags-from-filename view with a preview of the values for each target file
+pub(super) fn build_filename_pattern_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let selected_preset = state
+        .filename_patterns
+        .iter()
+        .find(|pattern| **pattern == state.filename_pattern)
+        .cloned();
+    let presets = row![
+        pick_list(state.filename_patterns.clone(), selected_preset.clone(), Message::FilenamePresetSelected)
+            .placeholder("Presets")
+            .width(Length::Fill),
+        button("Save Preset")
+            .on_press_maybe(
+                (selected_preset.is_none() && !state.filename_pattern.trim().is_empty())
+                    .then_some(Message::SaveFilenamePreset)
+            )
+            .padding(8.0 * zoom),
+        button("Remove Preset")
+            .on_press_maybe(selected_preset.is_some().then_some(Message::RemoveFilenamePreset))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let pattern_input = text_input("%albumartist%/%album%/%track% - %title%", &state.filename_pattern)
+        .on_input(Message::FilenamePatternChanged)
+        .size(text_size)
+        .padding(5.0 * zoom);
+
+    let targets = state.target_files();
+    let pattern = FilenamePattern::parse(&state.filename_pattern);
+
+    let mut preview = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    let mut matches = 0;
+    match &pattern {
+        Ok(pattern) => {
+            for path in &targets {
+                let values = pattern.extract(path).filter(|values| !values.is_empty());
+                let summary = match &values {
+                    Some(values) => {
+                        matches += 1;
+                        values
+                            .iter()
+                            .map(|(field, value)| format!("{}: {}", field.label(), value))
+                            .collect::<Vec<_>>()
+                            .join(" · ")
+                    }
+                    None => "No match".to_string(),
+                };
+                preview = preview.push(
+                    column![
+                        text(display_name(path)).size(text_size),
+                        text(summary).size(small_size).style(move |_theme| iced::widget::text::Style {
+                            color: values.is_none().then(|| iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                        }),
+                    ]
+                    .spacing(2.0 * zoom),
+                );
+            }
+        }
+        Err(e) => {
+            preview = preview.push(text(e.clone()).size(small_size).style(|_theme| iced::widget::text::Style {
+                color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+            }));
+        }
+    }
+
+    let in_memory = state.targets_current_file_only(&targets);
+    let ready = !state.batch_busy && matches > 0 && (in_memory || state.can_write_files());
+    let apply_label = if in_memory {
+        "Apply".to_string()
+    } else {
+        format!("Apply to {} files", matches)
+    };
+
+    container(
+        column![
+            text("Guess Tags from Filename")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            presets,
+            pattern_input,
+            text("Use %field% placeholders (%ignore% to skip text), / for folders, or a regex with named groups")
+                .size(small_size),
+            preview,
+            button(text(apply_label))
+                .on_press_maybe(ready.then_some(Message::ApplyFilenamePattern))
+                .padding(8.0 * zoom),
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
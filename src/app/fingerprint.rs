@@ -0,0 +1,207 @@
+use crate::audio::fingerprint::{compute_fingerprint, find_duplicates, Fingerprint};
+use crate::config::get_fingerprint_index_path;
+use crate::lookup::fingerprint::{FingerprintIndex, FingerprintProvider, IndexEntry, RecordingMatch};
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator, ParseError};
+use iced::{Element, Length, alignment::{Horizontal, Vertical}, widget::{Column, button, column, container, row, text}};
+use iced::widget::button as button_widget;
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, create_row, display_name};
+
+/// The fingerprint stored in a tag, or a freshly computed one
+///
+/// `duration_ms` is the track length from the tag (TLEN), fingerprints keep it in seconds.
+fn fingerprint_of(path: &std::path::Path, stored: Option<&str>, duration_ms: Option<u32>) -> Result<Fingerprint, String> {
+    let duration = duration_ms.map_or(0, |duration_ms| duration_ms / 1000);
+    if let Some(fingerprint) = stored.and_then(|stored| Fingerprint::decode(stored, duration)) {
+        return Ok(fingerprint);
+    }
+    compute_fingerprint(path).map_err(|e| e.to_string())
+}
+
+/// Load the local fingerprint index from the config directory
+fn load_fingerprint_index() -> Result<FingerprintIndex, String> {
+    let path = get_fingerprint_index_path().ok_or("could not determine the fingerprint index path")?;
+    FingerprintIndex::load(path).map_err(|e| e.to_string())
+}
+
+/// Async function to compute the encoded fingerprint of a file
+pub(super) async fn compute_fingerprint_async(path: PathBuf) -> Result<String, String> {
+    compute_fingerprint(&path)
+        .map(|fingerprint| fingerprint.encode())
+        .map_err(|e| e.to_string())
+}
+
+/// Async function to identify a file by its fingerprint in the local index
+pub(super) async fn identify_fingerprint_async(path: PathBuf, stored: Option<String>) -> Result<Vec<RecordingMatch>, String> {
+    let fingerprint = fingerprint_of(&path, stored.as_deref(), None)?;
+    let provider: Box<dyn FingerprintProvider> = Box::new(load_fingerprint_index()?);
+    provider.identify(&fingerprint).map_err(|e| e.to_string())
+}
+
+/// Async function to add files to the local fingerprint index
+///
+/// Files without metadata given are read from disk. Untagged files are skipped, as
+/// there is nothing to suggest from them.
+pub(super) async fn index_fingerprints_async(files: Vec<(PathBuf, Option<AudioMetadata>)>, separator: ListSeparator) -> Result<(BatchSummary, usize), String> {
+    let mut index = load_fingerprint_index()?;
+    let mut summary = BatchSummary::default();
+    for (path, metadata) in files {
+        let metadata = match metadata.map(Ok).unwrap_or_else(|| parse_id3(&path, separator)) {
+            Ok(metadata) => metadata,
+            Err(ParseError::NoId3Tag) => continue,
+            Err(e) => {
+                log::warn!("Could not read tags of {:?}: {}", path, e);
+                summary.failed.push(display_name(&path));
+                continue;
+            }
+        };
+        match fingerprint_of(&path, metadata.acoustid_fingerprint.as_deref(), metadata.duration) {
+            Ok(fingerprint) => {
+                let entry = IndexEntry::from_metadata(&path, &metadata, &fingerprint);
+                if entry.is_tagged() {
+                    index.add(entry);
+                    summary.written += 1;
+                }
+            }
+            Err(e) => {
+                log::warn!("Could not fingerprint {:?}: {}", path, e);
+                summary.failed.push(display_name(&path));
+            }
+        }
+    }
+    index.save().map_err(|e| e.to_string())?;
+    Ok((summary, index.len()))
+}
+
+/// Async function to group the files of a folder by recording
+pub(super) async fn find_duplicates_async(files: Vec<PathBuf>, separator: ListSeparator) -> Result<Vec<Vec<PathBuf>>, String> {
+    let mut paths = Vec::new();
+    let mut fingerprints = Vec::new();
+    for path in files {
+        let metadata = parse_id3(&path, separator).ok();
+        let stored = metadata.as_ref().and_then(|metadata| metadata.acoustid_fingerprint.as_deref());
+        match fingerprint_of(&path, stored, metadata.as_ref().and_then(|metadata| metadata.duration)) {
+            Ok(fingerprint) => {
+                paths.push(path);
+                fingerprints.push(fingerprint);
+            }
+            Err(e) => log::warn!("Skipping {:?} in duplicate search: {}", path, e),
+        }
+    }
+    if fingerprints.is_empty() {
+        return Err("no file could be decoded".to_string());
+    }
+    Ok(find_duplicates(&fingerprints)
+        .into_iter()
+        .map(|group| group.into_iter().map(|index| paths[index].clone()).collect())
+        .collect())
+}
+
+/// Build the acoustic fingerprint view with identified recordings and folder duplicates
+pub(super) fn build_fingerprint_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, spacing, padding, .. } = Sizes::new(zoom);
+
+    let fingerprint = metadata.acoustid_fingerprint.as_deref().map_or_else(
+        || "Not computed".to_string(),
+        |fingerprint| {
+            let preview: String = fingerprint.chars().take(32).collect();
+            format!("{}... ({} characters)", preview, fingerprint.len())
+        },
+    );
+
+    let idle = !state.fingerprint_busy;
+    let has_file = state.file_path.is_some();
+    let mut actions = row![
+        button("Compute Fingerprint")
+            .on_press_maybe((idle && has_file).then_some(Message::ComputeFingerprint))
+            .padding(8.0 * zoom),
+        button("Identify")
+            .on_press_maybe((idle && has_file).then_some(Message::IdentifyFingerprint))
+            .padding(8.0 * zoom),
+        button("Add to Index")
+            .on_press_maybe((idle && has_file).then_some(Message::AddToFingerprintIndex))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+    if state.folder_path.is_some() {
+        let folder_ready = idle && !state.folder_files.is_empty();
+        actions = actions.push(
+            button("Index Folder")
+                .on_press_maybe(folder_ready.then_some(Message::IndexFolderFingerprints))
+                .padding(8.0 * zoom),
+        );
+        actions = actions.push(
+            button("Find Duplicates")
+                .on_press_maybe(folder_ready.then_some(Message::FindDuplicates))
+                .padding(8.0 * zoom),
+        );
+    }
+
+    let mut matches_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    for (index, recording) in state.fingerprint_matches.iter().enumerate() {
+        let mut details = Vec::new();
+        if let Some(ref album) = recording.album {
+            details.push(album.clone());
+        }
+        if let Some(ref recording_id) = recording.recording_id {
+            details.push(recording_id.clone());
+        }
+        matches_column = matches_column.push(
+            row![
+                column![
+                    text(format!(
+                        "{} – {}",
+                        recording.artist.as_deref().unwrap_or("Unknown Artist"),
+                        recording.title.as_deref().unwrap_or("Unknown Title")
+                    ))
+                    .size(text_size),
+                    text(details.join(" · ")).size(((12.0 * zoom) as u32).max(1)),
+                ]
+                .width(Length::Fill),
+                text(format!("{}%", recording.score)).size(text_size),
+                button("Apply")
+                    .on_press(Message::ApplyRecordingMatch(index))
+                    .padding(5.0 * zoom),
+            ]
+            .spacing(spacing)
+            .align_y(Vertical::Center),
+        );
+    }
+
+    let mut duplicates_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    for (index, group) in state.duplicate_groups.iter().enumerate() {
+        duplicates_column = duplicates_column.push(text(format!("Duplicate group {}:", index + 1)).size(text_size));
+        for path in group {
+            duplicates_column = duplicates_column.push(
+                button(text(display_name(path)).size(text_size))
+                    .on_press(Message::FolderFileSelected(path.clone()))
+                    .padding(5.0 * zoom)
+                    .style(button_widget::text),
+            );
+        }
+    }
+
+    container(
+        column![
+            text("Acoustic Fingerprint")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            create_row("Fingerprint:", fingerprint, zoom, state.theme),
+            actions,
+            matches_column,
+            duplicates_column,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,85 @@
+use crate::id3_parser::genres::search_genres;
+use crate::id3_parser::AudioMetadata;
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        button, column, container, row, text, text_input
+    }
+};
+use iced::widget::button as button_widget;
+use super::{Message, Sizes, State};
+
+/// Most genres the genre picker lists for a search
+const MAX_GENRE_RESULTS: usize = 24;
+
+/// Build the genre picker: the genres of the track, a search over the ID3v1 genre list and
+/// the favorites, which are listed first and shown without a search
+pub(super) fn build_genre_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let mut chips = row![].spacing(5.0 * zoom);
+    for genre in &metadata.genres {
+        chips = chips.push(
+            button(text(format!("{} ×", genre)).size(text_size))
+                .on_press(Message::RemoveGenre(genre.clone()))
+                .padding(5.0 * zoom)
+                .style(button_widget::secondary),
+        );
+    }
+    let current: Element<'a, Message> = if metadata.genres.is_empty() {
+        text("No genre").size(small_size).into()
+    } else {
+        chips.wrap().vertical_spacing(5.0 * zoom).into()
+    };
+
+    let search = state.genre_search.trim();
+    let search_row = row![
+        text_input("Search genres or type a new one", &state.genre_search)
+            .on_input(Message::GenreSearchChanged)
+            .on_submit_maybe((!search.is_empty()).then(|| Message::AddGenre(search.to_string())))
+            .size(text_size)
+            .width(Length::Fill),
+        button(text("Add").size(text_size))
+            .on_press_maybe((!search.is_empty()).then(|| Message::AddGenre(search.to_string())))
+            .padding(5.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let mut results = row![].spacing(5.0 * zoom);
+    for genre in search_genres(search, &state.favorite_genres).into_iter().take(MAX_GENRE_RESULTS) {
+        let favorite = state.favorite_genres.iter().any(|favorite| favorite.eq_ignore_ascii_case(genre));
+        results = results.push(
+            row![
+                button(text(genre).size(text_size))
+                    .on_press(Message::AddGenre(genre.to_string()))
+                    .padding(5.0 * zoom),
+                button(text(if favorite { "★" } else { "☆" }).size(text_size))
+                    .on_press(Message::ToggleFavoriteGenre(genre.to_string()))
+                    .padding(2.0 * zoom)
+                    .style(button_widget::text),
+            ]
+            .align_y(Vertical::Center),
+        );
+    }
+
+    let mut column = column![
+        text("Genres")
+            .size(title_size)
+            .align_x(Horizontal::Center),
+        current,
+        search_row,
+        results.wrap().vertical_spacing(5.0 * zoom),
+    ]
+    .spacing(spacing)
+    .width(Length::Fill);
+    if search.is_empty() && state.favorite_genres.is_empty() {
+        column = column.push(text("Mark genres with ☆ to list them here").size(small_size));
+    }
+
+    container(column)
+        .padding(padding)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+        .into()
+}
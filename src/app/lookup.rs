@@ -0,0 +1,112 @@
+use crate::lookup::local::LocalDatabase;
+use crate::lookup::{LookupQuery, MetadataProvider, ReleaseCandidate};
+use crate::id3_parser::ListSeparator;
+use iced::{Element, Length, alignment::{Horizontal, Vertical}, widget::{Column, button, column, container, row, text}};
+use std::path::PathBuf;
+use std::sync::Arc;
+use super::{BatchSummary, LoadedDatabase, Message, Sizes, State, display_name, update_tags};
+
+/// Async function to open a lookup database file dialog
+pub(super) async fn open_lookup_database_dialog() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("MusicBrainz JSON", &["json", "jsonl"])
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Async function to look up releases matching a query in a local database
+///
+/// The database is only read from disk when it isn't loaded yet; it is returned with the
+/// releases so later searches can reuse it.
+pub(super) async fn lookup_async(
+    database: PathBuf,
+    cached: Option<Arc<dyn MetadataProvider>>,
+    query: LookupQuery,
+) -> Result<(LoadedDatabase, Vec<ReleaseCandidate>), String> {
+    let provider: Arc<dyn MetadataProvider> = match cached {
+        Some(provider) => provider,
+        None => Arc::new(LocalDatabase::load(&database).map_err(|e| e.to_string())?),
+    };
+    log::debug!("Searching {}", provider.name());
+    let candidates = provider.search(&query).map_err(|e| e.to_string())?;
+    Ok(((database, provider), candidates))
+}
+
+/// Async function to apply a release to the files of an album
+pub(super) async fn apply_candidate_async(files: Vec<PathBuf>, candidate: ReleaseCandidate, separator: ListSeparator) -> BatchSummary {
+    update_tags(&files, separator, |_, metadata| candidate.apply_to(metadata))
+}
+
+/// Build the metadata lookup view with the suggested releases
+pub(super) fn build_lookup_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, spacing, padding, .. } = Sizes::new(zoom);
+
+    let database_label = state
+        .lookup_database
+        .as_ref()
+        .map_or_else(|| "No database selected".to_string(), |path| display_name(path));
+    let idle = !state.lookup_busy;
+
+    let actions = row![
+        button("Choose Database...")
+            .on_press(Message::ChooseLookupDatabase)
+            .padding(8.0 * zoom),
+        text(database_label)
+            .size(text_size)
+            .width(Length::Fill),
+        button("Find Releases")
+            .on_press_maybe((idle && state.lookup_database.is_some()).then_some(Message::LookupMetadata))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let apply_label = if state.applies_to_folder() { "Apply to Album" } else { "Apply" };
+    let can_apply = idle && (!state.applies_to_folder() || state.can_write_files());
+    let mut candidates_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    for (index, candidate) in state.lookup_candidates.iter().enumerate() {
+        let mut details = Vec::new();
+        if let Some(year) = candidate.year {
+            details.push(year.to_string());
+        }
+        if let Some(ref label) = candidate.label {
+            details.push(label.clone());
+        }
+        details.push(format!("{} tracks", candidate.track_count()));
+        candidates_column = candidates_column.push(
+            row![
+                column![
+                    text(format!("{} – {}", candidate.artist, candidate.title)).size(text_size),
+                    text(details.join(" · ")).size(((12.0 * zoom) as u32).max(1)),
+                ]
+                .width(Length::Fill),
+                text(format!("{}%", candidate.score)).size(text_size),
+                button(apply_label)
+                    .on_press_maybe(can_apply.then_some(Message::ApplyCandidate(index)))
+                    .padding(5.0 * zoom),
+            ]
+            .spacing(spacing)
+            .align_y(Vertical::Center),
+        );
+    }
+
+    container(
+        column![
+            text("Metadata Lookup")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            actions,
+            candidates_column,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,96 @@
+use crate::config::AppTheme;
+use crate::id3_parser::lyrics::{format_timestamp, parse_lrc};
+use crate::id3_parser::{AudioMetadata, SyncedLyricLine};
+use iced::{Element, Length, alignment::Horizontal, widget::{Column, button, column, container, row, scrollable, text}};
+use std::path::PathBuf;
+use super::{Message, Sizes, create_row};
+
+/// Async function to open an LRC file dialog
+pub(super) async fn open_lrc_dialog() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("LRC Lyrics", &["lrc"])
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Async function to choose where to export an LRC file
+pub(super) async fn save_lrc_dialog(file_name: String) -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("LRC Lyrics", &["lrc"])
+        .set_file_name(file_name)
+        .save_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Async function to read and parse an LRC file
+pub(super) async fn import_lrc_async(path: PathBuf) -> Result<Vec<SyncedLyricLine>, String> {
+    std::fs::read_to_string(&path)
+        .map(|content| parse_lrc(&content))
+        .map_err(|e| e.to_string())
+}
+
+/// Async function to write an LRC file
+pub(super) async fn export_lrc_async(path: PathBuf, content: String) -> Result<PathBuf, String> {
+    std::fs::write(&path, content)
+        .map(|_| path)
+        .map_err(|e| e.to_string())
+}
+
+/// Build the synchronized lyrics (SYLT) timeline view
+pub(super) fn build_synced_lyrics_view(metadata: &AudioMetadata, zoom: f32, theme: AppTheme) -> Element<'_, Message> {
+    let Sizes { title: title_size, spacing, padding, .. } = Sizes::new(zoom);
+
+    let has_synced = !metadata.synced_lyrics.is_empty();
+    let has_unsynced = metadata.lyrics.as_ref().is_some_and(|lyrics| !lyrics.is_empty());
+
+    let actions = row![
+        button("Import LRC...")
+            .on_press(Message::ImportLrcDialog)
+            .padding(8.0 * zoom),
+        button("Export LRC...")
+            .on_press_maybe(has_synced.then_some(Message::ExportLrcDialog))
+            .padding(8.0 * zoom),
+        button("SYLT → USLT (LRC)")
+            .on_press_maybe(has_synced.then_some(Message::ConvertSyltToUslt))
+            .padding(8.0 * zoom),
+        button("USLT (LRC) → SYLT")
+            .on_press_maybe(has_unsynced.then_some(Message::ConvertUsltToSylt))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing);
+
+    let timeline: Element<'_, Message> = if has_synced {
+        let mut lines_column = Column::new()
+            .spacing(5.0 * zoom)
+            .width(Length::Fill);
+        for line in &metadata.synced_lyrics {
+            lines_column = lines_column.push(create_row(format_timestamp(line.time_ms), line.text.clone(), zoom, theme));
+        }
+        scrollable(lines_column)
+            .height(Length::Fixed(250.0 * zoom))
+            .width(Length::Fill)
+            .into()
+    } else {
+        text("No synchronized lyrics in this file.")
+            .size((14.0 * zoom) as u32)
+            .into()
+    };
+
+    container(
+        column![
+            text(format!("Synchronized Lyrics ({} lines)", metadata.synced_lyrics.len()))
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            actions,
+            timeline,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,3570 @@
+mod chapters;
+mod cleanup;
+mod comparison;
+mod credits;
+mod dates;
+mod duplicates;
+mod encoding;
+mod filename_pattern;
+mod fingerprint;
+mod genres;
+mod lookup;
+mod lyrics;
+mod numbering;
+mod paste;
+mod presets;
+mod rename;
+mod replay_gain;
+mod report;
+mod version;
+
+use crate::config::{
+    get_rename_log_path, load_config, load_recent_files, save_config, save_recent_files, AppTheme, RatingProfile,
+};
+use crate::id3_parser::chapters::{format_chapter_time, has_valid_times, next_element_id, parse_chapter_time};
+use crate::id3_parser::lyrics::{format_lrc, format_lrc_lines, parse_lrc};
+use crate::lookup::fingerprint::RecordingMatch;
+use crate::lookup::{LookupQuery, MetadataProvider, ReleaseCandidate};
+use crate::duplicates::{plan_duplicate_moves, DuplicateAction, DuplicateGroup, DuplicateOptions};
+use crate::report::{ReportFormat, ReportOptions, TagReport};
+use crate::rename::{sanitize_filename, MoveReport, PlannedRename, RenameStatus, UndoLog};
+use crate::id3_parser::cleanup::CleanupRules;
+use crate::id3_parser::encoding::{guess_encoding, repair, suspicious_fields, LegacyEncoding};
+use crate::id3_parser::filename::{apply_values, FilenamePattern};
+use crate::id3_parser::preset::{new_preset_name, PresetValue, TagPreset};
+use crate::id3_parser::snapshot::{format_tags_json, parse_tags_json, TagItem, TRACK_ITEMS};
+use crate::id3_parser::version::ConversionReport;
+use crate::id3_parser::validation::{new_issues, validate};
+use crate::id3_parser::fields::{
+    is_valid_uuid, musicbrainz_ids, sort_name, split_list, unwritable_changes, FieldChange, MetadataField,
+};
+use crate::id3_parser::{
+    parse_id3, write_id3, AudioMetadata, Chapter, Credit, ListSeparator, ParseError, Popularimeter,
+    ReplayGain, SyncedLyricLine, TagVersion,
+};
+use iced::{
+    Element, Length, Padding, Task, alignment::{Horizontal, Vertical}, widget::{
+        Column, Space, button, column, container, row, scrollable, text, text_input, image, pick_list, checkbox
+    }
+};
+use iced::widget::button as button_widget;
+use iced::widget::image::Handle;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use chapters::{build_chapters_view, open_chapters_dialog, save_chapters_dialog, import_chapters_async, export_chapters_async};
+use cleanup::{build_cleanup_view, apply_cleanup, preview_cleanup_async, apply_cleanup_async};
+use comparison::{Comparison, build_comparison_view, load_comparison_async};
+use credits::{CreditList, build_credits_view};
+use dates::build_dates_view;
+use duplicates::{build_duplicates_view, find_duplicate_tracks_async, resolve_duplicates_async};
+use encoding::{build_encoding_view, repair_encodings_async};
+use filename_pattern::{build_filename_pattern_view, apply_filename_pattern_async};
+use fingerprint::{
+    build_fingerprint_view, compute_fingerprint_async, identify_fingerprint_async, index_fingerprints_async, find_duplicates_async,
+};
+use genres::build_genre_view;
+use lookup::{build_lookup_view, open_lookup_database_dialog, lookup_async, apply_candidate_async};
+use lyrics::{build_synced_lyrics_view, open_lrc_dialog, save_lrc_dialog, import_lrc_async, export_lrc_async};
+use numbering::{NumberingInputs, build_numbering_view, apply_numbering_async};
+use paste::{build_paste_view, paste_tags_async};
+use presets::{build_preset_view, build_preset_editor, apply_preset_async, preview_preset_async};
+use rename::{build_rename_view, plan_renames_async, apply_renames_async, undo_rename_async};
+use replay_gain::{build_replay_gain_view, analyze_track_replay_gain_async, apply_album_replay_gain_async};
+use report::{build_report_view, build_report_async, save_report_dialog, export_report_async};
+use version::{build_version_view, preview_conversion_async, convert_files_async};
+
+/// File extensions of the audio formats the application opens
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "m4a", "aac", "ogg", "wav"];
+
+/// A lookup database loaded for searching, with the file it was read from
+type LoadedDatabase = (PathBuf, Arc<dyn MetadataProvider>);
+
+/// Application state
+#[derive(Debug, Clone)]
+pub struct State {
+    file_path: Option<PathBuf>,
+    metadata: Option<AudioMetadata>,
+    error: Option<String>,
+    status: Option<String>,     // Informational message (e.g. "Tags saved")
+    unsaved_changes: bool,      // Whether metadata was modified since it was loaded
+    chapter_time_inputs: Vec<(String, String)>, // Start/end text being edited for each chapter
+    field_inputs: HashMap<MetadataField, String>, // Field text that can't be stored yet (e.g. non-numeric, or a value to add to a list)
+    recent_files: Vec<PathBuf>, // Max 5 most recent files
+    folder_path: Option<PathBuf>, // Currently open folder (album)
+    folder_files: Vec<PathBuf>, // Audio files in the open folder, sorted by name
+    selected_files: Vec<PathBuf>, // Folder files selected for batch operations, in selection order
+    replay_gain_busy: bool,     // Whether a ReplayGain analysis is running
+    write_rva2: bool,           // Whether ReplayGain results are mirrored in RVA2 frames
+    lookup_database: Option<PathBuf>, // Local database used for metadata lookups
+    lookup_cache: Option<LoadedDatabase>, // Loaded lookup database, kept between searches
+    lookup_candidates: Vec<ReleaseCandidate>, // Releases suggested for the current file
+    lookup_busy: bool,          // Whether a lookup or apply is running
+    fingerprint_busy: bool,     // Whether fingerprints are being computed
+    fingerprint_matches: Vec<RecordingMatch>, // Recordings identified for the current file
+    duplicate_groups: Vec<Vec<PathBuf>>, // Folder files that share a recording
+    batch_busy: bool,           // Whether a batch tag operation is running
+    filename_pattern: String,   // Pattern used to guess tags from file names
+    filename_patterns: Vec<String>, // Saved filename pattern presets
+    rename_template: String,    // Template for the target paths of renamed files
+    rename_destination: Option<PathBuf>, // Folder renamed files move into (None = their own folder)
+    rename_plan: Vec<PlannedRename>, // Previewed renames of the target files
+    rename_undo_available: bool, // Whether the undo log has a rename to revert
+    numbering_inputs: NumberingInputs, // Options of the track numbering tool
+    cleanup_rules: CleanupRules, // Rules of the clean up tool
+    cleanup_exceptions: String, // Title Case exceptions, as typed (comma separated)
+    cleanup_fields: Vec<MetadataField>, // Fields the clean up tool changes
+    cleanup_preview: Vec<(PathBuf, Vec<FieldChange>)>, // Previewed changes of the target files
+    legacy_encoding: Option<LegacyEncoding>, // Code page chosen to repair mis-encoded text (None = guess)
+    conversion_preview: Option<(TagVersion, Vec<(PathBuf, ConversionReport)>)>, // Pending tag version conversion
+    genre_search: String,       // Text searched in the genre picker
+    favorite_genres: Vec<String>, // Genres listed first in the genre picker
+    report_open: bool,          // Whether the tag report is shown
+    report_options: ReportOptions, // Checks of the tag report
+    report: Option<TagReport>,  // Last tag report
+    report_busy: bool,          // Whether a folder is being scanned for the report
+    duplicates_open: bool,      // Whether the duplicate track finder is shown
+    duplicate_options: DuplicateOptions, // Criteria of the duplicate track finder
+    duplicate_folder: Option<PathBuf>, // Folder last searched for duplicate tracks
+    duplicate_tracks: Vec<DuplicateGroup>, // Likely duplicate tracks found in it
+    duplicate_actions: HashMap<PathBuf, DuplicateAction>, // What to do with each duplicate (default: keep)
+    duplicate_destination: Option<PathBuf>, // Folder duplicates marked "Move" go to
+    duplicates_busy: bool,      // Whether duplicates are being searched for or resolved
+    comparison: Option<Comparison>, // Two files whose tags are compared side by side
+    copied_tags: Option<(String, AudioMetadata)>, // Tags copied for pasting, and where they came from
+    paste_items: Vec<TagItem>,  // Items of the copied tags that are pasted
+    tag_presets: Vec<TagPreset>, // Named field values applied to tracks together
+    selected_preset: Option<usize>, // Preset being edited and applied
+    preset_preview: Result<Vec<(PathBuf, Vec<FieldChange>)>, String>, // Previewed changes of the selected preset
+    preset_preview_pending: usize, // Files whose preset preview is still being computed
+    theme: AppTheme,            // Dark or Light theme
+    zoom: f32,                  // Zoom level (1.0 = 100%)
+    settings_open: bool,        // Whether settings panel is visible
+    rating_profile: RatingProfile, // Player conventions for POPM ratings
+    legacy_separator: ListSeparator, // Separator of multiple values in ID3v2.3 tags
+}
+
+impl State {
+    pub fn new() -> Self {
+        log::info!("Initializing application state");
+        let config = load_config();
+        let recent_files = load_recent_files();
+        log::info!("Loaded {} recent files, theme: {:?}, zoom: {:.1}", 
+            recent_files.len(), config.theme, config.zoom);
+        Self {
+            file_path: None,
+            metadata: None,
+            error: None,
+            status: None,
+            unsaved_changes: false,
+            chapter_time_inputs: Vec::new(),
+            field_inputs: HashMap::new(),
+            recent_files,
+            folder_path: None,
+            folder_files: Vec::new(),
+            selected_files: Vec::new(),
+            replay_gain_busy: false,
+            write_rva2: false,
+            lookup_database: config.lookup_database.map(PathBuf::from),
+            lookup_cache: None,
+            lookup_candidates: Vec::new(),
+            lookup_busy: false,
+            fingerprint_busy: false,
+            fingerprint_matches: Vec::new(),
+            duplicate_groups: Vec::new(),
+            batch_busy: false,
+            filename_pattern: config.filename_patterns.first().cloned().unwrap_or_default(),
+            filename_patterns: config.filename_patterns,
+            rename_template: config.rename_template,
+            rename_destination: None,
+            rename_plan: Vec::new(),
+            numbering_inputs: NumberingInputs::default(),
+            cleanup_exceptions: config.cleanup_rules.case_exceptions.join(", "),
+            cleanup_rules: config.cleanup_rules,
+            cleanup_fields: vec![
+                MetadataField::Title,
+                MetadataField::Artist,
+                MetadataField::Album,
+                MetadataField::AlbumArtist,
+            ],
+            cleanup_preview: Vec::new(),
+            legacy_encoding: None,
+            conversion_preview: None,
+            genre_search: String::new(),
+            favorite_genres: config.favorite_genres,
+            report_open: false,
+            report_options: config.report_options,
+            report: None,
+            report_busy: false,
+            duplicates_open: false,
+            duplicate_options: config.duplicate_options,
+            duplicate_folder: None,
+            duplicate_tracks: Vec::new(),
+            duplicate_actions: HashMap::new(),
+            duplicate_destination: None,
+            duplicates_busy: false,
+            comparison: None,
+            copied_tags: None,
+            paste_items: Vec::new(),
+            selected_preset: (!config.tag_presets.is_empty()).then_some(0),
+            preset_preview: Ok(Vec::new()),
+            preset_preview_pending: 0,
+            tag_presets: config.tag_presets,
+            rename_undo_available: get_rename_log_path().is_some_and(|path| !UndoLog::load(path).is_empty()),
+            theme: config.theme,
+            zoom: config.zoom,
+            settings_open: false,
+            rating_profile: config.rating_profile,
+            legacy_separator: config.legacy_separator,
+        }
+    }
+
+    /// Get the current theme
+    pub fn theme(&self) -> iced::Theme {
+        self.theme.to_iced_theme()
+    }
+
+    /// Save current settings to disk
+    fn save_settings(&self) {
+        log::debug!("Saving settings: theme={:?}, zoom={:.1}", self.theme, self.zoom);
+        let mut config = load_config();
+        config.theme = self.theme;
+        config.zoom = self.zoom;
+        config.rating_profile = self.rating_profile;
+        config.lookup_database = self
+            .lookup_database
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string());
+        config.filename_patterns = self.filename_patterns.clone();
+        config.rename_template = self.rename_template.clone();
+        config.cleanup_rules = self.cleanup_rules.clone();
+        config.favorite_genres = self.favorite_genres.clone();
+        config.legacy_separator = self.legacy_separator;
+        config.report_options = self.report_options.clone();
+        config.duplicate_options = self.duplicate_options.clone();
+        config.tag_presets = self.tag_presets.clone();
+        save_config(&config);
+    }
+
+    /// Add a file to recent files list (max 5) and save to disk
+    fn add_to_recent_files(&mut self, path: PathBuf) {
+        log::debug!("Adding file to recent files: {:?}", path);
+        // Remove if already exists
+        self.recent_files.retain(|p| p != &path);
+        // Add to front
+        self.recent_files.insert(0, path);
+        // Keep only 5 most recent
+        if self.recent_files.len() > 5 {
+            log::debug!("Recent files list exceeded 5, truncating");
+            self.recent_files.truncate(5);
+        }
+        // Save to disk
+        save_recent_files(&self.recent_files);
+    }
+
+    /// Remove a file from recent files and save to disk
+    fn remove_from_recent_files(&mut self, path: &PathBuf) {
+        self.recent_files.retain(|p| p != path);
+        save_recent_files(&self.recent_files);
+    }
+
+    /// Follow moved files in the current file, selection and recent files
+    fn apply_moves(&mut self, moves: &[(PathBuf, PathBuf)]) {
+        let moved = |path: &PathBuf| moves.iter().find(|(from, _)| from == path).map(|(_, to)| to.clone());
+        if let Some(to) = self.file_path.as_ref().and_then(moved) {
+            self.file_path = Some(to);
+        }
+        self.selected_files = self
+            .selected_files
+            .iter()
+            .map(|path| moved(path).unwrap_or_else(|| path.clone()))
+            .collect();
+        self.cleanup_preview.clear();
+        self.refresh_preset_preview();
+        if self.recent_files.iter().any(|path| moved(path).is_some()) {
+            self.recent_files = self
+                .recent_files
+                .iter()
+                .map(|path| moved(path).unwrap_or_else(|| path.clone()))
+                .collect();
+            save_recent_files(&self.recent_files);
+        }
+    }
+
+    /// Make a file the current one while its tags are loaded
+    ///
+    /// Messages and results that belong to the previous file are cleared.
+    fn select_file(&mut self, path: PathBuf) {
+        self.file_path = Some(path);
+        self.error = None;
+        self.status = None;
+        self.fingerprint_matches.clear();
+        self.conversion_preview = None;
+    }
+
+    /// Apply a change to the loaded metadata and mark it as unsaved
+    fn modify_metadata(&mut self, change: impl FnOnce(&mut AudioMetadata)) {
+        if let Some(ref mut metadata) = self.metadata {
+            change(metadata);
+            self.unsaved_changes = true;
+            self.status = None;
+            self.sync_comparison();
+            self.refresh_preset_preview();
+        }
+    }
+
+    /// Show the tags of the loaded file, with its edits, on its side of the comparison
+    ///
+    /// That side mirrors the editor, so it never shows tags older than the editor's.
+    fn sync_comparison(&mut self) {
+        let (Some(comparison), Some(path), Some(metadata)) =
+            (self.comparison.as_mut(), self.file_path.as_ref(), self.metadata.as_ref())
+        else {
+            return;
+        };
+        for file in comparison.files.iter_mut().filter(|file| &file.path == path) {
+            file.metadata = metadata.clone();
+            file.unsaved_changes = self.unsaved_changes;
+        }
+    }
+
+    /// Reset the chapter time inputs from the loaded chapters
+    fn sync_chapter_inputs(&mut self) {
+        self.chapter_time_inputs = self
+            .metadata
+            .as_ref()
+            .map(|metadata| {
+                metadata
+                    .chapters
+                    .iter()
+                    .map(|chapter| (format_chapter_time(chapter.start_ms), format_chapter_time(chapter.end_ms)))
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    /// Sort the chapters by start time, keeping the time inputs with their chapter
+    fn sort_chapters(&mut self) {
+        let Some(ref mut metadata) = self.metadata else {
+            return;
+        };
+        if metadata.chapters.is_sorted_by_key(|chapter| chapter.start_ms) {
+            return;
+        }
+        let mut order: Vec<usize> = (0..metadata.chapters.len()).collect();
+        order.sort_by_key(|&index| metadata.chapters[index].start_ms);
+        metadata.chapters = order.iter().map(|&index| metadata.chapters[index].clone()).collect();
+        if self.chapter_time_inputs.len() == order.len() {
+            self.chapter_time_inputs = order.iter().map(|&index| self.chapter_time_inputs[index].clone()).collect();
+        }
+        self.sync_comparison();
+    }
+
+    /// Whether album operations should update the open folder rather than just the current file
+    fn applies_to_folder(&self) -> bool {
+        self.file_path
+            .as_ref()
+            .is_some_and(|path| self.folder_files.contains(path))
+    }
+
+    /// Files a batch operation works on: the selected folder files, or else the current file
+    fn target_files(&self) -> Vec<PathBuf> {
+        if self.selected_files.is_empty() {
+            return self.file_path.iter().cloned().collect();
+        }
+        self.folder_files
+            .iter()
+            .filter(|path| self.selected_files.contains(*path))
+            .cloned()
+            .collect()
+    }
+
+    /// Keep tags for pasting; everything they have is pasted except what belongs to one recording
+    fn set_copied_tags(&mut self, source: String, tags: AudioMetadata) {
+        self.paste_items = TagItem::all()
+            .filter(|item| item.is_set(&tags) && !TRACK_ITEMS.contains(item))
+            .collect();
+        self.copied_tags = Some((source, tags));
+    }
+
+    /// The preset being edited and applied
+    fn selected_preset(&self) -> Option<&TagPreset> {
+        self.selected_preset.and_then(|index| self.tag_presets.get(index))
+    }
+
+    /// Change the selected preset and save it
+    fn edit_preset(&mut self, change: impl FnOnce(&mut TagPreset)) {
+        if let Some(preset) = self.selected_preset.and_then(|index| self.tag_presets.get_mut(index)) {
+            change(preset);
+            self.refresh_preset_preview();
+            self.save_settings();
+        }
+    }
+
+    /// Recompute the preset preview after the preset, the targets or the loaded tags changed
+    ///
+    /// The loaded file is previewed as it's edited; a preview of other files is dropped, and
+    /// one still being computed is abandoned.
+    fn refresh_preset_preview(&mut self) {
+        if self.preset_preview_pending > 0 {
+            self.preset_preview_pending = 0;
+            self.batch_busy = false;
+        }
+        let targets = self.target_files();
+        self.preset_preview = match (self.selected_preset(), &self.metadata) {
+            (Some(preset), Some(metadata)) if self.targets_current_file_only(&targets) => preset
+                .changes(metadata, &targets[0])
+                .map(|changes| (!changes.is_empty()).then(|| (targets[0].clone(), changes)).into_iter().collect()),
+            _ => Ok(Vec::new()),
+        };
+    }
+
+    /// Whether a batch operation only touches the loaded file, so it can edit it in memory
+    fn targets_current_file_only(&self, targets: &[PathBuf]) -> bool {
+        targets.len() == 1 && self.file_path.as_ref() == targets.first()
+    }
+
+    /// Whether files other than the loaded one may be written on disk
+    ///
+    /// Writing them directly would drop unsaved edits of the loaded file, which may be one
+    /// of them, so those have to be saved or discarded first.
+    fn can_write_files(&self) -> bool {
+        !self.unsaved_changes
+    }
+
+    /// Duration of the loaded track in milliseconds, if its tag states it (TLEN)
+    fn duration_ms(&self) -> Option<u32> {
+        self.metadata.as_ref().and_then(|metadata| metadata.duration)
+    }
+}
+
+/// Messages that the application can handle
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum Message {
+    OpenFileDialog,
+    FileSelected(Option<PathBuf>),
+    FolderFileSelected(PathBuf),
+    SelectRecentFile(PathBuf),
+    OpenFolderDialog,
+    FolderSelected(Option<PathBuf>),
+    FolderScanned(Result<Vec<PathBuf>, String>),
+    MetadataParsed(Result<AudioMetadata, ParseError>),
+    ToggleSettings,
+    ThemeChanged(AppTheme),
+    ZoomIncrease,
+    ZoomDecrease,
+    RatingProfileChanged(RatingProfile),
+    LegacySeparatorChanged(ListSeparator),
+    RatingChanged(u8),
+    FieldChanged(MetadataField, String),
+    GenerateSortName(MetadataField),
+    OpenUrl(String),
+    UrlOpened(Result<(), String>),
+    SaveTags,
+    TagsSaved(Result<Vec<MetadataField>, ParseError>), // Changed fields the tag couldn't store
+    ImportLrcDialog,
+    LrcFileSelected(Option<PathBuf>),
+    LrcImported(Result<Vec<SyncedLyricLine>, String>),
+    ExportLrcDialog,
+    LrcExportPathSelected(Option<PathBuf>),
+    LrcExported(Result<PathBuf, String>),
+    ConvertSyltToUslt,
+    ConvertUsltToSylt,
+    AddChapter,
+    RemoveChapter(usize),
+    ChapterTitleChanged(usize, String),
+    ChapterStartChanged(usize, String),
+    ChapterEndChanged(usize, String),
+    ImportChaptersDialog,
+    ChaptersFileSelected(Option<PathBuf>),
+    ChaptersImported(Result<Vec<Chapter>, String>),
+    ExportChaptersDialog,
+    ChaptersExportPathSelected(Option<PathBuf>),
+    ChaptersExported(Result<PathBuf, String>),
+    AnalyzeTrackReplayGain,
+    TrackReplayGainAnalyzed(PathBuf, Result<ReplayGain, String>),
+    AnalyzeAlbumReplayGain,
+    AlbumReplayGainApplied(Result<(f64, BatchSummary), String>),
+    ToggleRva2(bool),
+    ChooseLookupDatabase,
+    LookupDatabaseSelected(Option<PathBuf>),
+    LookupMetadata,
+    LookupCompleted(Result<(LoadedDatabase, Vec<ReleaseCandidate>), String>),
+    ApplyCandidate(usize),
+    CandidateApplied(BatchSummary),
+    ComputeFingerprint,
+    FingerprintComputed(PathBuf, Result<String, String>),
+    IdentifyFingerprint,
+    FingerprintIdentified(Result<Vec<RecordingMatch>, String>),
+    ApplyRecordingMatch(usize),
+    AddToFingerprintIndex,
+    IndexFolderFingerprints,
+    FingerprintIndexUpdated(Result<(BatchSummary, usize), String>),
+    FindDuplicates,
+    DuplicatesFound(Result<Vec<Vec<PathBuf>>, String>),
+    ToggleFileSelected(PathBuf, bool),
+    SelectAllFiles(bool),
+    FilenamePatternChanged(String),
+    FilenamePresetSelected(String),
+    SaveFilenamePreset,
+    RemoveFilenamePreset,
+    ApplyFilenamePattern,
+    FilenamePatternApplied(BatchSummary),
+    RenameTemplateChanged(String),
+    ChooseRenameDestination,
+    RenameDestinationSelected(Option<PathBuf>),
+    ClearRenameDestination,
+    PreviewRenames,
+    RenamePreviewReady(Result<Vec<PlannedRename>, String>),
+    ApplyRenames,
+    RenamesApplied(Result<MoveReport, String>),
+    UndoRename,
+    NumberingStartChanged(String),
+    NumberingDiscChanged(String),
+    NumberingTotalDiscsChanged(String),
+    ApplyNumbering,
+    NumberingApplied(BatchSummary),
+    CleanupRulesChanged(CleanupRules),
+    CleanupExceptionsChanged(String),
+    ToggleCleanupField(MetadataField, bool),
+    PreviewCleanup,
+    CleanupPreviewReady(Result<Vec<(PathBuf, Vec<FieldChange>)>, String>),
+    ApplyCleanup,
+    CleanupApplied(BatchSummary),
+    LegacyEncodingSelected(LegacyEncoding),
+    RepairEncoding,
+    RepairSelectedEncodings,
+    EncodingsRepaired(BatchSummary),
+    PreviewConversion(TagVersion),
+    ConversionPreviewReady(TagVersion, Result<Vec<(PathBuf, ConversionReport)>, String>),
+    ApplyConversion,
+    CancelConversion,
+    ConversionApplied(BatchSummary),
+    ListInputChanged(MetadataField, String),
+    AddListValues(MetadataField),
+    RemoveListValue(MetadataField, usize),
+    AddCredit(CreditList),
+    RemoveCredit(CreditList, usize),
+    CreditRoleChanged(CreditList, usize, String),
+    CreditNameChanged(CreditList, usize, String),
+    GenreSearchChanged(String),
+    AddGenre(String),
+    RemoveGenre(String),
+    ToggleFavoriteGenre(String),
+    ToggleReport,
+    ToggleReportField(MetadataField, bool),
+    ReportOptionsChanged(ReportOptions),
+    ReportFolderDialog,
+    ReportFolderSelected(Option<PathBuf>),
+    ReportReady(Result<TagReport, String>),
+    ExportReportDialog(ReportFormat),
+    ReportExportPathSelected(ReportFormat, Option<PathBuf>),
+    ReportExported(Result<PathBuf, String>),
+    ToggleDuplicates,
+    DuplicateOptionsChanged(DuplicateOptions),
+    DuplicateFolderDialog,
+    DuplicateFolderSelected(Option<PathBuf>),
+    DuplicateTracksFound(Result<Vec<DuplicateGroup>, String>),
+    DuplicateActionChanged(PathBuf, DuplicateAction),
+    ChooseDuplicateDestination,
+    DuplicateDestinationSelected(Option<PathBuf>),
+    ApplyDuplicateActions,
+    DuplicateActionsApplied(Result<(MoveReport, Vec<PathBuf>), String>),
+    CompareSelectedFiles,
+    CompareWithFileDialog,
+    CompareFileSelected(Option<PathBuf>),
+    ComparisonLoaded(Result<Comparison, String>),
+    CopyComparedItem(TagItem, usize),
+    ToggleOnlyDifferences(bool),
+    SaveComparedFile(usize),
+    ComparedFileSaved(usize, Result<Vec<MetadataField>, ParseError>),
+    CloseComparison,
+    CopyTags,
+    CopyTagsToClipboard,
+    PasteFromClipboard,
+    ClipboardRead(Option<String>),
+    TogglePasteItem(TagItem, bool),
+    SelectPasteItems(bool),
+    PasteTags,
+    TagsPasted(BatchSummary),
+    DiscardCopiedTags,
+    PresetSelected(String),
+    NewPreset,
+    DeletePreset,
+    PresetNameChanged(String),
+    AddPresetValue,
+    RemovePresetValue(usize),
+    PresetFieldChanged(usize, MetadataField),
+    PresetValueChanged(usize, String),
+    PreviewPreset,
+    PresetFilePreviewed(PathBuf, Result<Vec<FieldChange>, String>),
+    ApplyPreset,
+    PresetApplied(BatchSummary),
+}
+
+/// Outcome of an operation writing tags to several files
+#[derive(Debug, Clone, Default)]
+pub struct BatchSummary {
+    written: usize,
+    failed: Vec<String>, // File names that couldn't be updated
+    undated: Vec<String>, // File names whose ID3v2.4-only dates weren't written to their ID3v2.3 tag
+}
+
+impl BatchSummary {
+    /// Error message listing the files that couldn't be updated, if any
+    fn failure_message(&self) -> Option<String> {
+        (!self.failed.is_empty()).then(|| format!("Could not tag: {}", self.failed.join(", ")))
+    }
+
+    /// Status text for the written files, noting dates their ID3v2.3 tags couldn't take
+    fn status(&self, done: String) -> String {
+        if self.undated.is_empty() {
+            done
+        } else {
+            format!(
+                "{}; release, encoding and tagging dates need ID3v2.4 and weren't written to: {}",
+                done,
+                self.undated.join(", ")
+            )
+        }
+    }
+}
+
+/// Status text after saving a file, noting dates its ID3v2.3 tag couldn't take
+fn saved_status(done: String, unwritten: &[MetadataField]) -> String {
+    if unwritten.is_empty() {
+        done
+    } else {
+        let fields: Vec<&str> = unwritten.iter().map(|field| field.label()).collect();
+        format!("{}; not written to the ID3v2.3 tag (convert it to ID3v2.4 first): {}", done, fields.join(", "))
+    }
+}
+
+/// Update function that handles messages and modifies state
+pub fn update(state: &mut State, message: Message) -> Task<Message> {
+    match message {
+        Message::OpenFileDialog => {
+            Task::perform(open_file_dialog(), Message::FileSelected)
+        }
+        Message::FileSelected(path) => {
+            if let Some(path) = path {
+                log::info!("File selected: {:?}", path);
+                state.add_to_recent_files(path.clone());
+                state.select_file(path.clone());
+                // Automatically parse when file is selected
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::FolderFileSelected(path) => {
+            // Files of the open folder are listed there, so they don't go into the recent files
+            log::info!("Folder file selected: {:?}", path);
+            state.select_file(path.clone());
+            Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed)
+        }
+        Message::SelectRecentFile(path) => {
+            if path.exists() {
+                log::info!("Recent file selected: {:?}", path);
+                state.add_to_recent_files(path.clone());
+                state.select_file(path.clone());
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            } else {
+                log::warn!("Recent file no longer exists: {:?}", path);
+                state.error = Some("File no longer exists".to_string());
+                // Remove from recent files if it doesn't exist
+                state.remove_from_recent_files(&path);
+            }
+            Task::none()
+        }
+        Message::OpenFolderDialog => {
+            Task::perform(open_folder_dialog(), Message::FolderSelected)
+        }
+        Message::FolderSelected(path) => {
+            if let Some(path) = path {
+                log::info!("Folder selected: {:?}", path);
+                state.folder_path = Some(path.clone());
+                return Task::perform(scan_folder_async(path), Message::FolderScanned);
+            }
+            Task::none()
+        }
+        Message::FolderScanned(result) => {
+            match result {
+                Ok(files) => {
+                    log::info!("Found {} audio files in folder", files.len());
+                    state.selected_files.retain(|path| files.contains(path));
+                    state.folder_files = files;
+                    state.duplicate_groups.clear();
+                    state.cleanup_preview.clear();
+                    state.refresh_preset_preview();
+                    state.error = None;
+                }
+                Err(e) => {
+                    log::error!("Failed to scan folder: {}", e);
+                    state.error = Some(format!("Failed to scan folder: {}", e));
+                    state.folder_path = None;
+                    state.folder_files.clear();
+                }
+            }
+            Task::none()
+        }
+        Message::MetadataParsed(result) => {
+            match result {
+                Ok(metadata) => {
+                    log::info!("Metadata parsed successfully. Title: {:?}, Artist: {:?}", 
+                        metadata.title, metadata.artists);
+                    state.metadata = Some(metadata);
+                    state.error = None;
+                    state.unsaved_changes = false;
+                    state.field_inputs.clear();
+                    state.sync_chapter_inputs();
+                    state.sync_comparison();
+                    state.refresh_preset_preview();
+                }
+                Err(ParseError::NoId3Tag) => {
+                    // Untagged files can still be tagged; a new tag is created on save
+                    log::info!("File has no ID3 tag, starting with empty metadata");
+                    state.metadata = Some(AudioMetadata::default());
+                    state.error = None;
+                    state.status = Some("No ID3 tag found, a new tag will be created on save".to_string());
+                    state.unsaved_changes = false;
+                    state.field_inputs.clear();
+                    state.sync_chapter_inputs();
+                    state.sync_comparison();
+                    state.refresh_preset_preview();
+                }
+                Err(e) => {
+                    log::error!("Failed to parse metadata: {}", e);
+                    state.error = Some(e.to_string());
+                    state.metadata = None;
+                    state.sync_chapter_inputs();
+                    state.refresh_preset_preview();
+                }
+            }
+            Task::none()
+        }
+        Message::ToggleSettings => {
+            state.settings_open = !state.settings_open;
+            Task::none()
+        }
+        Message::ToggleReport => {
+            state.report_open = !state.report_open;
+            Task::none()
+        }
+        Message::ToggleDuplicates => {
+            state.duplicates_open = !state.duplicates_open;
+            Task::none()
+        }
+        Message::DuplicateOptionsChanged(options) => {
+            state.duplicate_options = options;
+            state.save_settings();
+            Task::none()
+        }
+        Message::DuplicateFolderDialog => {
+            Task::perform(open_folder_dialog(), Message::DuplicateFolderSelected)
+        }
+        Message::DuplicateFolderSelected(folder) => {
+            if let Some(folder) = folder {
+                log::info!("Looking for duplicate tracks in: {:?}", folder);
+                state.duplicates_busy = true;
+                state.duplicate_tracks.clear();
+                state.duplicate_actions.clear();
+                state.status = Some(format!("Looking for duplicates in {}...", folder.to_string_lossy()));
+                state.duplicate_folder = Some(folder.clone());
+                return Task::perform(
+                    find_duplicate_tracks_async(folder, state.duplicate_options.clone(), state.legacy_separator),
+                    Message::DuplicateTracksFound,
+                );
+            }
+            Task::none()
+        }
+        Message::DuplicateTracksFound(result) => {
+            state.duplicates_busy = false;
+            match result {
+                Ok(groups) => {
+                    state.error = None;
+                    state.status = Some(format!("{} groups of duplicate tracks found", groups.len()));
+                    state.duplicate_tracks = groups;
+                }
+                Err(e) => {
+                    log::error!("Duplicate track search failed: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Duplicate track search failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::DuplicateActionChanged(path, action) => {
+            if action == DuplicateAction::Keep {
+                state.duplicate_actions.remove(&path);
+            } else {
+                state.duplicate_actions.insert(path, action);
+            }
+            Task::none()
+        }
+        Message::ChooseDuplicateDestination => {
+            Task::perform(open_folder_dialog(), Message::DuplicateDestinationSelected)
+        }
+        Message::DuplicateDestinationSelected(folder) => {
+            if folder.is_some() {
+                state.duplicate_destination = folder;
+            }
+            Task::none()
+        }
+        Message::ApplyDuplicateActions => {
+            let action = |path: &PathBuf| state.duplicate_actions.get(path).copied().unwrap_or_default();
+            if state
+                .duplicate_tracks
+                .iter()
+                .any(|group| group.files.iter().all(|file| action(&file.path) != DuplicateAction::Keep))
+            {
+                state.error = Some("Keep at least one file of each group".to_string());
+                return Task::none();
+            }
+            let marked = |wanted: DuplicateAction| -> Vec<PathBuf> {
+                state
+                    .duplicate_tracks
+                    .iter()
+                    .flat_map(|group| &group.files)
+                    .map(|file| file.path.clone())
+                    .filter(|path| action(path) == wanted)
+                    .collect()
+            };
+            let (to_move, to_trash) = (marked(DuplicateAction::Move), marked(DuplicateAction::Trash));
+            if state.unsaved_changes && state.file_path.as_ref().is_some_and(|path| to_trash.contains(path)) {
+                state.error = Some("Save the changes to the loaded file before trashing it".to_string());
+                return Task::none();
+            }
+            let moves = match (&state.duplicate_destination, to_move.is_empty()) {
+                (_, true) => Vec::new(),
+                (Some(destination), false) => plan_duplicate_moves(&to_move, destination),
+                (None, false) => {
+                    state.error = Some("Choose a folder to move the duplicates to".to_string());
+                    return Task::none();
+                }
+            };
+            if moves.is_empty() && to_trash.is_empty() {
+                return Task::none();
+            }
+            log::info!("Moving {} and trashing {} duplicate tracks", moves.len(), to_trash.len());
+            state.duplicates_busy = true;
+            Task::perform(resolve_duplicates_async(moves, to_trash), Message::DuplicateActionsApplied)
+        }
+        Message::DuplicateActionsApplied(result) => {
+            state.duplicates_busy = false;
+            match result {
+                Ok((report, trashed)) => {
+                    state.apply_moves(&report.moves);
+                    state.rename_undo_available =
+                        get_rename_log_path().is_some_and(|path| !UndoLog::load(path).is_empty());
+                    // Drop the files that are gone, and the groups that have no duplicates left
+                    let gone: Vec<&PathBuf> = report.moves.iter().map(|(from, _)| from).chain(&trashed).collect();
+                    for group in &mut state.duplicate_tracks {
+                        group.files.retain(|file| !gone.contains(&&file.path));
+                    }
+                    state.duplicate_tracks.retain(|group| group.files.len() > 1);
+                    state.duplicate_actions.retain(|path, _| !gone.contains(&path));
+                    if state.file_path.as_ref().is_some_and(|path| trashed.contains(path)) {
+                        state.file_path = None;
+                        state.metadata = None;
+                        state.unsaved_changes = false;
+                    }
+                    if state.recent_files.iter().any(|path| trashed.contains(path)) {
+                        state.recent_files.retain(|path| !trashed.contains(path));
+                        save_recent_files(&state.recent_files);
+                    }
+                    state.error =
+                        (!report.failed.is_empty()).then(|| format!("Could not remove: {}", report.failed.join(", ")));
+                    state.status = Some(format!("{} files moved, {} moved to the trash", report.moves.len(), trashed.len()));
+                    if let Some(folder) = state.folder_path.clone().filter(|folder| folder.is_dir()) {
+                        return Task::perform(scan_folder_async(folder), Message::FolderScanned);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Removing duplicates failed: {}", e);
+                    state.error = Some(format!("Removing duplicates failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::CompareSelectedFiles => {
+            if let [left, right] = state.selected_files.as_slice() {
+                log::info!("Comparing {:?} with {:?}", left, right);
+                return Task::perform(load_comparison_async(left.clone(), right.clone(), state.legacy_separator), Message::ComparisonLoaded);
+            }
+            Task::none()
+        }
+        Message::CompareWithFileDialog => {
+            Task::perform(open_file_dialog(), Message::CompareFileSelected)
+        }
+        Message::CompareFileSelected(path) => {
+            if let (Some(current), Some(path)) = (state.file_path.clone(), path) {
+                log::info!("Comparing {:?} with {:?}", current, path);
+                return Task::perform(load_comparison_async(current, path, state.legacy_separator), Message::ComparisonLoaded);
+            }
+            Task::none()
+        }
+        Message::ComparisonLoaded(result) => {
+            match result {
+                Ok(comparison) => {
+                    // The loaded file is compared with its edits, which are saved from either place
+                    state.error = None;
+                    state.comparison = Some(comparison);
+                    state.sync_comparison();
+                }
+                Err(e) => {
+                    log::error!("Failed to compare files: {}", e);
+                    state.error = Some(format!("Failed to compare files: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::CopyComparedItem(item, from) => {
+            let Some(comparison) = state.comparison.as_mut() else {
+                return Task::none();
+            };
+            let source = comparison.files[from].metadata.clone();
+            let target = &mut comparison.files[1 - from];
+            if state.file_path.as_ref() == Some(&target.path) {
+                // Copying to the loaded file edits it in the editor too
+                state.modify_metadata(|metadata| item.copy(&source, metadata));
+            } else {
+                item.copy(&source, &mut target.metadata);
+                target.unsaved_changes = true;
+            }
+            Task::none()
+        }
+        Message::ToggleOnlyDifferences(only_differences) => {
+            if let Some(comparison) = state.comparison.as_mut() {
+                comparison.only_differences = only_differences;
+            }
+            Task::none()
+        }
+        Message::SaveComparedFile(index) => {
+            if let Some(file) = state.comparison.as_ref().map(|comparison| &comparison.files[index]) {
+                let issues = validate(&file.metadata);
+                if !issues.is_empty() {
+                    log::warn!("Not saving {:?}: {} invalid field(s)", file.path, issues.len());
+                    let fields: Vec<&str> = issues.iter().map(|issue| issue.field.label()).collect();
+                    state.error = Some(format!("Fix the invalid fields before saving: {}", fields.join(", ")));
+                    return Task::none();
+                }
+                log::info!("Saving compared tags to: {:?}", file.path);
+                return Task::perform(save_file_async(file.path.clone(), file.metadata.clone(), state.legacy_separator), move |result| {
+                    Message::ComparedFileSaved(index, result)
+                });
+            }
+            Task::none()
+        }
+        Message::ComparedFileSaved(index, result) => {
+            let Some(file) = state.comparison.as_mut().map(|comparison| &mut comparison.files[index]) else {
+                return Task::none();
+            };
+            match result {
+                Ok(unwritten) => {
+                    file.unsaved_changes = false;
+                    state.error = None;
+                    state.status = Some(saved_status(format!("Tags saved to {}", display_name(&file.path)), &unwritten));
+                    // Show the saved tags if the file is also the loaded one
+                    if state.file_path.as_ref() == Some(&file.path) {
+                        return Task::perform(parse_file_async(file.path.clone(), state.legacy_separator), Message::MetadataParsed);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to save tags: {}", e);
+                    state.error = Some(format!("Failed to save tags: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::CloseComparison => {
+            state.comparison = None;
+            Task::none()
+        }
+        Message::CopyTags => {
+            if let (Some(path), Some(metadata)) = (state.file_path.clone(), state.metadata.clone()) {
+                state.set_copied_tags(display_name(&path), metadata);
+                state.status = Some("Tags copied".to_string());
+            }
+            Task::none()
+        }
+        Message::CopyTagsToClipboard => {
+            // The clipboard copy lets the tags be pasted in another session or program
+            if let Some(metadata) = state.metadata.as_ref() {
+                match format_tags_json(metadata) {
+                    Ok(json) => {
+                        state.status = Some("Tags copied to the clipboard (without the cover art)".to_string());
+                        return iced::clipboard::write(json);
+                    }
+                    Err(e) => {
+                        log::warn!("Could not copy tags to the clipboard: {}", e);
+                        state.error = Some(format!("Could not copy tags to the clipboard: {}", e));
+                    }
+                }
+            }
+            Task::none()
+        }
+        Message::PasteFromClipboard => {
+            iced::clipboard::read().map(Message::ClipboardRead)
+        }
+        Message::ClipboardRead(content) => {
+            match content.as_deref().map(parse_tags_json) {
+                Some(Ok(tags)) => {
+                    state.set_copied_tags("the clipboard".to_string(), tags);
+                    state.error = None;
+                }
+                Some(Err(e)) => {
+                    log::warn!("Clipboard does not hold copied tags: {}", e);
+                    state.error = Some(format!("The clipboard does not hold copied tags: {}", e));
+                }
+                None => state.error = Some("The clipboard is empty".to_string()),
+            }
+            Task::none()
+        }
+        Message::TogglePasteItem(item, enabled) => {
+            state.paste_items.retain(|pasted| *pasted != item);
+            if enabled {
+                state.paste_items.push(item);
+            }
+            Task::none()
+        }
+        Message::SelectPasteItems(all) => {
+            state.paste_items = match (&state.copied_tags, all) {
+                (Some((_, tags)), true) => TagItem::all().filter(|item| item.is_set(tags)).collect(),
+                _ => Vec::new(),
+            };
+            Task::none()
+        }
+        Message::PasteTags => {
+            let Some((_, tags)) = state.copied_tags.clone() else {
+                return Task::none();
+            };
+            let items = state.paste_items.clone();
+            let targets = state.target_files();
+            if state.targets_current_file_only(&targets) {
+                state.modify_metadata(|metadata| {
+                    for item in &items {
+                        item.copy(&tags, metadata);
+                    }
+                });
+                state.error = None;
+                return Task::none();
+            }
+            if !targets.is_empty() && !items.is_empty() {
+                log::info!("Pasting {} items to {} files", items.len(), targets.len());
+                state.batch_busy = true;
+                return Task::perform(paste_tags_async(targets, tags, items, state.legacy_separator), Message::TagsPasted);
+            }
+            Task::none()
+        }
+        Message::TagsPasted(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(summary.status(format!("Tags pasted to {} files", summary.written)));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::DiscardCopiedTags => {
+            state.copied_tags = None;
+            state.paste_items.clear();
+            Task::none()
+        }
+        Message::PresetSelected(name) => {
+            state.selected_preset = state.tag_presets.iter().position(|preset| preset.name == name);
+            state.refresh_preset_preview();
+            Task::none()
+        }
+        Message::NewPreset => {
+            state.tag_presets.push(TagPreset::new(new_preset_name(&state.tag_presets)));
+            state.selected_preset = Some(state.tag_presets.len() - 1);
+            state.refresh_preset_preview();
+            state.save_settings();
+            Task::none()
+        }
+        Message::DeletePreset => {
+            if let Some(index) = state.selected_preset.filter(|index| *index < state.tag_presets.len()) {
+                state.tag_presets.remove(index);
+                state.selected_preset = (!state.tag_presets.is_empty()).then(|| index.saturating_sub(1));
+                state.refresh_preset_preview();
+                state.save_settings();
+            }
+            Task::none()
+        }
+        Message::PresetNameChanged(name) => {
+            state.edit_preset(|preset| preset.name = name);
+            Task::none()
+        }
+        Message::AddPresetValue => {
+            state.edit_preset(|preset| {
+                // Start with the first field the preset doesn't have yet
+                let used: Vec<MetadataField> = preset.fields().map(|(field, _)| field).collect();
+                let field = MetadataField::ALL
+                    .into_iter()
+                    .find(|field| !used.contains(field))
+                    .unwrap_or(MetadataField::Comment);
+                preset.values.push(PresetValue {
+                    field: field.label().to_string(),
+                    value: String::new(),
+                });
+            });
+            Task::none()
+        }
+        Message::RemovePresetValue(index) => {
+            state.edit_preset(|preset| {
+                if index < preset.values.len() {
+                    preset.values.remove(index);
+                }
+            });
+            Task::none()
+        }
+        Message::PresetFieldChanged(index, field) => {
+            state.edit_preset(|preset| {
+                if let Some(value) = preset.values.get_mut(index) {
+                    value.field = field.label().to_string();
+                }
+            });
+            Task::none()
+        }
+        Message::PresetValueChanged(index, text) => {
+            state.edit_preset(|preset| {
+                if let Some(value) = preset.values.get_mut(index) {
+                    value.value = text;
+                }
+            });
+            Task::none()
+        }
+        Message::PreviewPreset => {
+            let targets = state.target_files();
+            if let Some(preset) = state.selected_preset().cloned().filter(|_| !targets.is_empty()) {
+                // Each file is read in its own task, and the preview fills in as they finish
+                state.batch_busy = true;
+                state.preset_preview = Ok(Vec::new());
+                state.preset_preview_pending = targets.len();
+                let separator = state.legacy_separator;
+                return Task::batch(targets.into_iter().map(|path| {
+                    let task_path = path.clone();
+                    Task::perform(preview_preset_async(path, preset.clone(), separator), move |result| {
+                        Message::PresetFilePreviewed(task_path.clone(), result)
+                    })
+                }));
+            }
+            Task::none()
+        }
+        Message::PresetFilePreviewed(path, result) => {
+            // Results of a preview that was abandoned or failed are dropped
+            if state.preset_preview_pending == 0 {
+                return Task::none();
+            }
+            state.preset_preview_pending -= 1;
+            match result {
+                Ok(changes) => {
+                    if let (Ok(preview), false) = (state.preset_preview.as_mut(), changes.is_empty()) {
+                        preview.push((path, changes));
+                        preview.sort_by(|a, b| a.0.cmp(&b.0));
+                    }
+                }
+                Err(e) => {
+                    state.preset_preview = Err(format!("{}: {}", display_name(&path), e));
+                    state.preset_preview_pending = 0;
+                }
+            }
+            if state.preset_preview_pending == 0 {
+                state.batch_busy = false;
+            }
+            Task::none()
+        }
+        Message::ApplyPreset => {
+            let Some(preset) = state.selected_preset().cloned() else {
+                return Task::none();
+            };
+            let targets = state.target_files();
+            if state.targets_current_file_only(&targets) {
+                let Some(mut metadata) = state.metadata.clone() else {
+                    return Task::none();
+                };
+                match preset.apply(&mut metadata, &targets[0]) {
+                    Ok(()) => {
+                        state.modify_metadata(|current| *current = metadata);
+                        state.error = None;
+                    }
+                    Err(e) => state.error = Some(format!("Could not apply the preset: {}", e)),
+                }
+                return Task::none();
+            }
+            if !targets.is_empty() {
+                log::info!("Applying preset {:?} to {} files", preset.name, targets.len());
+                state.batch_busy = true;
+                return Task::perform(apply_preset_async(targets, preset, state.legacy_separator), Message::PresetApplied);
+            }
+            Task::none()
+        }
+        Message::PresetApplied(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.refresh_preset_preview();
+            state.status = Some(summary.status(format!("Preset applied to {} files", summary.written)));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::ToggleReportField(field, required) => {
+            state.report_options.set_required(field, required);
+            state.save_settings();
+            Task::none()
+        }
+        Message::ReportOptionsChanged(options) => {
+            state.report_options = options;
+            state.save_settings();
+            Task::none()
+        }
+        Message::ReportFolderDialog => {
+            Task::perform(open_folder_dialog(), Message::ReportFolderSelected)
+        }
+        Message::ReportFolderSelected(folder) => {
+            if let Some(folder) = folder {
+                log::info!("Building tag report for: {:?}", folder);
+                state.report_busy = true;
+                state.status = Some(format!("Scanning {}...", folder.to_string_lossy()));
+                return Task::perform(
+                    build_report_async(folder, state.report_options.clone(), state.legacy_separator),
+                    Message::ReportReady,
+                );
+            }
+            Task::none()
+        }
+        Message::ReportReady(result) => {
+            state.report_busy = false;
+            match result {
+                Ok(report) => {
+                    state.error = None;
+                    state.status = Some(report.summary());
+                    state.report = Some(report);
+                }
+                Err(e) => {
+                    log::error!("Tag report failed: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Tag report failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ExportReportDialog(format) => {
+            if let Some(ref report) = state.report {
+                let file_name = format!("{} tag report.{}", display_name(&report.folder), format.extension());
+                return Task::perform(save_report_dialog(format, file_name), move |path| {
+                    Message::ReportExportPathSelected(format, path)
+                });
+            }
+            Task::none()
+        }
+        Message::ReportExportPathSelected(format, path) => {
+            if let (Some(path), Some(report)) = (path, state.report.as_ref()) {
+                log::info!("Exporting {} tag report: {:?}", format, path);
+                return Task::perform(export_report_async(path, format.render(report)), Message::ReportExported);
+            }
+            Task::none()
+        }
+        Message::ReportExported(result) => {
+            match result {
+                Ok(path) => {
+                    state.error = None;
+                    state.status = Some(format!("Report exported to {}", path.to_string_lossy()));
+                }
+                Err(e) => {
+                    log::error!("Failed to export tag report: {}", e);
+                    state.error = Some(format!("Failed to export tag report: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ThemeChanged(theme) => {
+            log::debug!("Theme changed to: {:?}", theme);
+            state.theme = theme;
+            state.save_settings();
+            Task::none()
+        }
+        Message::ZoomIncrease => {
+            let new_zoom = (state.zoom + 0.1).min(2.0);
+            log::debug!("Zoom increased from {:.1} to {:.1}", state.zoom, new_zoom);
+            state.zoom = new_zoom;
+            state.save_settings();
+            Task::none()
+        }
+        Message::ZoomDecrease => {
+            let new_zoom = (state.zoom - 0.1).max(0.5);
+            log::debug!("Zoom decreased from {:.1} to {:.1}", state.zoom, new_zoom);
+            state.zoom = new_zoom;
+            state.save_settings();
+            Task::none()
+        }
+        Message::RatingProfileChanged(profile) => {
+            log::debug!("Rating profile changed to: {:?}", profile);
+            state.rating_profile = profile;
+            state.save_settings();
+            Task::none()
+        }
+        Message::LegacySeparatorChanged(separator) => {
+            log::debug!("ID3v2.3 list separator changed to: {:?}", separator);
+            state.legacy_separator = separator;
+            state.save_settings();
+            // Values of ID3v2.3 tags are split differently now
+            if let (Some(path), false) = (state.file_path.clone(), state.unsaved_changes) {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::RatingChanged(stars) => {
+            let profile = state.rating_profile;
+            let rating = profile.rating_for_stars(stars);
+            log::debug!("Rating changed to {} stars (POPM {} for {:?})", stars, rating, profile.email());
+            state.modify_metadata(|metadata| {
+                match metadata.popularimeters.iter_mut().find(|popm| popm.email == profile.email()) {
+                    Some(popm) => popm.rating = rating,
+                    None => metadata.popularimeters.push(Popularimeter {
+                        email: profile.email().to_string(),
+                        rating,
+                        counter: 0,
+                    }),
+                }
+            });
+            Task::none()
+        }
+        Message::FieldChanged(field, value) => {
+            if field.accepts(&value) {
+                state.field_inputs.remove(&field);
+                state.modify_metadata(|metadata| field.set_value(metadata, &value));
+            } else {
+                // Keep the text so it can be corrected, without touching the metadata
+                state.field_inputs.insert(field, value);
+            }
+            Task::none()
+        }
+        Message::GenerateSortName(field) => {
+            if let Some(source) = field.sort_source() {
+                state.field_inputs.remove(&field);
+                state.modify_metadata(|metadata| {
+                    if let Some(value) = source.value(metadata) {
+                        field.set_value(metadata, &sort_name(&value));
+                    }
+                });
+            }
+            Task::none()
+        }
+        Message::OpenUrl(url) => {
+            log::info!("Opening URL: {}", url);
+            Task::perform(open_url_async(url), Message::UrlOpened)
+        }
+        Message::UrlOpened(result) => {
+            if let Err(e) = result {
+                log::error!("{}", e);
+                state.error = Some(e);
+            }
+            Task::none()
+        }
+        Message::SaveTags => {
+            // Retimed chapters keep their row while being edited and are put in order on save
+            state.sort_chapters();
+            if let (Some(path), Some(metadata)) = (state.file_path.clone(), state.metadata.clone()) {
+                let issues = validate(&metadata);
+                if !issues.is_empty() {
+                    log::warn!("Not saving {:?}: {} invalid field(s)", path, issues.len());
+                    let fields: Vec<&str> = issues.iter().map(|issue| issue.field.label()).collect();
+                    state.error = Some(format!("Fix the invalid fields before saving: {}", fields.join(", ")));
+                    return Task::none();
+                }
+                if let Some(index) = metadata.chapters.iter().position(|chapter| !has_valid_times(chapter)) {
+                    log::warn!("Not saving {:?}: chapter {} doesn't end after it starts", path, index + 1);
+                    state.error = Some(format!("Chapter {} must end after it starts", index + 1));
+                    return Task::none();
+                }
+                log::info!("Saving tags to: {:?}", path);
+                return Task::perform(save_file_async(path, metadata, state.legacy_separator), Message::TagsSaved);
+            }
+            Task::none()
+        }
+        Message::TagsSaved(result) => {
+            match result {
+                Ok(unwritten) => {
+                    state.unsaved_changes = false;
+                    state.error = None;
+                    state.status = Some(saved_status("Tags saved".to_string(), &unwritten));
+                    state.sync_comparison();
+                }
+                Err(e) => {
+                    log::error!("Failed to save tags: {}", e);
+                    state.error = Some(format!("Failed to save tags: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ImportLrcDialog => {
+            Task::perform(open_lrc_dialog(), Message::LrcFileSelected)
+        }
+        Message::LrcFileSelected(path) => {
+            if let Some(path) = path {
+                log::info!("Importing LRC file: {:?}", path);
+                return Task::perform(import_lrc_async(path), Message::LrcImported);
+            }
+            Task::none()
+        }
+        Message::LrcImported(result) => {
+            match result {
+                Ok(lines) if lines.is_empty() => {
+                    state.error = Some("The LRC file contains no timed lyrics".to_string());
+                }
+                Ok(lines) => {
+                    log::info!("Imported {} synchronized lyric lines", lines.len());
+                    state.error = None;
+                    state.modify_metadata(|metadata| metadata.synced_lyrics = lines);
+                }
+                Err(e) => {
+                    log::error!("Failed to import LRC file: {}", e);
+                    state.error = Some(format!("Failed to import LRC file: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ExportLrcDialog => {
+            if let Some(ref metadata) = state.metadata {
+                let file_name = state
+                    .file_path
+                    .as_ref()
+                    .and_then(|path| path.file_stem())
+                    .map(|stem| format!("{}.lrc", stem.to_string_lossy()))
+                    .unwrap_or_else(|| format!("{}.lrc", sanitize_filename(metadata.title.as_deref().unwrap_or("lyrics"))));
+                return Task::perform(save_lrc_dialog(file_name), Message::LrcExportPathSelected);
+            }
+            Task::none()
+        }
+        Message::LrcExportPathSelected(path) => {
+            if let (Some(path), Some(metadata)) = (path, state.metadata.as_ref()) {
+                log::info!("Exporting LRC file: {:?}", path);
+                return Task::perform(export_lrc_async(path, format_lrc(metadata)), Message::LrcExported);
+            }
+            Task::none()
+        }
+        Message::LrcExported(result) => {
+            match result {
+                Ok(path) => {
+                    state.error = None;
+                    state.status = Some(format!("Lyrics exported to {}", path.to_string_lossy()));
+                }
+                Err(e) => {
+                    log::error!("Failed to export LRC file: {}", e);
+                    state.error = Some(format!("Failed to export LRC file: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ConvertSyltToUslt => {
+            state.modify_metadata(|metadata| {
+                metadata.lyrics = Some(format_lrc_lines(&metadata.synced_lyrics));
+            });
+            Task::none()
+        }
+        Message::ConvertUsltToSylt => {
+            let lines = state
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.lyrics.as_deref())
+                .map(parse_lrc)
+                .unwrap_or_default();
+            if lines.is_empty() {
+                state.error = Some("The unsynchronized lyrics contain no LRC timestamps".to_string());
+            } else {
+                state.error = None;
+                state.modify_metadata(|metadata| metadata.synced_lyrics = lines);
+            }
+            Task::none()
+        }
+        Message::AddChapter => {
+            let duration_ms = state.duration_ms();
+            state.modify_metadata(|metadata| {
+                // New chapters start where the last one ends
+                let start_ms = metadata.chapters.last().map_or(0, |chapter| chapter.end_ms);
+                let end_ms = duration_ms
+                    .filter(|&duration| duration > start_ms)
+                    .unwrap_or(start_ms + 60_000);
+                let chapter = Chapter {
+                    element_id: next_element_id(&metadata.chapters),
+                    start_ms,
+                    end_ms,
+                    title: Some(format!("Chapter {}", metadata.chapters.len() + 1)),
+                    url: None,
+                    image: None,
+                    image_format: None,
+                };
+                metadata.chapters.push(chapter);
+            });
+            state.sync_chapter_inputs();
+            Task::none()
+        }
+        Message::RemoveChapter(index) => {
+            state.modify_metadata(|metadata| {
+                if index < metadata.chapters.len() {
+                    metadata.chapters.remove(index);
+                }
+            });
+            state.sync_chapter_inputs();
+            Task::none()
+        }
+        Message::ChapterTitleChanged(index, title) => {
+            state.modify_metadata(|metadata| {
+                if let Some(chapter) = metadata.chapters.get_mut(index) {
+                    chapter.title = (!title.is_empty()).then_some(title);
+                }
+            });
+            Task::none()
+        }
+        Message::ChapterStartChanged(index, value) => {
+            if let Some(start_ms) = parse_chapter_time(&value) {
+                state.modify_metadata(|metadata| {
+                    if let Some(chapter) = metadata.chapters.get_mut(index) {
+                        chapter.start_ms = start_ms;
+                    }
+                });
+            }
+            if let Some(inputs) = state.chapter_time_inputs.get_mut(index) {
+                inputs.0 = value;
+            }
+            Task::none()
+        }
+        Message::ChapterEndChanged(index, value) => {
+            if let Some(end_ms) = parse_chapter_time(&value) {
+                state.modify_metadata(|metadata| {
+                    if let Some(chapter) = metadata.chapters.get_mut(index) {
+                        chapter.end_ms = end_ms;
+                    }
+                });
+            }
+            if let Some(inputs) = state.chapter_time_inputs.get_mut(index) {
+                inputs.1 = value;
+            }
+            Task::none()
+        }
+        Message::ImportChaptersDialog => {
+            Task::perform(open_chapters_dialog(), Message::ChaptersFileSelected)
+        }
+        Message::ChaptersFileSelected(path) => {
+            if let Some(path) = path {
+                log::info!("Importing chapters from: {:?}", path);
+                return Task::perform(
+                    import_chapters_async(path, state.file_path.clone(), state.duration_ms()),
+                    Message::ChaptersImported,
+                );
+            }
+            Task::none()
+        }
+        Message::ChaptersImported(result) => {
+            match result {
+                Ok(chapters) if chapters.is_empty() => {
+                    state.error = Some("The chapter file contains no chapters".to_string());
+                }
+                Ok(chapters) => {
+                    log::info!("Imported {} chapters", chapters.len());
+                    state.error = None;
+                    let open_ended = chapters.last().is_some_and(|chapter| !has_valid_times(chapter));
+                    state.modify_metadata(|metadata| metadata.chapters = chapters);
+                    state.sync_chapter_inputs();
+                    if open_ended {
+                        // Neither the tag nor the audio file states the track length
+                        state.status = Some("Set the end time of the last chapter before saving".to_string());
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to import chapters: {}", e);
+                    state.error = Some(format!("Failed to import chapters: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ExportChaptersDialog => {
+            let file_name = state
+                .file_path
+                .as_ref()
+                .and_then(|path| path.file_stem())
+                .map(|stem| format!("{}.chapters.txt", stem.to_string_lossy()))
+                .unwrap_or_else(|| "chapters.txt".to_string());
+            Task::perform(save_chapters_dialog(file_name), Message::ChaptersExportPathSelected)
+        }
+        Message::ChaptersExportPathSelected(path) => {
+            if let (Some(path), Some(metadata)) = (path, state.metadata.as_ref()) {
+                log::info!("Exporting chapters to: {:?}", path);
+                return Task::perform(export_chapters_async(path, metadata.chapters.clone()), Message::ChaptersExported);
+            }
+            Task::none()
+        }
+        Message::AnalyzeTrackReplayGain => {
+            if let Some(path) = state.file_path.clone() {
+                log::info!("Analyzing track loudness: {:?}", path);
+                state.replay_gain_busy = true;
+                state.status = Some("Analyzing track loudness...".to_string());
+                let existing = state
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.replay_gain.clone())
+                    .unwrap_or_default();
+                return Task::perform(
+                    analyze_track_replay_gain_async(path.clone(), existing, state.write_rva2),
+                    move |result| Message::TrackReplayGainAnalyzed(path, result),
+                );
+            }
+            Task::none()
+        }
+        Message::TrackReplayGainAnalyzed(path, result) => {
+            state.replay_gain_busy = false;
+            // Another file may have been opened during the analysis
+            if state.file_path.as_ref() != Some(&path) {
+                log::info!("Discarding the track loudness of {:?}, it is no longer open", path);
+                state.status = None;
+                return Task::none();
+            }
+            match result {
+                Ok(replay_gain) => {
+                    state.error = None;
+                    state.modify_metadata(|metadata| metadata.replay_gain = replay_gain);
+                    state.status = Some("Track ReplayGain computed, save to write the values".to_string());
+                }
+                Err(e) => {
+                    log::error!("Track loudness analysis failed: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Track loudness analysis failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::AnalyzeAlbumReplayGain => {
+            if !state.folder_files.is_empty() {
+                log::info!("Analyzing album loudness for {} files", state.folder_files.len());
+                state.replay_gain_busy = true;
+                state.status = Some(format!("Analyzing {} files...", state.folder_files.len()));
+                return Task::perform(
+                    apply_album_replay_gain_async(state.folder_files.clone(), state.write_rva2, state.legacy_separator),
+                    Message::AlbumReplayGainApplied,
+                );
+            }
+            Task::none()
+        }
+        Message::AlbumReplayGainApplied(result) => {
+            state.replay_gain_busy = false;
+            match result {
+                Ok((album_gain, summary)) => {
+                    state.error = summary.failure_message();
+                    state.status = Some(format!(
+                        "Album gain {:+.2} dB written to {} files",
+                        album_gain, summary.written
+                    ));
+                    // Reload the current file to show the written values
+                    if let Some(path) = state.file_path.clone() {
+                        return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Album loudness analysis failed: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Album loudness analysis failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ToggleRva2(enabled) => {
+            state.write_rva2 = enabled;
+            Task::none()
+        }
+        Message::ChooseLookupDatabase => {
+            Task::perform(open_lookup_database_dialog(), Message::LookupDatabaseSelected)
+        }
+        Message::LookupDatabaseSelected(path) => {
+            if let Some(path) = path {
+                log::info!("Lookup database selected: {:?}", path);
+                state.lookup_database = Some(path);
+                state.lookup_cache = None;
+                state.lookup_candidates.clear();
+                state.save_settings();
+            }
+            Task::none()
+        }
+        Message::LookupMetadata => {
+            if let (Some(database), Some(metadata)) = (state.lookup_database.clone(), state.metadata.as_ref()) {
+                let query = LookupQuery::from_metadata(metadata);
+                if query.is_empty() {
+                    state.error = Some("Fill in artist, album, title or ISRC to look up".to_string());
+                    return Task::none();
+                }
+                log::info!("Looking up metadata: {:?}", query);
+                state.lookup_busy = true;
+                state.lookup_candidates.clear();
+                let cached = state
+                    .lookup_cache
+                    .as_ref()
+                    .filter(|(path, _)| path == &database)
+                    .map(|(_, provider)| provider.clone());
+                return Task::perform(lookup_async(database, cached, query), Message::LookupCompleted);
+            }
+            Task::none()
+        }
+        Message::LookupCompleted(result) => {
+            state.lookup_busy = false;
+            match result {
+                Ok(((database, provider), candidates)) => {
+                    // Keep the database for the next search unless another one was chosen meanwhile
+                    if state.lookup_database.as_ref() == Some(&database) {
+                        state.lookup_cache = Some((database, provider));
+                    }
+                    state.error = None;
+                    state.status = Some(format!("{} matching releases found", candidates.len()));
+                    state.lookup_candidates = candidates;
+                }
+                Err(e) => {
+                    log::error!("Metadata lookup failed: {}", e);
+                    state.error = Some(format!("Metadata lookup failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ApplyCandidate(index) => {
+            let Some(candidate) = state.lookup_candidates.get(index).cloned() else {
+                return Task::none();
+            };
+            log::info!("Applying release {} ({})", candidate.title, candidate.release_id);
+            if state.applies_to_folder() {
+                if !state.can_write_files() {
+                    state.error = Some("Save or discard the changes of the current file first".to_string());
+                    return Task::none();
+                }
+                // Tag every file of the album on disk
+                state.lookup_busy = true;
+                return Task::perform(
+                    apply_candidate_async(state.folder_files.clone(), candidate, state.legacy_separator),
+                    Message::CandidateApplied,
+                );
+            }
+            let mut applied = false;
+            state.modify_metadata(|metadata| applied = candidate.apply_to(metadata));
+            if !applied {
+                state.error = Some("No track of the release matches this file".to_string());
+            }
+            Task::none()
+        }
+        Message::CandidateApplied(summary) => {
+            state.lookup_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(format!("Release applied to {} files", summary.written));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::ComputeFingerprint => {
+            if let Some(path) = state.file_path.clone() {
+                log::info!("Computing fingerprint: {:?}", path);
+                state.fingerprint_busy = true;
+                state.status = Some("Computing fingerprint...".to_string());
+                return Task::perform(compute_fingerprint_async(path.clone()), move |result| {
+                    Message::FingerprintComputed(path, result)
+                });
+            }
+            Task::none()
+        }
+        Message::FingerprintComputed(path, result) => {
+            state.fingerprint_busy = false;
+            // Another file may have been opened while fingerprinting
+            if state.file_path.as_ref() != Some(&path) {
+                log::info!("Discarding the fingerprint of {:?}, it is no longer open", path);
+                state.status = None;
+                return Task::none();
+            }
+            match result {
+                Ok(fingerprint) => {
+                    state.error = None;
+                    state.modify_metadata(|metadata| metadata.acoustid_fingerprint = Some(fingerprint));
+                    state.status = Some("Fingerprint computed, save to write it".to_string());
+                }
+                Err(e) => {
+                    log::error!("Fingerprinting failed: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Fingerprinting failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::IdentifyFingerprint => {
+            if let (Some(path), Some(metadata)) = (state.file_path.clone(), state.metadata.as_ref()) {
+                log::info!("Identifying by fingerprint: {:?}", path);
+                state.fingerprint_busy = true;
+                state.fingerprint_matches.clear();
+                return Task::perform(
+                    identify_fingerprint_async(path, metadata.acoustid_fingerprint.clone()),
+                    Message::FingerprintIdentified,
+                );
+            }
+            Task::none()
+        }
+        Message::FingerprintIdentified(result) => {
+            state.fingerprint_busy = false;
+            match result {
+                Ok(matches) => {
+                    state.error = None;
+                    state.status = Some(format!("{} matching recordings found", matches.len()));
+                    state.fingerprint_matches = matches;
+                }
+                Err(e) => {
+                    log::error!("Fingerprint lookup failed: {}", e);
+                    state.error = Some(format!("Fingerprint lookup failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ApplyRecordingMatch(index) => {
+            if let Some(recording) = state.fingerprint_matches.get(index).cloned() {
+                log::info!("Applying recording {:?} - {:?}", recording.artist, recording.title);
+                state.modify_metadata(|metadata| recording.apply_to(metadata));
+            }
+            Task::none()
+        }
+        Message::AddToFingerprintIndex => {
+            if let (Some(path), Some(metadata)) = (state.file_path.clone(), state.metadata.clone()) {
+                state.fingerprint_busy = true;
+                return Task::perform(
+                    index_fingerprints_async(vec![(path, Some(metadata))], state.legacy_separator),
+                    Message::FingerprintIndexUpdated,
+                );
+            }
+            Task::none()
+        }
+        Message::IndexFolderFingerprints => {
+            if !state.folder_files.is_empty() {
+                log::info!("Indexing fingerprints of {} files", state.folder_files.len());
+                state.fingerprint_busy = true;
+                state.status = Some(format!("Fingerprinting {} files...", state.folder_files.len()));
+                let files = state.folder_files.iter().map(|path| (path.clone(), None)).collect();
+                return Task::perform(index_fingerprints_async(files, state.legacy_separator), Message::FingerprintIndexUpdated);
+            }
+            Task::none()
+        }
+        Message::FingerprintIndexUpdated(result) => {
+            state.fingerprint_busy = false;
+            match result {
+                Ok((summary, total)) => {
+                    state.error = summary.failure_message();
+                    state.status = Some(format!(
+                        "{} files added to the fingerprint index ({} entries)",
+                        summary.written, total
+                    ));
+                }
+                Err(e) => {
+                    log::error!("Failed to update fingerprint index: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Failed to update fingerprint index: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::FindDuplicates => {
+            if !state.folder_files.is_empty() {
+                log::info!("Looking for duplicates among {} files", state.folder_files.len());
+                state.fingerprint_busy = true;
+                state.duplicate_groups.clear();
+                state.status = Some(format!("Fingerprinting {} files...", state.folder_files.len()));
+                return Task::perform(find_duplicates_async(state.folder_files.clone(), state.legacy_separator), Message::DuplicatesFound);
+            }
+            Task::none()
+        }
+        Message::DuplicatesFound(result) => {
+            state.fingerprint_busy = false;
+            match result {
+                Ok(groups) => {
+                    state.error = None;
+                    state.status = Some(format!("{} groups of duplicates found", groups.len()));
+                    state.duplicate_groups = groups;
+                }
+                Err(e) => {
+                    log::error!("Duplicate search failed: {}", e);
+                    state.status = None;
+                    state.error = Some(format!("Duplicate search failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ToggleFileSelected(path, selected) => {
+            if selected {
+                if !state.selected_files.contains(&path) {
+                    state.selected_files.push(path);
+                }
+            } else {
+                state.selected_files.retain(|selected| selected != &path);
+            }
+            // The previews covered the previous selection
+            state.cleanup_preview.clear();
+            state.refresh_preset_preview();
+            Task::none()
+        }
+        Message::SelectAllFiles(selected) => {
+            state.selected_files = if selected { state.folder_files.clone() } else { Vec::new() };
+            state.cleanup_preview.clear();
+            state.refresh_preset_preview();
+            Task::none()
+        }
+        Message::FilenamePatternChanged(pattern) => {
+            state.filename_pattern = pattern;
+            Task::none()
+        }
+        Message::FilenamePresetSelected(pattern) => {
+            state.filename_pattern = pattern;
+            Task::none()
+        }
+        Message::SaveFilenamePreset => {
+            let pattern = state.filename_pattern.trim().to_string();
+            if !pattern.is_empty() && !state.filename_patterns.contains(&pattern) {
+                log::info!("Saving filename pattern preset: {}", pattern);
+                state.filename_patterns.push(pattern);
+                state.save_settings();
+            }
+            Task::none()
+        }
+        Message::RemoveFilenamePreset => {
+            log::info!("Removing filename pattern preset: {}", state.filename_pattern);
+            state.filename_patterns.retain(|pattern| pattern != &state.filename_pattern);
+            state.save_settings();
+            Task::none()
+        }
+        Message::ApplyFilenamePattern => {
+            let pattern = match FilenamePattern::parse(&state.filename_pattern) {
+                Ok(pattern) => pattern,
+                Err(e) => {
+                    state.error = Some(format!("Invalid filename pattern: {}", e));
+                    return Task::none();
+                }
+            };
+            let targets = state.target_files();
+            if state.targets_current_file_only(&targets) {
+                match pattern.extract(&targets[0]) {
+                    Some(values) if !values.is_empty() => {
+                        state.modify_metadata(|metadata| apply_values(metadata, &values));
+                        state.error = None;
+                    }
+                    _ => state.error = Some("The file name doesn't match the pattern".to_string()),
+                }
+                return Task::none();
+            }
+            if !targets.is_empty() {
+                log::info!("Guessing tags from file names of {} files", targets.len());
+                state.batch_busy = true;
+                return Task::perform(
+                    apply_filename_pattern_async(targets, pattern, state.legacy_separator),
+                    Message::FilenamePatternApplied,
+                );
+            }
+            Task::none()
+        }
+        Message::FilenamePatternApplied(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(format!("Tags guessed from file names for {} files", summary.written));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::RenameTemplateChanged(template) => {
+            state.rename_template = template;
+            state.rename_plan.clear();
+            Task::none()
+        }
+        Message::ChooseRenameDestination => {
+            Task::perform(open_folder_dialog(), Message::RenameDestinationSelected)
+        }
+        Message::RenameDestinationSelected(path) => {
+            if let Some(path) = path {
+                log::info!("Rename destination selected: {:?}", path);
+                state.rename_destination = Some(path);
+                state.rename_plan.clear();
+            }
+            Task::none()
+        }
+        Message::ClearRenameDestination => {
+            state.rename_destination = None;
+            state.rename_plan.clear();
+            Task::none()
+        }
+        Message::PreviewRenames => {
+            let targets = state.target_files();
+            if !targets.is_empty() {
+                state.batch_busy = true;
+                return Task::perform(
+                    plan_renames_async(targets, state.rename_template.clone(), state.rename_destination.clone(), state.legacy_separator),
+                    Message::RenamePreviewReady,
+                );
+            }
+            Task::none()
+        }
+        Message::RenamePreviewReady(result) => {
+            state.batch_busy = false;
+            match result {
+                Ok(plan) => {
+                    state.error = None;
+                    state.rename_plan = plan;
+                }
+                Err(e) => {
+                    state.rename_plan.clear();
+                    state.error = Some(format!("Invalid rename template: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ApplyRenames => {
+            if state.rename_plan.iter().any(|entry| entry.status == RenameStatus::Ready) {
+                log::info!("Renaming files with template: {}", state.rename_template);
+                state.batch_busy = true;
+                state.save_settings();
+                return Task::perform(
+                    apply_renames_async(std::mem::take(&mut state.rename_plan)),
+                    Message::RenamesApplied,
+                );
+            }
+            Task::none()
+        }
+        Message::UndoRename => {
+            state.batch_busy = true;
+            state.rename_plan.clear();
+            Task::perform(undo_rename_async(), Message::RenamesApplied)
+        }
+        Message::RenamesApplied(result) => {
+            state.batch_busy = false;
+            match result {
+                Ok(report) => {
+                    state.apply_moves(&report.moves);
+                    state.rename_undo_available =
+                        get_rename_log_path().is_some_and(|path| !UndoLog::load(path).is_empty());
+                    state.error =
+                        (!report.failed.is_empty()).then(|| format!("Could not move: {}", report.failed.join(", ")));
+                    state.status = Some(format!("{} files moved", report.moves.len()));
+                    // The open folder may have gained or lost files
+                    if let Some(folder) = state.folder_path.clone().filter(|folder| folder.is_dir()) {
+                        return Task::perform(scan_folder_async(folder), Message::FolderScanned);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Renaming files failed: {}", e);
+                    state.error = Some(format!("Renaming files failed: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::NumberingStartChanged(value) => {
+            state.numbering_inputs.start = value;
+            Task::none()
+        }
+        Message::NumberingDiscChanged(value) => {
+            state.numbering_inputs.disc = value;
+            Task::none()
+        }
+        Message::NumberingTotalDiscsChanged(value) => {
+            state.numbering_inputs.total_discs = value;
+            Task::none()
+        }
+        Message::ApplyNumbering => {
+            if let Some(numbering) = state.numbering_inputs.parse() {
+                if !state.selected_files.is_empty() {
+                    log::info!("Numbering {} files: {:?}", state.selected_files.len(), numbering);
+                    state.batch_busy = true;
+                    return Task::perform(
+                        apply_numbering_async(state.selected_files.clone(), numbering, state.legacy_separator),
+                        Message::NumberingApplied,
+                    );
+                }
+            }
+            Task::none()
+        }
+        Message::NumberingApplied(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(format!("{} files numbered", summary.written));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::CleanupRulesChanged(rules) => {
+            state.cleanup_rules = rules;
+            state.cleanup_preview.clear();
+            Task::none()
+        }
+        Message::CleanupExceptionsChanged(value) => {
+            state.cleanup_rules.case_exceptions = value
+                .split(',')
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_string)
+                .collect();
+            state.cleanup_exceptions = value;
+            state.cleanup_preview.clear();
+            Task::none()
+        }
+        Message::ToggleCleanupField(field, enabled) => {
+            state.cleanup_fields.retain(|existing| *existing != field);
+            if enabled {
+                state.cleanup_fields.push(field);
+            }
+            state.cleanup_preview.clear();
+            Task::none()
+        }
+        Message::PreviewCleanup => {
+            let targets = state.target_files();
+            if !targets.is_empty() {
+                state.batch_busy = true;
+                return Task::perform(
+                    preview_cleanup_async(targets, state.cleanup_rules.clone(), state.cleanup_fields.clone(), state.legacy_separator),
+                    Message::CleanupPreviewReady,
+                );
+            }
+            Task::none()
+        }
+        Message::CleanupPreviewReady(result) => {
+            state.batch_busy = false;
+            match result {
+                Ok(preview) => {
+                    state.error = None;
+                    state.cleanup_preview = preview;
+                }
+                Err(e) => {
+                    state.cleanup_preview.clear();
+                    state.error = Some(format!("Could not preview the clean up: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ApplyCleanup => {
+            let rules = state.cleanup_rules.clone();
+            let fields = state.cleanup_fields.clone();
+            state.save_settings();
+            let targets = state.target_files();
+            if state.targets_current_file_only(&targets) {
+                state.modify_metadata(|metadata| apply_cleanup(&rules, &fields, metadata));
+                state.error = None;
+                return Task::none();
+            }
+            // Only files the preview showed changes for are written
+            let files: Vec<PathBuf> = std::mem::take(&mut state.cleanup_preview)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect();
+            if !files.is_empty() {
+                log::info!("Cleaning up {} fields of {} files", fields.len(), files.len());
+                state.batch_busy = true;
+                return Task::perform(apply_cleanup_async(files, rules, fields, state.legacy_separator), Message::CleanupApplied);
+            }
+            Task::none()
+        }
+        Message::CleanupApplied(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(format!("{} files cleaned up", summary.written));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::ListInputChanged(field, value) => {
+            state.field_inputs.insert(field, value);
+            Task::none()
+        }
+        Message::AddListValues(field) => {
+            // Several values can be added at once, separated like the text form of the list
+            let values = state.field_inputs.remove(&field).map(|value| split_list(&value)).unwrap_or_default();
+            if !values.is_empty() {
+                state.modify_metadata(|metadata| field.list_values_mut(metadata).extend(values));
+            }
+            Task::none()
+        }
+        Message::RemoveListValue(field, index) => {
+            state.modify_metadata(|metadata| {
+                let values = field.list_values_mut(metadata);
+                if index < values.len() {
+                    values.remove(index);
+                }
+            });
+            Task::none()
+        }
+        Message::AddCredit(list) => {
+            state.modify_metadata(|metadata| list.credits_mut(metadata).push(Credit::default()));
+            Task::none()
+        }
+        Message::RemoveCredit(list, index) => {
+            state.modify_metadata(|metadata| {
+                let credits = list.credits_mut(metadata);
+                if index < credits.len() {
+                    credits.remove(index);
+                }
+            });
+            Task::none()
+        }
+        Message::CreditRoleChanged(list, index, role) => {
+            state.modify_metadata(|metadata| {
+                if let Some(credit) = list.credits_mut(metadata).get_mut(index) {
+                    credit.role = role;
+                }
+            });
+            Task::none()
+        }
+        Message::CreditNameChanged(list, index, name) => {
+            state.modify_metadata(|metadata| {
+                if let Some(credit) = list.credits_mut(metadata).get_mut(index) {
+                    credit.name = name;
+                }
+            });
+            Task::none()
+        }
+        Message::GenreSearchChanged(value) => {
+            state.genre_search = value;
+            Task::none()
+        }
+        Message::AddGenre(genre) => {
+            let genre = genre.trim().to_string();
+            if !genre.is_empty() {
+                state.modify_metadata(|metadata| {
+                    if !metadata.genres.iter().any(|existing| existing.eq_ignore_ascii_case(&genre)) {
+                        metadata.genres.push(genre);
+                    }
+                });
+                state.genre_search.clear();
+            }
+            Task::none()
+        }
+        Message::RemoveGenre(genre) => {
+            state.modify_metadata(|metadata| metadata.genres.retain(|existing| *existing != genre));
+            Task::none()
+        }
+        Message::ToggleFavoriteGenre(genre) => {
+            if let Some(index) = state.favorite_genres.iter().position(|favorite| favorite.eq_ignore_ascii_case(&genre)) {
+                state.favorite_genres.remove(index);
+            } else {
+                state.favorite_genres.push(genre);
+            }
+            state.save_settings();
+            Task::none()
+        }
+        Message::LegacyEncodingSelected(encoding) => {
+            state.legacy_encoding = Some(encoding);
+            Task::none()
+        }
+        Message::RepairEncoding => {
+            let encoding = state
+                .legacy_encoding
+                .or_else(|| state.metadata.as_ref().and_then(guess_encoding));
+            if let Some(encoding) = encoding {
+                log::info!("Repairing mis-encoded text as {}", encoding);
+                state.modify_metadata(|metadata| {
+                    repair(metadata, encoding);
+                });
+            }
+            Task::none()
+        }
+        Message::RepairSelectedEncodings => {
+            // Without a chosen code page each file gets its own guess
+            if !state.selected_files.is_empty() {
+                log::info!(
+                    "Repairing mis-encoded text of {} files as {}",
+                    state.selected_files.len(),
+                    state.legacy_encoding.map_or_else(|| "guessed per file".to_string(), |encoding| encoding.to_string())
+                );
+                state.batch_busy = true;
+                return Task::perform(
+                    repair_encodings_async(state.target_files(), state.legacy_encoding, state.legacy_separator),
+                    Message::EncodingsRepaired,
+                );
+            }
+            Task::none()
+        }
+        Message::EncodingsRepaired(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(format!("Text repaired in {} files", summary.written));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::PreviewConversion(target) => {
+            let targets = state.target_files();
+            if !targets.is_empty() {
+                state.batch_busy = true;
+                return Task::perform(preview_conversion_async(targets, target, state.legacy_separator), move |result| {
+                    Message::ConversionPreviewReady(target, result)
+                });
+            }
+            Task::none()
+        }
+        Message::ConversionPreviewReady(target, result) => {
+            state.batch_busy = false;
+            match result {
+                Ok(preview) => {
+                    state.error = None;
+                    state.conversion_preview = Some((target, preview));
+                }
+                Err(e) => {
+                    state.conversion_preview = None;
+                    state.error = Some(format!("Could not read the tags to convert: {}", e));
+                }
+            }
+            Task::none()
+        }
+        Message::ApplyConversion => {
+            if let Some((target, preview)) = state.conversion_preview.take() {
+                let files: Vec<PathBuf> = preview
+                    .into_iter()
+                    .filter(|(_, report)| !report.is_empty())
+                    .map(|(path, _)| path)
+                    .collect();
+                if !files.is_empty() {
+                    log::info!("Converting {} files to {}", files.len(), target);
+                    state.batch_busy = true;
+                    return Task::perform(convert_files_async(files, target, state.legacy_separator), Message::ConversionApplied);
+                }
+            }
+            Task::none()
+        }
+        Message::CancelConversion => {
+            state.conversion_preview = None;
+            Task::none()
+        }
+        Message::ConversionApplied(summary) => {
+            state.batch_busy = false;
+            state.error = summary.failure_message();
+            state.status = Some(format!("{} files converted", summary.written));
+            if let Some(path) = state.file_path.clone() {
+                return Task::perform(parse_file_async(path, state.legacy_separator), Message::MetadataParsed);
+            }
+            Task::none()
+        }
+        Message::ChaptersExported(result) => {
+            match result {
+                Ok(path) => {
+                    state.error = None;
+                    state.status = Some(format!("Chapters exported to {}", path.to_string_lossy()));
+                }
+                Err(e) => {
+                    log::error!("Failed to export chapters: {}", e);
+                    state.error = Some(format!("Failed to export chapters: {}", e));
+                }
+            }
+            Task::none()
+        }
+    }
+}
+
+/// View function that builds the UI
+pub fn view(state: &State) -> Element<'_, Message> {
+    // Apply zoom to sizes
+    let base_size = 32.0 * state.zoom;
+    let base_spacing = 10.0 * state.zoom;
+    let base_padding = 20.0 * state.zoom;
+
+    // Settings button in top right
+    let settings_button = button("⚙️")
+        .on_press(Message::ToggleSettings)
+        .padding(8);
+
+    // Header with title and settings button
+    let header = row![
+        text("ID3 Tag Manager")
+            .size(base_size as u32)
+            .width(Length::Fill)
+            .align_x(Horizontal::Center),
+        settings_button,
+    ]
+    .spacing(10)
+    .align_y(Vertical::Center)
+    .width(Length::Fill);
+
+    let file_picker = button("Select Audio File")
+        .on_press(Message::OpenFileDialog)
+        .padding(10);
+
+    let save_label = if state.unsaved_changes { "Save Tags *" } else { "Save Tags" };
+    let save_button = button(save_label)
+        .on_press_maybe((state.metadata.is_some() && state.unsaved_changes).then_some(Message::SaveTags))
+        .padding(10);
+
+    let folder_picker = button("Open Folder")
+        .on_press(Message::OpenFolderDialog)
+        .padding(10);
+
+    let report_button = button("Tag Report")
+        .on_press(Message::ToggleReport)
+        .padding(10)
+        .style(if state.report_open { button_widget::primary } else { button_widget::secondary });
+
+    let duplicates_button = button("Duplicates")
+        .on_press(Message::ToggleDuplicates)
+        .padding(10)
+        .style(if state.duplicates_open { button_widget::primary } else { button_widget::secondary });
+
+    let compare_button = button("Compare With...")
+        .on_press_maybe(state.file_path.is_some().then_some(Message::CompareWithFileDialog))
+        .padding(10)
+        .style(button_widget::secondary);
+
+    let copy_button = button("Copy Tags")
+        .on_press_maybe(state.metadata.is_some().then_some(Message::CopyTags))
+        .padding(10)
+        .style(button_widget::secondary);
+
+    let copy_clipboard_button = button("Copy to Clipboard")
+        .on_press_maybe(state.metadata.is_some().then_some(Message::CopyTagsToClipboard))
+        .padding(10)
+        .style(button_widget::secondary);
+
+    let clipboard_button = button("Paste From Clipboard")
+        .on_press(Message::PasteFromClipboard)
+        .padding(10)
+        .style(button_widget::secondary);
+
+    let file_actions = row![
+        file_picker,
+        folder_picker,
+        save_button,
+        report_button,
+        duplicates_button,
+        compare_button,
+        copy_button,
+        copy_clipboard_button,
+        clipboard_button,
+    ]
+    .spacing(base_spacing)
+    .wrap()
+    .vertical_spacing(base_spacing);
+
+    // Display current file path (read-only) - apply zoom to text size
+    let file_text_size = (14.0 * state.zoom) as u32;
+    let file_path_display = if let Some(ref path) = state.file_path {
+        container(
+            text(path.to_string_lossy())
+                .size(file_text_size)
+                .style(|_theme| {
+                    iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.6, 0.8, 1.0)),
+                    }
+                })
+        )
+        .padding(10.0 * state.zoom)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+    } else {
+        container(
+            text("No file selected")
+                .size(file_text_size)
+                .style(|_theme| {
+                    iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                    }
+                })
+        )
+        .padding(10.0 * state.zoom)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+    };
+
+    let mut content = column![
+        header,
+        Space::new().height(20.0 * state.zoom),
+        file_actions,
+        Space::new().height(base_spacing),
+        file_path_display,
+    ]
+    .spacing(base_spacing)
+    .padding(base_padding)
+    .width(Length::Fill);
+
+    // Add recent files section
+    if !state.recent_files.is_empty() {
+        content = content.push(Space::new().height(20.0 * state.zoom));
+        content = content.push(build_recent_files_view(&state.recent_files, &state.file_path, state.zoom));
+    }
+
+    // Add open folder section
+    if let Some(ref folder) = state.folder_path {
+        content = content.push(Space::new().height(20.0 * state.zoom));
+        content = content.push(build_folder_view(state, folder));
+    }
+
+    if state.report_open {
+        content = content.push(build_report_view(state));
+    }
+    if state.duplicates_open {
+        content = content.push(build_duplicates_view(state));
+    }
+    if let Some(ref comparison) = state.comparison {
+        content = content.push(build_comparison_view(comparison, state.zoom));
+    }
+
+    // Add batch tools for the current or selected files
+    if state.file_path.is_some() || !state.selected_files.is_empty() {
+        content = content.push(build_filename_pattern_view(state));
+        content = content.push(build_rename_view(state));
+        content = content.push(build_cleanup_view(state));
+        if let Some((source, tags)) = &state.copied_tags {
+            content = content.push(build_paste_view(state, source, tags));
+        }
+        content = content.push(build_preset_view(state));
+    }
+    if !state.selected_files.is_empty() {
+        content = content.push(build_numbering_view(state));
+    }
+
+    // Show error if any
+    if let Some(ref error) = state.error {
+        content = content.push(
+            container(text(error).style(|_theme| {
+                iced::widget::text::Style {
+                    color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                }
+            }))
+            .padding(10)
+            .style(container::rounded_box),
+        );
+    }
+
+    // Show status message if any
+    if let Some(ref status) = state.status {
+        content = content.push(
+            container(text(status).style(|_theme| {
+                iced::widget::text::Style {
+                    color: Some(iced::Color::from_rgb(0.3, 0.8, 0.4)),
+                }
+            }))
+            .padding(10)
+            .style(container::rounded_box),
+        );
+    }
+
+    // Show metadata if available
+    if let Some(ref metadata) = state.metadata {
+        content = content.push(Space::new().height(20.0 * state.zoom));
+        content = content.push(build_metadata_view(state, metadata));
+        content = content.push(build_genre_view(state, metadata));
+        content = content.push(build_dates_view(state, metadata));
+        content = content.push(build_credits_view(metadata, state.zoom));
+        content = content.push(build_version_view(state, metadata));
+        if !suspicious_fields(metadata).is_empty() {
+            content = content.push(build_encoding_view(state, metadata));
+        }
+        content = content.push(build_synced_lyrics_view(metadata, state.zoom, state.theme));
+        content = content.push(build_chapters_view(metadata, &state.chapter_time_inputs, state.zoom));
+        content = content.push(build_replay_gain_view(state, metadata));
+        content = content.push(build_lookup_view(state));
+        content = content.push(build_fingerprint_view(state, metadata));
+    } else {
+        let no_metadata_text_size = (14.0 * state.zoom) as u32;
+        content = content.push(
+            container(
+                text("No metadata loaded. Select an audio file to view its ID3 tags.")
+                    .size(no_metadata_text_size)
+                    .align_x(Horizontal::Center),
+            )
+            .padding(20.0 * state.zoom)
+            .width(Length::Fill),
+        );
+    }
+
+    let main_content = container(
+        scrollable(
+            content
+                .width(Length::Fill)
+                .align_x(Horizontal::Center)
+        )
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding(Padding::new(base_padding));
+
+    // Add settings overlay if open
+    if state.settings_open {
+        // Create overlay with semi-transparent background and centered settings window
+        // Use a container that covers the entire screen
+        container(
+            row![
+                Space::new().width(Length::Fill),
+                column![
+                    Space::new().height(Length::Fill),
+                    build_settings_overlay(state),
+                    Space::new().height(Length::Fill),
+                ]
+                .align_x(Horizontal::Center)
+                .width(Length::Shrink),
+                Space::new().width(Length::Fill),
+            ]
+            .width(Length::Fill)
+            .height(Length::Fill)
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|theme| {
+            container::Style {
+                background: Some(iced::Color::from_rgba(0.0, 0.0, 0.0, 0.5).into()),
+                ..container::rounded_box(theme)
+            }
+        })
+        .into()
+    } else {
+        main_content.into()
+    }
+}
+
+/// Build the settings overlay window (fixed size, not affected by zoom)
+fn build_settings_overlay<'a>(state: &'a State) -> Element<'a, Message> {
+    // Fixed sizes for the overlay (not affected by zoom)
+    const TITLE_SIZE: u32 = 20;
+    const BUTTON_PADDING: f32 = 10.0;
+    const SECTION_PADDING: f32 = 15.0;
+    const SPACING: f32 = 10.0;
+    const BUTTON_WIDTH: f32 = 50.0;
+    
+    // Capture theme for use in closures
+    let theme = state.theme;
+
+    // Theme selection
+    let dark_button = if state.theme == AppTheme::Dark {
+        button("Dark")
+            .on_press(Message::ThemeChanged(AppTheme::Dark))
+            .style(button_widget::primary)
+    } else {
+        button("Dark")
+            .on_press(Message::ThemeChanged(AppTheme::Dark))
+            .style(button_widget::secondary)
+    };
+    
+    let light_button = if state.theme == AppTheme::Light {
+        button("Light")
+            .on_press(Message::ThemeChanged(AppTheme::Light))
+            .style(button_widget::primary)
+    } else {
+        button("Light")
+            .on_press(Message::ThemeChanged(AppTheme::Light))
+            .style(button_widget::secondary)
+    };
+    
+    let theme_buttons = row![dark_button, light_button].spacing(SPACING);
+
+    // Zoom controls with + and - buttons (centered text)
+    let zoom_controls = row![
+        button(
+            text("-")
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Horizontal::Center)
+                .align_y(Vertical::Center)
+        )
+            .on_press(Message::ZoomDecrease)
+            .width(Length::Fixed(BUTTON_WIDTH))
+            .height(Length::Fixed(BUTTON_WIDTH))
+            .padding(BUTTON_PADDING),
+        text(format!("Zoom: {:.0}%", state.zoom * 100.0))
+            .size(16) // Slightly larger for bold appearance
+            .width(Length::Fill)
+            .align_x(Horizontal::Center)
+            .style(move |_theme| {
+                iced::widget::text::Style {
+                    // Theme-aware label color
+                    color: Some(match theme {
+                        AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1), // Dark for light theme
+                        AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),  // Light for dark theme
+                    }),
+                }
+            }),
+        button(
+            text("+")
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Horizontal::Center)
+                .align_y(Vertical::Center)
+        )
+            .on_press(Message::ZoomIncrease)
+            .width(Length::Fixed(BUTTON_WIDTH))
+            .height(Length::Fixed(BUTTON_WIDTH))
+            .padding(BUTTON_PADDING),
+    ]
+    .spacing(SPACING)
+    .align_y(Vertical::Center);
+
+    // Close button
+    let close_button = button(text("X")
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .align_x(Horizontal::Center)
+    .align_y(Vertical::Center))
+        .on_press(Message::ToggleSettings)
+        .padding(5.0)
+        .width(Length::Fixed(30.0))
+        .height(Length::Fixed(30.0));
+
+    container(
+        column![
+            // Header row with title and close button
+            row![
+                text("Accessibility Settings")
+                    .size(TITLE_SIZE)
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center),
+                close_button,
+            ]
+            .align_y(Vertical::Center)
+            .width(Length::Fill),
+            Space::new().height(SPACING),
+            row![
+                text("Theme:")
+                    .size(16) // Slightly larger for bold appearance
+                    .width(Length::Fixed(100.0))
+                    .style(move |_theme| {
+                        iced::widget::text::Style {
+                            // Theme-aware label color
+                            color: Some(match theme {
+                                AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1), // Dark for light theme
+                                AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),  // Light for dark theme
+                            }),
+                        }
+                    }),
+                theme_buttons,
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            Space::new().height(SPACING),
+            zoom_controls,
+            Space::new().height(SPACING),
+            row![
+                text("Ratings:")
+                    .size(16) // Slightly larger for bold appearance
+                    .width(Length::Fixed(100.0))
+                    .style(move |_theme| {
+                        iced::widget::text::Style {
+                            // Theme-aware label color
+                            color: Some(match theme {
+                                AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1), // Dark for light theme
+                                AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),  // Light for dark theme
+                            }),
+                        }
+                    }),
+                pick_list(RatingProfile::ALL, Some(state.rating_profile), Message::RatingProfileChanged)
+                    .width(Length::Fill),
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            Space::new().height(SPACING),
+            row![
+                text("v2.3 lists:")
+                    .size(16) // Slightly larger for bold appearance
+                    .width(Length::Fixed(100.0))
+                    .style(move |_theme| {
+                        iced::widget::text::Style {
+                            // Theme-aware label color
+                            color: Some(match theme {
+                                AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1), // Dark for light theme
+                                AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),  // Light for dark theme
+                            }),
+                        }
+                    }),
+                pick_list(ListSeparator::ALL, Some(state.legacy_separator), Message::LegacySeparatorChanged)
+                    .width(Length::Fill),
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            Space::new().height(SPACING),
+            text("Tag Presets:")
+                .size(16) // Slightly larger for bold appearance
+                .style(move |_theme| {
+                    iced::widget::text::Style {
+                        // Theme-aware label color
+                        color: Some(match theme {
+                            AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1), // Dark for light theme
+                            AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),  // Light for dark theme
+                        }),
+                    }
+                }),
+            build_preset_editor(state),
+        ]
+        .spacing(SPACING)
+        .width(Length::Fill),
+    )
+    .padding(SECTION_PADDING)
+    .style(container::rounded_box)
+    .width(Length::Fixed(420.0))
+    .into()
+}
+
+/// Async function to open file dialog
+async fn open_file_dialog() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("Audio Files", AUDIO_EXTENSIONS)
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Async function to open a link in the browser
+async fn open_url_async(url: String) -> Result<(), String> {
+    open::that(&url).map_err(|e| format!("Failed to open {}: {}", url, e))
+}
+
+/// Async function to parse ID3 tags
+async fn parse_file_async(path: PathBuf, separator: ListSeparator) -> Result<AudioMetadata, ParseError> {
+    parse_id3(path, separator)
+}
+
+/// Async function to open a folder dialog
+async fn open_folder_dialog() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .pick_folder()
+        .await
+        .map(|folder| folder.path().to_path_buf())
+}
+
+/// Whether a path has one of the supported audio file extensions
+fn is_audio_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.iter().any(|audio_ext| ext.eq_ignore_ascii_case(audio_ext)))
+}
+
+/// Async function to list the audio files of a folder, sorted by name
+async fn scan_folder_async(path: PathBuf) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(&path).map_err(|e| e.to_string())?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_audio_file(path))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// List the audio files of a folder and its subfolders, sorted by path
+///
+/// Symlinked folders aren't followed, so links pointing back up the tree can't loop.
+/// Subfolders that can't be read are skipped.
+fn list_audio_files_recursive(folder: &std::path::Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let mut folders = vec![folder.to_path_buf()];
+    while let Some(current) = folders.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) if current == folder => return Err(format!("{}: {}", current.to_string_lossy(), e)),
+            Err(e) => {
+                log::warn!("Skipping folder {:?}: {}", current, e);
+                continue;
+            }
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            // The type of the entry itself, so a symlink to a folder isn't a folder
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                folders.push(path);
+            } else if path.is_file() && is_audio_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parse, change and write the tags of several files
+///
+/// `change` returns false to leave a file untouched (it's then reported as failed). Files
+/// the change gives validation issues they didn't have before aren't written either.
+fn update_tags(
+    files: &[PathBuf],
+    separator: ListSeparator,
+    mut change: impl FnMut(&std::path::Path, &mut AudioMetadata) -> bool,
+) -> BatchSummary {
+    let mut summary = BatchSummary::default();
+    for path in files {
+        let mut metadata = match parse_id3(path, separator) {
+            Ok(metadata) => metadata,
+            Err(ParseError::NoId3Tag) => AudioMetadata::default(),
+            Err(e) => {
+                log::warn!("Could not read tags of {:?}: {}", path, e);
+                summary.failed.push(display_name(path));
+                continue;
+            }
+        };
+        let issues_before = validate(&metadata);
+        // Only the ID3v2.4-only dates are compared afterwards, so the rest isn't copied
+        let dates = AudioMetadata {
+            released: metadata.released,
+            encoded: metadata.encoded,
+            tagged: metadata.tagged,
+            ..AudioMetadata::default()
+        };
+        if !change(path, &mut metadata) {
+            log::debug!("Leaving {:?} untouched", path);
+            summary.failed.push(display_name(path));
+            continue;
+        }
+        let issues = new_issues(&issues_before, &metadata);
+        if !issues.is_empty() {
+            let fields: Vec<&str> = issues.iter().map(|issue| issue.field.label()).collect();
+            log::warn!("Not writing {:?}, invalid fields: {}", path, fields.join(", "));
+            summary.failed.push(display_name(path));
+            continue;
+        }
+        match write_id3(path, &metadata, separator) {
+            Ok(()) => {
+                summary.written += 1;
+                if !unwritable_changes(&dates, &metadata).is_empty() {
+                    summary.undated.push(display_name(path));
+                }
+            }
+            Err(e) => {
+                log::warn!("Could not write tags to {:?}: {}", path, e);
+                summary.failed.push(display_name(path));
+            }
+        }
+    }
+    summary
+}
+
+/// File name of a path for display, falling back to the full path
+fn display_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Async function to write ID3 tags
+///
+/// Returns the changed fields the file's ID3v2.3 tag couldn't store.
+async fn save_file_async(
+    path: PathBuf,
+    metadata: AudioMetadata,
+    separator: ListSeparator,
+) -> Result<Vec<MetadataField>, ParseError> {
+    let unwritten = match parse_id3(&path, separator) {
+        Ok(original) => unwritable_changes(&original, &metadata),
+        Err(_) => Vec::new(),
+    };
+    write_id3(path, &metadata, separator)?;
+    Ok(unwritten)
+}
+
+/// Build the recent files view
+fn build_recent_files_view<'a>(recent_files: &'a [PathBuf], current_file: &'a Option<PathBuf>, zoom: f32) -> Element<'a, Message> {
+    let text_size = (16.0 * zoom) as u32;
+    let title_size = (20.0 * zoom) as u32;
+    let spacing = 10.0 * zoom;
+    let padding = 15.0 * zoom;
+    
+    let mut files_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+
+    for path in recent_files {
+        // Extract just the filename from the path
+        let filename = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        let path_clone = path.clone();
+        
+        // Check if this is the currently selected file
+        let is_selected = current_file.as_ref() == Some(path);
+        
+        // Apply different styling for selected file
+        let file_button = if is_selected {
+            button(
+                text(filename)
+                    .size(text_size)
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Left)
+            )
+            .on_press(Message::SelectRecentFile(path_clone))
+            .padding(8.0 * zoom)
+            .width(Length::Fill)
+            .style(|theme: &iced::Theme, status: button_widget::Status| {
+                // Use a stronger, more vibrant color for selected file
+                let selected_color = iced::Color::from_rgb(0.2, 0.6, 1.0); // Bright blue
+                button_widget::Style {
+                    background: Some(selected_color.into()),
+                    text_color: iced::Color::WHITE,
+                    border: iced::Border {
+                        width: 1.0,
+                        color: iced::Color::from_rgb(0.1, 0.5, 0.9),
+                        radius: 4.0.into(),
+                    },
+                    ..button_widget::primary(theme, status)
+                }
+            })
+        } else {
+            button(
+                text(filename)
+                    .size(text_size)
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Left)
+            )
+            .on_press(Message::SelectRecentFile(path_clone))
+            .padding(8.0 * zoom)
+            .width(Length::Fill)
+        };
+        
+        files_column = files_column.push(file_button);
+    }
+
+    container(
+        column![
+            text(format!("Recent Files ({})", recent_files.len()))
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            Space::new().height(spacing),
+            files_column,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Build the metadata display view
+fn build_metadata_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let theme = state.theme;
+    let rating_profile = state.rating_profile;
+    let field_inputs = &state.field_inputs;
+    let issues = validate(metadata);
+    let title_size = (24.0 * zoom) as u32;
+    let spacing = 10.0 * zoom;
+    let padding = 15.0 * zoom;
+    
+    let mut metadata_rows = Column::new()
+        .spacing(spacing)
+        .width(Length::Fill);
+
+    // Add an editable row for each metadata field (duration is read from the audio, not edited;
+    // timestamps and genres have their own editors)
+    metadata_rows = add_numeric_field(metadata_rows, "Duration:", metadata.duration, zoom, theme);
+    for field in MetadataField::ALL
+        .into_iter()
+        .filter(|field| !field.is_timestamp() && *field != MetadataField::Genre)
+    {
+        if field.is_list() {
+            let input = field_inputs.get(&field).map(String::as_str).unwrap_or_default();
+            metadata_rows = metadata_rows.push(create_list_field_row(field, field.list_values(metadata), input, zoom, theme));
+            continue;
+        }
+        let value = field_inputs
+            .get(&field)
+            .cloned()
+            .or_else(|| field.value(metadata))
+            .unwrap_or_default();
+        let field_issues = issues
+            .iter()
+            .filter(|issue| issue.field == field)
+            .map(|issue| issue.message.clone())
+            .collect();
+        metadata_rows = metadata_rows.push(create_field_row(field, value, field_issues, zoom, theme));
+    }
+
+    // Rating from the POPM frame of the configured player, falling back to any other player
+    let popm = metadata
+        .popularimeters
+        .iter()
+        .find(|popm| popm.email == rating_profile.email())
+        .or_else(|| metadata.popularimeters.first());
+    let stars = popm.map_or(0, |popm| rating_profile.stars_for_rating(popm.rating));
+    metadata_rows = metadata_rows.push(create_element_row("Rating:", build_star_rating(stars, zoom), zoom, theme));
+    for popm in &metadata.popularimeters {
+        metadata_rows = metadata_rows.push(create_row(
+            format!("POPM ({}):", popm.email),
+            format!("{}/255, {} plays", popm.rating, popm.counter),
+            zoom,
+            theme,
+        ));
+    }
+    if let Some(play_count) = metadata.play_count {
+        metadata_rows = metadata_rows.push(create_row("Play Count:", play_count.to_string(), zoom, theme));
+    }
+
+    // Handle lyrics with truncation
+    if let Some(ref lyrics) = metadata.lyrics {
+        if !lyrics.is_empty() {
+            let display_lyrics = if lyrics.len() > 200 {
+                format!("{}...", &lyrics[..200])
+            } else {
+                lyrics.clone()
+            };
+            metadata_rows = metadata_rows.push(create_row("Lyrics:", display_lyrics, zoom, theme));
+        }
+    }
+
+    // Display custom fields
+    for (key, value) in &metadata.custom_fields {
+        if !value.is_empty() {
+            let label = format!("{}:", key);
+            metadata_rows = metadata_rows.push(create_row(label, value.clone(), zoom, theme));
+        }
+    }
+
+    let mut metadata_content = column![
+        text("Metadata")
+            .size(title_size)
+            .align_x(Horizontal::Center),
+        Space::new().height(spacing * 1.5),
+    ]
+    .spacing(spacing)
+    .width(Length::Fill);
+
+    // Add cover art - show default if not available
+    let cover_display: Element<'_, Message> = if let Some(ref cover_data) = metadata.cover_art {
+        // Generate a unique filename based on track metadata with correct extension
+        let extension = determine_image_extension(&metadata.cover_art_format);
+        let filename_base = generate_cover_filename(metadata);
+        let filename = format!("{}.{}", filename_base, extension);
+        let temp_dir = std::env::temp_dir().join("id3_iced_manager");
+        let temp_path = temp_dir.join(&filename);
+        
+        // Create directory if it doesn't exist
+        if let Err(e) = std::fs::create_dir_all(&temp_dir) {
+            log::error!("Failed to create temp directory {:?}: {}", temp_dir, e);
+        }
+        
+        // Write cover art to file
+        use std::fs;
+        use std::io::Write;
+        
+        log::debug!("Saving cover art to: {:?} (format: {:?})", temp_path, metadata.cover_art_format);
+        
+        // Only write if file doesn't exist (reuse existing file)
+        if temp_path.exists() {
+            log::debug!("Cover file already exists, reusing: {:?}", temp_path);
+            let handle = Handle::from_path(temp_path.clone());
+            image(handle)
+                .width(Length::Fixed(200.0))
+                .height(Length::Fixed(200.0))
+                .into()
+        } else {
+            match fs::File::create(&temp_path) {
+                Ok(mut file) => {
+                    if file.write_all(cover_data).is_ok() {
+                        drop(file); // Close file before reading
+                        
+                        // Verify file exists and has content
+                        if temp_path.exists() {
+                            if let Ok(file_metadata) = fs::metadata(&temp_path) {
+                                log::debug!("Cover file created successfully: {:?}, size: {} bytes", temp_path, file_metadata.len());
+                            }
+                            let handle = Handle::from_path(temp_path.clone());
+                            image(handle)
+                                .width(Length::Fixed(200.0))
+                                .height(Length::Fixed(200.0))
+                                .into()
+                        } else {
+                            log::warn!("Cover file does not exist after creation: {:?}", temp_path);
+                            create_default_cover()
+                        }
+                    } else {
+                        log::error!("Failed to write cover data to file: {:?}", temp_path);
+                        create_default_cover()
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to create cover file {:?}: {}", temp_path, e);
+                    create_default_cover()
+                }
+            }
+        }
+    } else {
+        create_default_cover()
+    };
+    
+    metadata_content = metadata_content.push(
+        container(cover_display)
+            .align_x(Horizontal::Center)
+            .padding(10.0 * zoom)
+    );
+    metadata_content = metadata_content.push(Space::new().height(spacing * 1.5));
+
+    metadata_content = metadata_content.push(
+        container(metadata_rows)
+            .padding(padding)
+            .style(container::rounded_box)
+            .width(Length::Fill)
+    );
+
+    container(metadata_content)
+        .padding(20.0 * zoom)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Font sizes and spacing of the tool panels, scaled by the zoom level
+struct Sizes {
+    title: u32,
+    text: u32,
+    small: u32,
+    spacing: f32,
+    padding: f32,
+}
+
+impl Sizes {
+    fn new(zoom: f32) -> Self {
+        Sizes {
+            title: (20.0 * zoom) as u32,
+            text: (14.0 * zoom) as u32,
+            small: ((12.0 * zoom) as u32).max(1),
+            spacing: 10.0 * zoom,
+            padding: 15.0 * zoom,
+        }
+    }
+}
+
+/// Build the open folder view listing its audio files
+fn build_folder_view<'a>(state: &'a State, folder: &'a std::path::Path) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let files = &state.folder_files;
+    let current_file = &state.file_path;
+    let Sizes { title: title_size, spacing, padding, .. } = Sizes::new(zoom);
+    // File names are as large as in the recent files list
+    let text_size = (16.0 * zoom) as u32;
+
+    let mut files_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+
+    for path in files {
+        let is_selected = current_file.as_ref() == Some(path);
+        let file_button = button(
+            text(display_name(path))
+                .size(text_size)
+                .width(Length::Fill)
+                .align_x(Horizontal::Left)
+        )
+        .on_press(Message::FolderFileSelected(path.clone()))
+        .padding(8.0 * zoom)
+        .width(Length::Fill)
+        .style(if is_selected { button_widget::primary } else { button_widget::secondary });
+        let path_for_toggle = path.clone();
+        // The selection position is shown since numbering follows it
+        let selection_index = state.selected_files.iter().position(|selected| selected == path);
+        files_column = files_column.push(
+            row![
+                checkbox(selection_index.is_some())
+                    .label(selection_index.map_or_else(String::new, |index| format!("{}", index + 1)))
+                    .on_toggle(move |selected| Message::ToggleFileSelected(path_for_toggle.clone(), selected)),
+                file_button,
+            ]
+            .spacing(spacing)
+            .align_y(Vertical::Center),
+        );
+    }
+
+    let all_selected = !files.is_empty() && state.selected_files.len() == files.len();
+    let selection = row![
+        checkbox(all_selected)
+            .label(format!("{} selected", state.selected_files.len()))
+            .on_toggle(Message::SelectAllFiles),
+        Space::new().width(Length::Fill),
+        button(text("Compare").size((14.0 * zoom) as u32))
+            .on_press_maybe((state.selected_files.len() == 2).then_some(Message::CompareSelectedFiles))
+            .padding(6.0 * zoom)
+            .style(button_widget::secondary),
+    ]
+    .align_y(Vertical::Center);
+
+    let folder_name = display_name(folder);
+    container(
+        column![
+            text(format!("Folder: {} ({} files)", folder_name, files.len()))
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            selection,
+            scrollable(files_column)
+                .height(Length::Fixed(250.0 * zoom))
+                .width(Length::Fill),
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Create a metadata row element
+fn create_row<'a>(label: impl Into<String>, value: String, zoom: f32, theme: AppTheme) -> Element<'a, Message> {
+    let label_str = label.into();
+    let text_size = (14.0 * zoom) as u32;
+    // Make labels appear bold by using a slightly larger size (15px instead of 14px)
+    let label_size = ((15.0 * zoom) as u32).max(1);
+    
+    // Theme-aware label color: dark for light theme, light for dark theme
+    let label_color = match theme {
+        AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1), // Dark color for light theme
+        AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),  // Light color for dark theme
+    };
+    
+    row![
+        text(label_str.clone())
+            .size(label_size)
+            .width(Length::Fixed(150.0 * zoom))
+            .style(move |_theme| {
+                iced::widget::text::Style {
+                    // Make labels more prominent (appear bold) with theme-aware color
+                    color: Some(label_color),
+                }
+            }),
+        text(value)
+            .size(text_size)
+            .width(Length::Fill),
+    ]
+    .spacing(10.0 * zoom)
+    .align_y(Vertical::Center)
+    .into()
+}
+
+/// Create a metadata row with an arbitrary element as its value
+fn create_element_row<'a>(label: impl Into<String>, value: Element<'a, Message>, zoom: f32, theme: AppTheme) -> Element<'a, Message> {
+    let label_size = ((15.0 * zoom) as u32).max(1);
+
+    // Theme-aware label color: dark for light theme, light for dark theme
+    let label_color = match theme {
+        AppTheme::Light => iced::Color::from_rgb(0.1, 0.1, 0.1),
+        AppTheme::Dark => iced::Color::from_rgb(0.9, 0.9, 0.9),
+    };
+
+    row![
+        text(label.into())
+            .size(label_size)
+            .width(Length::Fixed(150.0 * zoom))
+            .style(move |_theme| {
+                iced::widget::text::Style {
+                    color: Some(label_color),
+                }
+            }),
+        value,
+    ]
+    .spacing(10.0 * zoom)
+    .align_y(Vertical::Center)
+    .into()
+}
+
+/// Build a 5-star rating widget; clicking the current rating clears it
+fn build_star_rating<'a>(stars: u8, zoom: f32) -> Element<'a, Message> {
+    let mut stars_row = row![].spacing(2.0 * zoom);
+    for star in 1..=5u8 {
+        let label = if star <= stars { "★" } else { "☆" };
+        let new_rating = if star == stars { 0 } else { star };
+        stars_row = stars_row.push(
+            button(text(label).size((18.0 * zoom) as u32))
+                .on_press(Message::RatingChanged(new_rating))
+                .padding(2.0 * zoom)
+                .style(button_widget::text),
+        );
+    }
+    stars_row.into()
+}
+
+/// Create an editable metadata field row
+///
+/// Numeric fields holding text that isn't a number and malformed MusicBrainz IDs are flagged
+/// below the input; MusicBrainz IDs also get a link to their MusicBrainz page.
+fn create_field_row<'a>(
+    field: MetadataField,
+    value: String,
+    issues: Vec<String>,
+    zoom: f32,
+    theme: AppTheme,
+) -> Element<'a, Message> {
+    let text_size = (14.0 * zoom) as u32;
+
+    // Text that can't be stored yet replaces the issues of the stored value
+    let problems = if !field.accepts(&value) {
+        vec!["Must be a number".to_string()]
+    } else {
+        issues
+    };
+
+    let mut input_row = row![
+        text_input(field.label(), &value)
+            .on_input(move |value| Message::FieldChanged(field, value))
+            .size(text_size)
+            .width(Length::Fill),
+    ]
+    .spacing(5.0 * zoom)
+    .align_y(Vertical::Center);
+
+    if let Some(entity) = field.musicbrainz_entity() {
+        let link = musicbrainz_ids(&value)
+            .first()
+            .filter(|id| is_valid_uuid(id))
+            .map(|id| format!("https://musicbrainz.org/{}/{}", entity, id));
+        input_row = input_row.push(
+            button(text("Open in MusicBrainz").size(text_size))
+                .on_press_maybe(link.map(Message::OpenUrl))
+                .padding(5.0 * zoom),
+        );
+    }
+
+    if field.sort_source().is_some() {
+        input_row = input_row.push(
+            button(text("Auto").size(text_size))
+                .on_press(Message::GenerateSortName(field))
+                .padding(5.0 * zoom)
+                .style(button_widget::secondary),
+        );
+    }
+
+    let mut value_column = column![input_row].spacing(2.0 * zoom).width(Length::Fill);
+    for problem in problems {
+        value_column = value_column.push(
+            text(problem)
+                .size(((12.0 * zoom) as u32).max(1))
+                .style(|_theme| {
+                    iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                    }
+                }),
+        );
+    }
+
+    create_element_row(format!("{}:", field.label()), value_column.into(), zoom, theme)
+}
+
+/// Create a metadata row for a field with several values: a chip per value, removed by
+/// clicking it, and an input adding values
+fn create_list_field_row<'a>(
+    field: MetadataField,
+    values: &'a [String],
+    input: &'a str,
+    zoom: f32,
+    theme: AppTheme,
+) -> Element<'a, Message> {
+    let text_size = (14.0 * zoom) as u32;
+
+    let mut chips = row![].spacing(5.0 * zoom);
+    for (index, value) in values.iter().enumerate() {
+        chips = chips.push(
+            button(text(format!("{} ×", value)).size(text_size))
+                .on_press(Message::RemoveListValue(field, index))
+                .padding(5.0 * zoom)
+                .style(button_widget::secondary),
+        );
+    }
+    let can_add = !input.trim().is_empty();
+    chips = chips.push(
+        text_input(&format!("Add {}", field.label().to_lowercase()), input)
+            .on_input(move |value| Message::ListInputChanged(field, value))
+            .on_submit_maybe(can_add.then_some(Message::AddListValues(field)))
+            .size(text_size)
+            .width(Length::Fixed(200.0 * zoom)),
+    );
+    chips = chips.push(
+        button(text("Add").size(text_size))
+            .on_press_maybe(can_add.then_some(Message::AddListValues(field)))
+            .padding(5.0 * zoom),
+    );
+
+    create_element_row(
+        format!("{}:", field.label()),
+        chips.wrap().vertical_spacing(5.0 * zoom).into(),
+        zoom,
+        theme,
+    )
+}
+
+/// Add a numeric field if it exists
+fn add_numeric_field<'a>(rows: Column<'a, Message>, label: &'a str, value: Option<u32>, zoom: f32, theme: AppTheme) -> Column<'a, Message> {
+    if let Some(val) = value {
+        rows.push(create_row(label, val.to_string(), zoom, theme))
+    } else {
+        rows
+    }
+}
+
+/// Determine the file extension based on the image format
+fn determine_image_extension(format: &Option<String>) -> &'static str {
+    match format.as_deref() {
+        Some("image/jpeg") | Some("image/jpg") => "jpg",
+        Some("image/png") => "png",
+        Some("image/gif") => "gif",
+        Some("image/webp") => "webp",
+        _ => "jpg", // Default to jpg if format is unknown
+    }
+}
+
+/// Generate a unique filename for the cover image based on track metadata
+fn generate_cover_filename(metadata: &AudioMetadata) -> String {
+    // Create a hash from track metadata to ensure uniqueness
+    let mut hasher = DefaultHasher::new();
+    
+    // Use title and artist if available, otherwise use a hash of all metadata
+    let identifier = if let (Some(title), Some(artist)) = (&metadata.title, metadata.artists.first()) {
+        format!("{}_{}", sanitize_filename(title), sanitize_filename(artist))
+    } else if let Some(title) = &metadata.title {
+        sanitize_filename(title)
+    } else if let Some(artist) = metadata.artists.first() {
+        sanitize_filename(artist)
+    } else {
+        // Fallback: hash the cover data or use a timestamp
+        format!("cover_{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs())
+    };
+    
+    identifier.hash(&mut hasher);
+    let hash = hasher.finish();
+    
+    // Limit filename length and add hash for uniqueness
+    let mut filename = identifier.chars().take(50).collect::<String>();
+    filename.push_str(&format!("_{:x}.jpg", hash));
+    
+    filename
+}
+
+/// Create a default cover image placeholder
+fn create_default_cover<'a>() -> Element<'a, Message> {
+    container(
+        column![
+            text("🎵")
+                .size(80)
+                .align_x(Horizontal::Center),
+            text("No Cover")
+                .size(16)
+                .align_x(Horizontal::Center)
+                .style(|_theme| {
+                    iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+                    }
+                }),
+        ]
+        .spacing(10)
+        .align_x(Horizontal::Center)
+        .width(Length::Fill)
+        .height(Length::Fill)
+    )
+    .width(Length::Fixed(200.0))
+    .height(Length::Fixed(200.0))
+    .center_x(Length::Fill)
+    .center_y(Length::Fill)
+    .style(|theme| {
+        container::Style {
+            background: Some(iced::Color::from_rgb(0.2, 0.2, 0.2).into()),
+            border: iced::Border {
+                width: 1.0,
+                color: iced::Color::from_rgb(0.4, 0.4, 0.4),
+                radius: 4.0.into(),
+            },
+            ..container::rounded_box(theme)
+        }
+    })
+    .into()
+}
@@ -0,0 +1,163 @@
+use crate::id3_parser::{AudioMetadata, ListSeparator};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, text, text_input
+    }
+};
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, display_name, update_tags};
+
+/// Text of the track numbering options, as typed
+#[derive(Debug, Clone)]
+pub struct NumberingInputs {
+    pub(super) start: String,
+    pub(super) disc: String,
+    pub(super) total_discs: String,
+}
+
+impl Default for NumberingInputs {
+    fn default() -> Self {
+        Self {
+            start: "1".to_string(),
+            disc: String::new(),
+            total_discs: String::new(),
+        }
+    }
+}
+
+impl NumberingInputs {
+    /// Parsed options, or None if a value isn't a number (empty disc values are left alone)
+    pub(super) fn parse(&self) -> Option<Numbering> {
+        let optional = |value: &str| -> Option<Option<u32>> {
+            let value = value.trim();
+            if value.is_empty() {
+                Some(None)
+            } else {
+                value.parse().ok().filter(|number| *number > 0).map(Some)
+            }
+        };
+        Some(Numbering {
+            start: self.start.trim().parse().ok().filter(|start| *start > 0)?,
+            disc: optional(&self.disc)?,
+            total_discs: optional(&self.total_discs)?,
+        })
+    }
+}
+
+/// Track and disc numbers to assign to a sequence of files
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Numbering {
+    start: u32,
+    disc: Option<u32>,
+    total_discs: Option<u32>,
+}
+
+impl Numbering {
+    /// Number the file at `index` of `count` files: TRCK `n/total` and, if set, TPOS `d/total`
+    fn apply(&self, index: usize, count: usize, metadata: &mut AudioMetadata) {
+        metadata.track = Some(self.start + index as u32);
+        metadata.total_tracks = Some(self.start + count.saturating_sub(1) as u32);
+        if self.disc.is_some() {
+            metadata.disc = self.disc;
+        }
+        if self.total_discs.is_some() {
+            metadata.total_discs = self.total_discs;
+        }
+    }
+}
+
+/// Async function to number files in the given order
+pub(super) async fn apply_numbering_async(files: Vec<PathBuf>, numbering: Numbering, separator: ListSeparator) -> BatchSummary {
+    update_tags(&files, separator, |path, metadata| {
+        let Some(index) = files.iter().position(|file| file == path) else {
+            return false;
+        };
+        numbering.apply(index, files.len(), metadata);
+        true
+    })
+}
+
+/// Build the track numbering view with the numbers each selected file will get
+pub(super) fn build_numbering_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let inputs = &state.numbering_inputs;
+    let number_input = |placeholder: &str, value: &str, on_input: fn(String) -> Message| {
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .size(text_size)
+            .padding(5.0 * zoom)
+            .width(Length::Fixed(70.0 * zoom))
+    };
+    let options = row![
+        text("Start at").size(text_size),
+        number_input("1", &inputs.start, Message::NumberingStartChanged),
+        text("Disc").size(text_size),
+        number_input("keep", &inputs.disc, Message::NumberingDiscChanged),
+        text("of").size(text_size),
+        number_input("keep", &inputs.total_discs, Message::NumberingTotalDiscsChanged),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let numbering = inputs.parse();
+    let mut preview = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    match numbering {
+        Some(numbering) => {
+            let count = state.selected_files.len();
+            for (index, path) in state.selected_files.iter().enumerate() {
+                let mut numbers = AudioMetadata::default();
+                numbering.apply(index, count, &mut numbers);
+                let mut summary = format!(
+                    "Track {}/{}",
+                    numbers.track.unwrap_or_default(),
+                    numbers.total_tracks.unwrap_or_default()
+                );
+                match (numbers.disc, numbers.total_discs) {
+                    (Some(disc), Some(total)) => summary.push_str(&format!(" · Disc {}/{}", disc, total)),
+                    (Some(disc), None) => summary.push_str(&format!(" · Disc {}", disc)),
+                    (None, Some(total)) => summary.push_str(&format!(" · Disc ?/{}", total)),
+                    (None, None) => {}
+                }
+                preview = preview.push(
+                    row![
+                        text(display_name(path)).size(small_size).width(Length::Fill),
+                        text(summary).size(small_size),
+                    ]
+                    .spacing(spacing),
+                );
+            }
+        }
+        None => {
+            preview = preview.push(text("Numbers must be positive whole numbers").size(small_size).style(|_theme| {
+                iced::widget::text::Style {
+                    color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                }
+            }));
+        }
+    }
+
+    let ready = !state.batch_busy && numbering.is_some() && state.can_write_files();
+    container(
+        column![
+            text("Track Numbering")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            text("Files are numbered in the order they were selected").size(small_size),
+            options,
+            preview,
+            button(text(format!("Number {} files", state.selected_files.len())))
+                .on_press_maybe(ready.then_some(Message::ApplyNumbering))
+                .padding(8.0 * zoom),
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
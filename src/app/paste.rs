@@ -0,0 +1,85 @@
+use crate::id3_parser::snapshot::TagItem;
+use crate::id3_parser::{AudioMetadata, ListSeparator};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        button, column, container, row, text, checkbox
+    }
+};
+use iced::widget::button as button_widget;
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, update_tags};
+
+/// Async function to paste the chosen items of copied tags to files
+pub(super) async fn paste_tags_async(files: Vec<PathBuf>, tags: AudioMetadata, items: Vec<TagItem>, separator: ListSeparator) -> BatchSummary {
+    update_tags(&files, separator, |_, metadata| {
+        for item in &items {
+            item.copy(&tags, metadata);
+        }
+        true
+    })
+}
+
+/// Build the paste view: the items of the copied tags to apply, and the files they go to
+pub(super) fn build_paste_view<'a>(state: &'a State, source: &'a str, tags: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let mut items = row![].spacing(spacing).align_y(Vertical::Center);
+    for item in TagItem::all().filter(|item| item.is_set(tags)) {
+        // Short values are shown, so the checkboxes say what is pasted
+        let value = item.value(tags);
+        let label = if value.is_empty() || value.chars().count() > 40 || item == TagItem::Lyrics {
+            item.label().to_string()
+        } else {
+            format!("{}: {}", item.label(), value)
+        };
+        items = items.push(
+            checkbox(state.paste_items.contains(&item))
+                .label(label)
+                .text_size(small_size)
+                .on_toggle(move |enabled| Message::TogglePasteItem(item, enabled)),
+        );
+    }
+
+    let targets = state.target_files();
+    let in_memory = state.targets_current_file_only(&targets);
+    let ready = !state.paste_items.is_empty()
+        && !targets.is_empty()
+        && (in_memory || (!state.batch_busy && state.can_write_files()));
+    let paste_label = if in_memory { "Paste".to_string() } else { format!("Paste to {} files", targets.len()) };
+    let actions = row![
+        button(text(paste_label).size(text_size))
+            .on_press_maybe(ready.then_some(Message::PasteTags))
+            .padding(8.0 * zoom),
+        button(text("All").size(text_size))
+            .on_press(Message::SelectPasteItems(true))
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+        button(text("None").size(text_size))
+            .on_press(Message::SelectPasteItems(false))
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+        button(text("Discard").size(text_size))
+            .on_press(Message::DiscardCopiedTags)
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+    ]
+    .spacing(spacing);
+
+    container(
+        column![
+            text("Paste Tags")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            text(format!("Copied from {}", source)).size(small_size),
+            items.wrap().vertical_spacing(5.0 * zoom),
+            actions,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
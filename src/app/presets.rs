@@ -0,0 +1,203 @@
+use crate::id3_parser::preset::TagPreset;
+use crate::id3_parser::fields::{FieldChange, MetadataField};
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator, ParseError};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, scrollable, text, text_input, pick_list
+    }
+};
+use iced::widget::button as button_widget;
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, display_name, update_tags};
+
+/// Build the tag preset editor of the settings overlay (fixed sizes, like the rest of it)
+pub(super) fn build_preset_editor(state: &State) -> Element<'_, Message> {
+    const SPACING: f32 = 10.0;
+    const SMALL_SIZE: u32 = 12;
+
+    let names: Vec<String> = state.tag_presets.iter().map(|preset| preset.name.clone()).collect();
+    let selected = state.selected_preset().map(|preset| preset.name.clone());
+    let mut editor = column![
+        row![
+            pick_list(names, selected, Message::PresetSelected)
+                .placeholder("No presets")
+                .width(Length::Fill),
+            button("New").on_press(Message::NewPreset),
+            button("Delete")
+                .on_press_maybe(state.selected_preset().is_some().then_some(Message::DeletePreset))
+                .style(button_widget::secondary),
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center),
+    ]
+    .spacing(SPACING)
+    .width(Length::Fill);
+
+    if let Some(preset) = state.selected_preset() {
+        let mut values = Column::new().spacing(5.0).width(Length::Fill);
+        for (index, value) in preset.values.iter().enumerate() {
+            values = values.push(
+                row![
+                    pick_list(MetadataField::ALL, MetadataField::from_name(&value.field), move |field| {
+                        Message::PresetFieldChanged(index, field)
+                    })
+                    .text_size(SMALL_SIZE)
+                    .width(Length::Fixed(140.0)),
+                    text_input("Value, e.g. ℗ {year} {publisher}", &value.value)
+                        .on_input(move |text| Message::PresetValueChanged(index, text))
+                        .size(SMALL_SIZE)
+                        .padding(5.0)
+                        .width(Length::Fill),
+                    button(text("×").size(SMALL_SIZE))
+                        .on_press(Message::RemovePresetValue(index))
+                        .padding(5.0)
+                        .style(button_widget::secondary),
+                ]
+                .spacing(5.0)
+                .align_y(Vertical::Center),
+            );
+        }
+        editor = editor.push(
+            text_input("Name", &preset.name)
+                .on_input(Message::PresetNameChanged)
+                .padding(5.0),
+        );
+        editor = editor.push(container(scrollable(values)).max_height(200.0));
+        editor = editor.push(
+            text("Values can use {field} names, {filename} and {parent_dir}; an empty value clears the field")
+                .size(SMALL_SIZE),
+        );
+        editor = editor.push(
+            button(text("Add Field").size(SMALL_SIZE))
+                .on_press(Message::AddPresetValue)
+                .style(button_widget::secondary),
+        );
+    }
+
+    editor.into()
+}
+
+/// Async function to apply a tag preset to files
+pub(super) async fn apply_preset_async(files: Vec<PathBuf>, preset: TagPreset, separator: ListSeparator) -> BatchSummary {
+    update_tags(&files, separator, |path, metadata| match preset.apply(metadata, path) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Could not apply preset {:?} to {:?}: {}", preset.name, path, e);
+            false
+        }
+    })
+}
+
+/// Async function to preview the changes a tag preset makes to a file (an untagged file gets a new tag)
+pub(super) async fn preview_preset_async(path: PathBuf, preset: TagPreset, separator: ListSeparator) -> Result<Vec<FieldChange>, String> {
+    let metadata = match parse_id3(&path, separator) {
+        Ok(metadata) => metadata,
+        Err(ParseError::NoId3Tag) => AudioMetadata::default(),
+        Err(e) => return Err(e.to_string()),
+    };
+    preset.changes(&metadata, &path)
+}
+
+/// Build the tag preset view: the values of the chosen preset and the files they go to
+pub(super) fn build_preset_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let names: Vec<String> = state.tag_presets.iter().map(|preset| preset.name.clone()).collect();
+    let selected = state.selected_preset().map(|preset| preset.name.clone());
+    let targets = state.target_files();
+    let in_memory = state.targets_current_file_only(&targets);
+    let ready = state.selected_preset().is_some_and(|preset| !preset.values.is_empty())
+        && !targets.is_empty()
+        && (in_memory || (!state.batch_busy && state.can_write_files()));
+    let apply_label = if in_memory { "Apply".to_string() } else { format!("Apply to {} files", targets.len()) };
+    let mut picker = row![
+        text("Preset").size(text_size),
+        pick_list(names, selected, Message::PresetSelected)
+            .placeholder("No presets")
+            .text_size(text_size)
+            .width(Length::Fixed(220.0 * zoom)),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+    if !in_memory {
+        picker = picker.push(
+            button(text("Preview").size(text_size))
+                .on_press_maybe(ready.then_some(Message::PreviewPreset))
+                .padding(8.0 * zoom),
+        );
+    }
+    picker = picker.push(
+        button(text(apply_label).size(text_size))
+            .on_press_maybe(ready.then_some(Message::ApplyPreset))
+            .padding(8.0 * zoom),
+    );
+
+    let mut values = Column::new().spacing(4.0 * zoom).width(Length::Fill);
+    match state.selected_preset() {
+        Some(preset) => {
+            for (field, value) in preset.fields() {
+                values = values.push(
+                    row![
+                        text(field.label()).size(small_size).width(Length::Fixed(150.0 * zoom)),
+                        text(if value.is_empty() { "(cleared)" } else { value }).size(small_size),
+                    ]
+                    .spacing(spacing),
+                );
+            }
+        }
+        None => values = values.push(text("Presets are managed in the settings (⚙️)").size(small_size)),
+    }
+
+    // The loaded file is previewed as it's edited, other files when asked
+    let mut preview = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    match &state.preset_preview {
+        Ok(files) => {
+            for (path, changes) in files {
+                preview = preview.push(text(display_name(path)).size(text_size));
+                for change in changes {
+                    preview = preview.push(
+                        row![
+                            text(change.field.label()).size(small_size).width(Length::Fixed(110.0 * zoom)),
+                            text(change.before.as_str())
+                                .size(small_size)
+                                .width(Length::Fill)
+                                .style(|_theme| iced::widget::text::Style {
+                                    color: Some(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+                                }),
+                            text("→").size(small_size),
+                            text(change.after.as_str()).size(small_size).width(Length::Fill),
+                        ]
+                        .spacing(spacing),
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            preview = preview.push(text(format!("Could not preview the preset: {}", e)).size(small_size).style(|_theme| {
+                iced::widget::text::Style {
+                    color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                }
+            }));
+        }
+    }
+
+    container(
+        column![
+            text("Tag Presets")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            picker,
+            values,
+            preview,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,153 @@
+use crate::config::get_rename_log_path;
+use crate::rename::{apply_renames, plan_renames, MoveReport, PlannedRename, RenameStatus, UndoLog};
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator, ParseError};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        Column, button, column, container, row, text, text_input
+    }
+};
+use std::path::PathBuf;
+use super::{Message, Sizes, State, display_name};
+
+/// Async function to plan the renames of several files from their tags
+pub(super) async fn plan_renames_async(
+    files: Vec<PathBuf>,
+    template: String,
+    destination: Option<PathBuf>,
+    separator: ListSeparator,
+) -> Result<Vec<PlannedRename>, String> {
+    let mut tagged = Vec::new();
+    for path in files {
+        let metadata = match parse_id3(&path, separator) {
+            Ok(metadata) => metadata,
+            Err(ParseError::NoId3Tag) => AudioMetadata::default(),
+            Err(e) => return Err(format!("{}: {}", display_name(&path), e)),
+        };
+        tagged.push((path, metadata));
+    }
+    plan_renames(&tagged, &template, destination.as_deref())
+}
+
+/// Async function to move files as planned and record the moves in the undo log
+pub(super) async fn apply_renames_async(plan: Vec<PlannedRename>) -> Result<MoveReport, String> {
+    let report = apply_renames(&plan);
+    let log_path = get_rename_log_path().ok_or("could not determine the undo log path")?;
+    UndoLog::load(log_path).record(&report.moves)?;
+    Ok(report)
+}
+
+/// Async function to revert the most recent rename
+pub(super) async fn undo_rename_async() -> Result<MoveReport, String> {
+    let log_path = get_rename_log_path().ok_or("could not determine the undo log path")?;
+    UndoLog::load(log_path).undo_last()
+}
+
+/// Build the rename view with the template and a preview table of the planned moves
+pub(super) fn build_rename_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let template_input = text_input("{album_artist}/{year} - {album}/{disc}{track:02} {title}.{ext}", &state.rename_template)
+        .on_input(Message::RenameTemplateChanged)
+        .size(text_size)
+        .padding(5.0 * zoom);
+
+    let destination_label = state
+        .rename_destination
+        .as_ref()
+        .map_or_else(|| "Each file's own folder".to_string(), |path| path.to_string_lossy().to_string());
+    let destination = row![
+        text(format!("Destination: {}", destination_label))
+            .size(text_size)
+            .width(Length::Fill),
+        button("Choose...")
+            .on_press(Message::ChooseRenameDestination)
+            .padding(5.0 * zoom),
+        button("Reset")
+            .on_press_maybe(state.rename_destination.is_some().then_some(Message::ClearRenameDestination))
+            .padding(5.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    // The preview only applies while the target files are the ones it was made for
+    let targets = state.target_files();
+    let plan_current = !state.rename_plan.is_empty()
+        && state.rename_plan.iter().map(|entry| &entry.source).eq(targets.iter());
+
+    let mut table = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    let mut ready = 0;
+    if plan_current {
+        let base = state.rename_destination.as_deref();
+        for entry in &state.rename_plan {
+            let (status, color) = match &entry.status {
+                RenameStatus::Ready => {
+                    ready += 1;
+                    ("Ready".to_string(), iced::Color::from_rgb(0.3, 0.8, 0.4))
+                }
+                RenameStatus::Unchanged => ("Unchanged".to_string(), iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                RenameStatus::Conflict(reason) => (reason.clone(), iced::Color::from_rgb(1.0, 0.3, 0.3)),
+            };
+            let target = base
+                .or_else(|| entry.source.parent())
+                .and_then(|base| entry.target.strip_prefix(base).ok())
+                .unwrap_or(&entry.target);
+            table = table.push(
+                row![
+                    text(display_name(&entry.source))
+                        .size(small_size)
+                        .width(Length::FillPortion(2)),
+                    text("→").size(small_size),
+                    text(target.to_string_lossy().to_string())
+                        .size(small_size)
+                        .width(Length::FillPortion(3)),
+                    text(status)
+                        .size(small_size)
+                        .width(Length::FillPortion(1))
+                        .style(move |_theme| iced::widget::text::Style { color: Some(color) }),
+                ]
+                .spacing(spacing)
+                .align_y(Vertical::Center),
+            );
+        }
+    }
+
+    let idle = !state.batch_busy;
+    // Renaming reads the tags on disk, so pending edits must be saved first
+    let can_rename = idle && plan_current && ready > 0 && !state.unsaved_changes;
+    let actions = row![
+        button("Preview")
+            .on_press_maybe((idle && !targets.is_empty()).then_some(Message::PreviewRenames))
+            .padding(8.0 * zoom),
+        button(text(format!("Rename {} files", ready)))
+            .on_press_maybe(can_rename.then_some(Message::ApplyRenames))
+            .padding(8.0 * zoom),
+        button("Undo Last Rename")
+            .on_press_maybe((idle && state.rename_undo_available).then_some(Message::UndoRename))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    container(
+        column![
+            text("Rename Files from Tags")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            template_input,
+            text("Fields in {braces}, {track:02} pads with zeros, plus {ext}, {filename} and {parent_dir}; / creates folders")
+                .size(small_size),
+            destination,
+            actions,
+            table,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
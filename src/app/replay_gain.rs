@@ -0,0 +1,127 @@
+use crate::audio::replaygain::{album_loudness, analyze_track, gain_for_loudness, TrackLoudness};
+use crate::id3_parser::{supports_id3, AudioMetadata, ListSeparator, ReplayGain};
+use iced::{
+    Element, Length, alignment::{Horizontal, Vertical}, widget::{
+        button, column, container, row, text, checkbox
+    }
+};
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, create_row, display_name, update_tags};
+
+/// Async function to compute the track ReplayGain of a file, keeping its album values
+pub(super) async fn analyze_track_replay_gain_async(path: PathBuf, existing: ReplayGain, rva2: bool) -> Result<ReplayGain, String> {
+    let track = analyze_track(&path).map_err(|e| e.to_string())?;
+    Ok(ReplayGain {
+        track_gain: Some(track.gain()),
+        track_peak: Some(track.peak),
+        rva2,
+        ..existing
+    })
+}
+
+/// Async function to compute track and album ReplayGain for a folder and write it to every file
+pub(super) async fn apply_album_replay_gain_async(files: Vec<PathBuf>, rva2: bool, separator: ListSeparator) -> Result<(f64, BatchSummary), String> {
+    let mut paths = Vec::new();
+    let mut tracks: Vec<TrackLoudness> = Vec::new();
+    let mut failed = Vec::new();
+    for path in files {
+        // Files without ID3 support can't take the values, so they don't count towards the album
+        if !supports_id3(&path) {
+            log::warn!("Skipping {:?} in album analysis: no ID3 support", path);
+            failed.push(display_name(&path));
+            continue;
+        }
+        match analyze_track(&path) {
+            Ok(track) => {
+                paths.push(path);
+                tracks.push(track);
+            }
+            Err(e) => {
+                log::warn!("Skipping {:?} in album analysis: {}", path, e);
+                failed.push(display_name(&path));
+            }
+        }
+    }
+    if tracks.is_empty() {
+        return Err("no file could be decoded".to_string());
+    }
+
+    let (album_loudness_lufs, album_peak) = album_loudness(&tracks).map_err(|e| e.to_string())?;
+    let album_gain = gain_for_loudness(album_loudness_lufs);
+
+    let mut summary = update_tags(&paths, separator, |path, metadata| {
+        let Some(track) = paths.iter().position(|p| p == path).map(|index| &tracks[index]) else {
+            return false;
+        };
+        metadata.replay_gain = ReplayGain {
+            track_gain: Some(track.gain()),
+            track_peak: Some(track.peak),
+            album_gain: Some(album_gain),
+            album_peak: Some(album_peak),
+            rva2,
+        };
+        true
+    });
+    summary.failed.extend(failed);
+
+    Ok((album_gain, summary))
+}
+
+/// Build the ReplayGain view with the stored values and analysis actions
+pub(super) fn build_replay_gain_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let theme = state.theme;
+    let Sizes { title: title_size, spacing, padding, .. } = Sizes::new(zoom);
+
+    let replay_gain = &metadata.replay_gain;
+    let format_gain = |gain: Option<f64>| gain.map_or_else(|| "-".to_string(), |gain| format!("{:+.2} dB", gain));
+    let format_peak = |peak: Option<f64>| peak.map_or_else(|| "-".to_string(), |peak| format!("{:.6}", peak));
+
+    let values = column![
+        create_row("Track Gain:", format_gain(replay_gain.track_gain), zoom, theme),
+        create_row("Track Peak:", format_peak(replay_gain.track_peak), zoom, theme),
+        create_row("Album Gain:", format_gain(replay_gain.album_gain), zoom, theme),
+        create_row("Album Peak:", format_peak(replay_gain.album_peak), zoom, theme),
+        create_row("RVA2:", if replay_gain.rva2 { "Present" } else { "Not present" }.to_string(), zoom, theme),
+    ]
+    .spacing(5.0 * zoom);
+
+    let idle = !state.replay_gain_busy;
+    let album_ready = idle && !state.folder_files.is_empty() && state.can_write_files();
+
+    let mut actions = row![
+        button("Analyze Track")
+            .on_press_maybe((idle && state.file_path.is_some()).then_some(Message::AnalyzeTrackReplayGain))
+            .padding(8.0 * zoom),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+    if state.folder_path.is_some() {
+        actions = actions.push(
+            button(text(format!("Analyze Album ({} files)", state.folder_files.len())))
+                .on_press_maybe(album_ready.then_some(Message::AnalyzeAlbumReplayGain))
+                .padding(8.0 * zoom),
+        );
+    }
+    actions = actions.push(
+        checkbox(state.write_rva2)
+            .label("Also write RVA2")
+            .on_toggle(Message::ToggleRva2),
+    );
+
+    container(
+        column![
+            text("ReplayGain")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            values,
+            actions,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
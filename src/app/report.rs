@@ -0,0 +1,162 @@
+use crate::report::{build_report, ReportFormat, ReportOptions, TagReport};
+use crate::id3_parser::fields::MetadataField;
+use crate::id3_parser::ListSeparator;
+use iced::{
+    Element, Length, alignment::Vertical, widget::{
+        Column, button, column, container, row, scrollable, text, text_input, checkbox
+    }
+};
+use iced::widget::button as button_widget;
+use std::path::PathBuf;
+use super::{Message, Sizes, State, display_name, list_audio_files_recursive};
+
+/// Most problems the tag report lists on screen (exports have all of them)
+const MAX_REPORT_ROWS: usize = 500;
+
+/// Async function to check the tags of the audio files below a folder
+pub(super) async fn build_report_async(folder: PathBuf, options: ReportOptions, separator: ListSeparator) -> Result<TagReport, String> {
+    let files = list_audio_files_recursive(&folder)?;
+    if files.is_empty() {
+        return Err("no audio files found".to_string());
+    }
+    Ok(build_report(&folder, &files, &options, separator))
+}
+
+/// Async function to choose where to export the tag report
+pub(super) async fn save_report_dialog(format: ReportFormat, file_name: String) -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(file_name)
+        .save_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Async function to write an exported tag report
+pub(super) async fn export_report_async(path: PathBuf, content: String) -> Result<PathBuf, String> {
+    std::fs::write(&path, content)
+        .map(|_| path)
+        .map_err(|e| e.to_string())
+}
+
+/// Build the tag health report: its options, a summary and the problems found
+pub(super) fn build_report_view(state: &State) -> Element<'_, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let options = &state.report_options;
+    let required = options.required();
+    let mut fields = row![text("Required:").size(text_size)]
+        .spacing(spacing)
+        .align_y(Vertical::Center);
+    for field in MetadataField::ALL {
+        fields = fields.push(
+            checkbox(required.contains(&field))
+                .label(field.label())
+                .text_size(small_size)
+                .on_toggle(move |enabled| Message::ToggleReportField(field, enabled)),
+        );
+    }
+
+    let size_input = |value: u32, change: fn(&mut ReportOptions, u32)| {
+        let options = options.clone();
+        text_input("px", &value.to_string())
+            .on_input(move |value| {
+                let mut options = options.clone();
+                let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+                change(&mut options, digits.parse().unwrap_or(0));
+                Message::ReportOptionsChanged(options)
+            })
+            .size(text_size)
+            .padding(5.0 * zoom)
+            .width(Length::Fixed(80.0 * zoom))
+    };
+    let cover_sizes = row![
+        text("Cover art from").size(text_size),
+        size_input(options.min_cover_size, |options, size| options.min_cover_size = size),
+        text("to").size(text_size),
+        size_input(options.max_cover_size, |options, size| options.max_cover_size = size),
+        text("px").size(text_size),
+    ]
+    .spacing(spacing)
+    .align_y(Vertical::Center);
+
+    let label = if state.report_busy { "Scanning..." } else { "Scan Folder..." };
+    let rescan = state.report.as_ref().map(|report| Message::ReportFolderSelected(Some(report.folder.clone())));
+    let has_report = state.report.is_some();
+    let actions = row![
+        button(text(label).size(text_size))
+            .on_press_maybe((!state.report_busy).then_some(Message::ReportFolderDialog))
+            .padding(8.0 * zoom),
+        button(text("Rescan").size(text_size))
+            .on_press_maybe(rescan.filter(|_| !state.report_busy))
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+        button(text("Export CSV").size(text_size))
+            .on_press_maybe(has_report.then_some(Message::ExportReportDialog(ReportFormat::Csv)))
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+        button(text("Export HTML").size(text_size))
+            .on_press_maybe(has_report.then_some(Message::ExportReportDialog(ReportFormat::Html)))
+            .padding(8.0 * zoom)
+            .style(button_widget::secondary),
+    ]
+    .spacing(spacing);
+
+    let mut report_column = column![
+        text("Tag Report").size(title_size),
+        fields.wrap().vertical_spacing(5.0 * zoom),
+        cover_sizes,
+        actions,
+    ]
+    .spacing(spacing)
+    .width(Length::Fill);
+
+    if let Some(ref report) = state.report {
+        report_column = report_column.push(
+            text(format!("{}: {}", report.folder.to_string_lossy(), report.summary()))
+            .size(text_size),
+        );
+        let counts: Vec<String> = report
+            .counts()
+            .into_iter()
+            .map(|(kind, count)| format!("{}: {}", kind.label(), count))
+            .collect();
+        if !counts.is_empty() {
+            report_column = report_column.push(text(counts.join(" · ")).size(small_size));
+        }
+
+        let mut problems = Column::new().spacing(4.0 * zoom).width(Length::Fill);
+        for problem in report.problems.iter().take(MAX_REPORT_ROWS) {
+            problems = problems.push(
+                row![
+                    button(text(display_name(&problem.path)).size(small_size))
+                        .on_press(Message::FileSelected(Some(problem.path.clone())))
+                        .padding(4.0 * zoom)
+                        .style(button_widget::text)
+                        .width(Length::FillPortion(3)),
+                    text(problem.kind.label()).size(small_size).width(Length::FillPortion(2)),
+                    text(&problem.details).size(small_size).width(Length::FillPortion(4)),
+                ]
+                .spacing(spacing)
+                .align_y(Vertical::Center),
+            );
+        }
+        if report.problems.len() > MAX_REPORT_ROWS {
+            problems = problems.push(
+                text(format!(
+                    "...and {} more (export the report for the full list)",
+                    report.problems.len() - MAX_REPORT_ROWS
+                ))
+                .size(small_size),
+            );
+        }
+        report_column = report_column.push(scrollable(problems).height(Length::Fixed(300.0 * zoom)).width(Length::Fill));
+    }
+
+    container(report_column)
+        .padding(padding)
+        .style(container::rounded_box)
+        .width(Length::Fill)
+        .into()
+}
@@ -0,0 +1,121 @@
+use crate::id3_parser::version::{convert_file, preview_conversion, ConversionReport};
+use crate::id3_parser::{AudioMetadata, ListSeparator, ParseError, TagVersion};
+use iced::{Element, Length, alignment::Horizontal, widget::{Column, button, column, container, row, text}};
+use std::path::PathBuf;
+use super::{BatchSummary, Message, Sizes, State, create_row, display_name};
+
+/// Async function to report what converting several files to a tag version would do
+///
+/// Files without a tag are left out, as new tags are written as ID3v2.4 anyway.
+pub(super) async fn preview_conversion_async(
+    files: Vec<PathBuf>,
+    target: TagVersion,
+    separator: ListSeparator,
+) -> Result<Vec<(PathBuf, ConversionReport)>, String> {
+    let mut preview = Vec::new();
+    for path in files {
+        match preview_conversion(&path, target, separator) {
+            Ok(report) => preview.push((path, report)),
+            Err(ParseError::NoId3Tag) => continue,
+            Err(e) => return Err(format!("{}: {}", display_name(&path), e)),
+        }
+    }
+    Ok(preview)
+}
+
+/// Async function to convert the tags of several files to another version
+pub(super) async fn convert_files_async(files: Vec<PathBuf>, target: TagVersion, separator: ListSeparator) -> BatchSummary {
+    let mut summary = BatchSummary::default();
+    for path in files {
+        match convert_file(&path, target, separator) {
+            Ok(_) => summary.written += 1,
+            Err(e) => {
+                log::warn!("Could not convert {:?} to {}: {}", path, target, e);
+                summary.failed.push(display_name(&path));
+            }
+        }
+    }
+    summary
+}
+
+/// Build the tag version view with the conversion actions and the report of a pending conversion
+pub(super) fn build_version_view<'a>(state: &'a State, metadata: &'a AudioMetadata) -> Element<'a, Message> {
+    let zoom = state.zoom;
+    let Sizes { title: title_size, text: text_size, small: small_size, spacing, padding } = Sizes::new(zoom);
+
+    let version = metadata
+        .tag_version
+        .map_or_else(|| "No tag yet (saved as ID3v2.4)".to_string(), |version| version.to_string());
+
+    let targets = state.target_files();
+    let in_memory = state.targets_current_file_only(&targets);
+    let idle = !state.batch_busy && state.can_write_files() && !targets.is_empty();
+    let convert_button = |label: &'static str, target: TagVersion| {
+        let needed = !in_memory || metadata.tag_version.is_some_and(|version| version != target);
+        button(label)
+            .on_press_maybe((idle && needed).then_some(Message::PreviewConversion(target)))
+            .padding(8.0 * zoom)
+    };
+    let actions = row![
+        convert_button("Convert to v2.3", TagVersion::Id3v23),
+        convert_button("Convert to v2.4", TagVersion::Id3v24),
+    ]
+    .spacing(spacing);
+
+    let mut report_column = Column::new()
+        .spacing(5.0 * zoom)
+        .width(Length::Fill);
+    if let Some((target, preview)) = &state.conversion_preview {
+        let pending: Vec<&(PathBuf, ConversionReport)> =
+            preview.iter().filter(|(_, report)| !report.is_empty()).collect();
+        let already = preview.len() - pending.len();
+        if already > 0 {
+            report_column = report_column.push(text(format!("{} files are already {}", already, target)).size(small_size));
+        }
+        for (path, report) in &pending {
+            report_column = report_column.push(
+                text(format!("{}: {} → {}", display_name(path), report.from, report.to)).size(text_size),
+            );
+            for mapping in &report.mapped {
+                report_column = report_column.push(text(format!("  {}", mapping)).size(small_size));
+            }
+            for dropped in &report.dropped {
+                report_column = report_column.push(
+                    text(format!("  Dropped: {}", dropped))
+                        .size(small_size)
+                        .style(|_theme| iced::widget::text::Style {
+                            color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                        }),
+                );
+            }
+        }
+        report_column = report_column.push(
+            row![
+                button(text(format!("Convert {} files", pending.len())))
+                    .on_press_maybe((!state.batch_busy && !pending.is_empty()).then_some(Message::ApplyConversion))
+                    .padding(8.0 * zoom),
+                button("Cancel")
+                    .on_press(Message::CancelConversion)
+                    .padding(8.0 * zoom),
+            ]
+            .spacing(spacing),
+        );
+    }
+
+    container(
+        column![
+            text("Tag Version")
+                .size(title_size)
+                .align_x(Horizontal::Center),
+            create_row("Version:", version, zoom, state.theme),
+            actions,
+            report_column,
+        ]
+        .spacing(spacing)
+        .width(Length::Fill),
+    )
+    .padding(padding)
+    .style(container::rounded_box)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,126 @@
+use super::DecodeError;
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, Track};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Sample rate and channel count of a decoded stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: usize,
+}
+
+/// Decode the first audio track of a file, passing interleaved `f32` samples to `on_samples`
+///
+/// Returns the format of the decoded stream. Corrupt packets are skipped rather than aborting
+/// the whole decode, as most players do.
+pub fn decode_file<P: AsRef<Path>>(
+    path: P,
+    mut on_samples: impl FnMut(AudioFormat, &[f32]),
+) -> Result<AudioFormat, DecodeError> {
+    let path_ref = path.as_ref();
+
+    log::debug!("Decoding audio from: {:?}", path_ref);
+
+    let mut format = open_format(path_ref)?;
+    let track = audio_track(format.as_ref())?;
+    let track_id = track.id;
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| DecodeError::UnsupportedFormat(e.to_string()))?;
+
+    let mut audio_format = None;
+    let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // End of stream
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(DecodeError::DecodeFailed(e.to_string())),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(msg)) => {
+                log::debug!("Skipping corrupt packet: {}", msg);
+                continue;
+            }
+            Err(e) => return Err(DecodeError::DecodeFailed(e.to_string())),
+        };
+
+        let spec = *decoded.spec();
+        let current_format = AudioFormat {
+            sample_rate: spec.rate,
+            channels: spec.channels.count(),
+        };
+        audio_format = Some(current_format);
+
+        // Reuse the sample buffer unless the packet is larger than what it can hold
+        let buffer = match sample_buffer {
+            Some(ref mut buffer) if buffer.capacity() >= decoded.capacity() * current_format.channels => buffer,
+            _ => sample_buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        on_samples(current_format, buffer.samples());
+    }
+
+    audio_format.ok_or_else(|| DecodeError::DecodeFailed("no audio frames decoded".to_string()))
+}
+
+/// Duration of the first audio track of a file in seconds, as stated by its container
+///
+/// Only the headers are read, so this is fast, but not every file states its length
+/// (e.g. MP3 files without a Xing/Info header may not).
+pub fn probe_duration<P: AsRef<Path>>(path: P) -> Result<Option<u32>, DecodeError> {
+    let format = open_format(path.as_ref())?;
+    let params = &audio_track(format.as_ref())?.codec_params;
+    let duration = match (params.n_frames, params.time_base, params.sample_rate) {
+        (Some(frames), Some(time_base), _) => Some(time_base.calc_time(frames).seconds),
+        (Some(frames), None, Some(sample_rate)) if sample_rate > 0 => Some(frames / u64::from(sample_rate)),
+        _ => None,
+    };
+    Ok(duration.map(|seconds| u32::try_from(seconds).unwrap_or(u32::MAX)))
+}
+
+/// Open a file and probe its container format
+fn open_format(path: &Path) -> Result<Box<dyn FormatReader>, DecodeError> {
+    if !path.exists() {
+        log::error!("File not found: {:?}", path);
+        return Err(DecodeError::FileNotFound);
+    }
+
+    let file = File::open(path).map_err(|e| DecodeError::IoError(e.to_string()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| DecodeError::UnsupportedFormat(e.to_string()))?;
+    Ok(probed.format)
+}
+
+/// The first track of a container that holds audio
+fn audio_track(format: &dyn FormatReader) -> Result<&Track, DecodeError> {
+    format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| DecodeError::UnsupportedFormat("no audio track".to_string()))
+}
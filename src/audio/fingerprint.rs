@@ -0,0 +1,286 @@
+use super::decode::decode_file;
+use super::DecodeError;
+use base64::alphabet::URL_SAFE;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use rusty_chromaprint::{Configuration, FingerprintCompressor, Fingerprinter};
+use std::path::Path;
+
+/// Length of audio that is fingerprinted, in seconds (the `fpcalc` / AcoustID default)
+pub const FINGERPRINT_LENGTH: u32 = 120;
+
+/// Similarity above which two fingerprints are considered the same recording
+pub const DUPLICATE_THRESHOLD: f64 = 0.85;
+
+/// Maximum alignment shift tried when comparing fingerprints, in items (~5 seconds)
+const MAX_ALIGN_OFFSET: isize = 40;
+
+/// Minimum number of overlapping items for a meaningful comparison
+const MIN_OVERLAP: usize = 20;
+
+/// Unpadded URL-safe base64 used by Chromaprint for encoded fingerprints (padding is accepted)
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// A Chromaprint acoustic fingerprint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub duration: u32, // Duration of the whole track in seconds (0 if unknown)
+    pub data: Vec<u32>, // Raw sub-fingerprints
+}
+
+impl Fingerprint {
+    /// Encode the fingerprint as a compressed, base64 encoded string (as printed by `fpcalc`)
+    pub fn encode(&self) -> String {
+        let config = Configuration::default();
+        let compressed = FingerprintCompressor::from(&config).compress(&self.data);
+        BASE64.encode(compressed)
+    }
+
+    /// Decode a compressed, base64 encoded fingerprint
+    pub fn decode(encoded: &str, duration: u32) -> Option<Self> {
+        // Fingerprints in the standard alphabet (`+/`) are accepted as well
+        let bytes = BASE64.decode(encoded.trim().replace('+', "-").replace('/', "_")).ok()?;
+        let data = decompress(&bytes)?;
+        Some(Self { duration, data })
+    }
+
+    /// Similarity to another fingerprint, from 0.0 (unrelated) to 1.0 (identical)
+    ///
+    /// The fingerprints are aligned on the offset with the fewest differing bits, so tracks
+    /// with a few seconds of extra silence still compare as equal.
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        let mut best = 0.0;
+        for offset in -MAX_ALIGN_OFFSET..=MAX_ALIGN_OFFSET {
+            let (a, b) = if offset >= 0 {
+                (self.data.get(offset as usize..), Some(&other.data[..]))
+            } else {
+                (Some(&self.data[..]), other.data.get(offset.unsigned_abs()..))
+            };
+            let (Some(a), Some(b)) = (a, b) else {
+                continue;
+            };
+            let overlap = a.len().min(b.len());
+            if overlap < MIN_OVERLAP {
+                continue;
+            }
+            let differing: u32 = a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum();
+            let similarity = 1.0 - f64::from(differing) / (32.0 * overlap as f64);
+            if similarity > best {
+                best = similarity;
+            }
+        }
+        best
+    }
+}
+
+/// Decode the first `FINGERPRINT_LENGTH` seconds of a file and compute its fingerprint
+pub fn compute_fingerprint<P: AsRef<Path>>(path: P) -> Result<Fingerprint, DecodeError> {
+    let path_ref = path.as_ref();
+    let config = Configuration::default();
+    let mut fingerprinter = Fingerprinter::new(&config);
+    let mut started = false;
+    let mut start_error = None;
+    let mut frames: u64 = 0;
+    let mut pcm = Vec::new();
+
+    let format = decode_file(path_ref, |format, samples| {
+        if start_error.is_some() {
+            return;
+        }
+        if !started {
+            if let Err(e) = fingerprinter.start(format.sample_rate, format.channels as u32) {
+                start_error = Some(format!("{:?}", e));
+                return;
+            }
+            started = true;
+        }
+
+        // Only the beginning of the track is fingerprinted, but the whole track is counted
+        let limit = u64::from(FINGERPRINT_LENGTH) * u64::from(format.sample_rate);
+        let remaining = limit.saturating_sub(frames) as usize * format.channels;
+        frames += (samples.len() / format.channels.max(1)) as u64;
+        if remaining == 0 {
+            return;
+        }
+
+        pcm.clear();
+        pcm.extend(
+            samples[..samples.len().min(remaining)]
+                .iter()
+                .map(|sample| (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16),
+        );
+        fingerprinter.consume(&pcm);
+    })?;
+
+    if let Some(e) = start_error {
+        return Err(DecodeError::DecodeFailed(e));
+    }
+    fingerprinter.finish();
+
+    let fingerprint = Fingerprint {
+        duration: (frames / u64::from(format.sample_rate.max(1))) as u32,
+        data: fingerprinter.fingerprint().to_vec(),
+    };
+    if fingerprint.data.is_empty() {
+        return Err(DecodeError::DecodeFailed("audio too short to fingerprint".to_string()));
+    }
+
+    log::info!(
+        "Fingerprinted {:?}: {} items, {} s",
+        path_ref,
+        fingerprint.data.len(),
+        fingerprint.duration
+    );
+    Ok(fingerprint)
+}
+
+/// Group fingerprints of the same recording, returning groups of indices with more than one entry
+pub fn find_duplicates(fingerprints: &[Fingerprint]) -> Vec<Vec<usize>> {
+    let mut group_of: Vec<Option<usize>> = vec![None; fingerprints.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for i in 0..fingerprints.len() {
+        for j in i + 1..fingerprints.len() {
+            if group_of[j].is_some() && group_of[j] == group_of[i] {
+                continue;
+            }
+            // Durations of the same recording rarely differ by more than a few seconds
+            let (a, b) = (fingerprints[i].duration, fingerprints[j].duration);
+            if a > 0 && b > 0 && a.abs_diff(b) > 10 {
+                continue;
+            }
+            if fingerprints[i].similarity(&fingerprints[j]) < DUPLICATE_THRESHOLD {
+                continue;
+            }
+            match (group_of[i], group_of[j]) {
+                (Some(group), None) => {
+                    groups[group].push(j);
+                    group_of[j] = Some(group);
+                }
+                (None, Some(group)) => {
+                    groups[group].push(i);
+                    group_of[i] = Some(group);
+                }
+                (None, None) => {
+                    group_of[i] = Some(groups.len());
+                    group_of[j] = Some(groups.len());
+                    groups.push(vec![i, j]);
+                }
+                (Some(a), Some(b)) => {
+                    // Merge group b into group a
+                    let moved = std::mem::take(&mut groups[b]);
+                    for &index in &moved {
+                        group_of[index] = Some(a);
+                    }
+                    groups[a].extend(moved);
+                }
+            }
+        }
+    }
+
+    groups.retain(|group| group.len() > 1);
+    for group in &mut groups {
+        group.sort_unstable();
+    }
+    groups
+}
+
+/// Decompress a Chromaprint compressed fingerprint into its sub-fingerprints
+///
+/// Layout: algorithm byte, 24-bit big-endian item count, then the bit positions of each
+/// XOR-delta item as packed 3-bit values, followed by packed 5-bit overflow values.
+fn decompress(bytes: &[u8]) -> Option<Vec<u32>> {
+    let header = bytes.get(..4)?;
+    let size = (usize::from(header[1]) << 16) | (usize::from(header[2]) << 8) | usize::from(header[3]);
+    let body = &bytes[4..];
+
+    // Read the normal values until every item is terminated by a zero
+    let mut normal = Vec::new();
+    let mut terminators = 0;
+    let mut bit = 0;
+    while terminators < size {
+        let value = read_bits(body, bit, 3)?;
+        bit += 3;
+        if value == 0 {
+            terminators += 1;
+        }
+        normal.push(value);
+    }
+
+    // Values at the 3-bit maximum continue in the exceptional section
+    let mut exceptional_bit = normal.len() * 3;
+    exceptional_bit = exceptional_bit.div_ceil(8) * 8;
+    for value in normal.iter_mut().filter(|value| **value == 7) {
+        *value += read_bits(body, exceptional_bit, 5)?;
+        exceptional_bit += 5;
+    }
+
+    let mut data = Vec::with_capacity(size);
+    let mut item: u32 = 0;
+    let mut last_bit = 0;
+    for value in normal {
+        if value == 0 {
+            data.push(item ^ data.last().copied().unwrap_or(0));
+            item = 0;
+            last_bit = 0;
+            continue;
+        }
+        last_bit += value;
+        if last_bit > 32 {
+            return None;
+        }
+        item |= 1 << (last_bit - 1);
+    }
+    Some(data)
+}
+
+/// Read `count` bits (LSB first) starting at bit offset `bit`
+fn read_bits(bytes: &[u8], bit: usize, count: usize) -> Option<u32> {
+    let mut value = 0;
+    for i in 0..count {
+        let position = bit + i;
+        let byte = bytes.get(position / 8)?;
+        value |= u32::from((byte >> (position % 8)) & 1) << i;
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint() -> Fingerprint {
+        Fingerprint {
+            duration: 0,
+            data: vec![0xDEAD_BEEF, 0x0123_4567, 0xFFFF_0000, 0x0000_0001, 0x8000_0000],
+        }
+    }
+
+    #[test]
+    fn encoded_fingerprints_decode_to_the_same_data() {
+        let encoded = fingerprint().encode();
+        assert!(!encoded.contains(['=', '+', '/']));
+        assert_eq!(Fingerprint::decode(&encoded, 0), Some(fingerprint()));
+    }
+
+    #[test]
+    fn decodes_padded_and_standard_alphabet_fingerprints() {
+        let encoded = fingerprint().encode();
+        let padding = "=".repeat((4 - encoded.len() % 4) % 4);
+        let standard = format!("{}{}", encoded.replace('-', "+").replace('_', "/"), padding);
+        assert_eq!(Fingerprint::decode(&standard, 0), Some(fingerprint()));
+    }
+
+    #[test]
+    fn rejects_invalid_fingerprints() {
+        assert_eq!(Fingerprint::decode("not base64!", 0), None);
+        assert_eq!(Fingerprint::decode("", 0), None);
+    }
+}
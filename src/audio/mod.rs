@@ -0,0 +1,25 @@
+pub mod decode;
+pub mod fingerprint;
+pub mod replaygain;
+
+/// Errors that can occur while decoding audio
+#[derive(Debug, Clone)]
+pub enum DecodeError {
+    FileNotFound,
+    UnsupportedFormat(String),
+    DecodeFailed(String),
+    IoError(String),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::FileNotFound => write!(f, "File not found"),
+            DecodeError::UnsupportedFormat(msg) => write!(f, "Unsupported audio format: {}", msg),
+            DecodeError::DecodeFailed(msg) => write!(f, "Audio decoding failed: {}", msg),
+            DecodeError::IoError(msg) => write!(f, "IO error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
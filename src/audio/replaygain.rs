@@ -0,0 +1,84 @@
+use super::decode::decode_file;
+use super::DecodeError;
+use ebur128::{EbuR128, Mode};
+use std::path::Path;
+
+/// ReplayGain 2.0 reference loudness in LUFS
+pub const REFERENCE_LOUDNESS: f64 = -18.0;
+
+/// Loudness measurement of a single track
+pub struct TrackLoudness {
+    pub loudness: f64, // Integrated loudness in LUFS
+    pub peak: f64,     // Sample peak, 1.0 = full scale
+    meter: EbuR128,    // Kept around to compute the album loudness
+}
+
+impl std::fmt::Debug for TrackLoudness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrackLoudness")
+            .field("loudness", &self.loudness)
+            .field("peak", &self.peak)
+            .finish()
+    }
+}
+
+impl TrackLoudness {
+    /// ReplayGain 2.0 track gain in dB
+    pub fn gain(&self) -> f64 {
+        gain_for_loudness(self.loudness)
+    }
+}
+
+/// Convert an integrated loudness to a ReplayGain 2.0 gain in dB
+pub fn gain_for_loudness(loudness: f64) -> f64 {
+    REFERENCE_LOUDNESS - loudness
+}
+
+/// Decode a file and measure its EBU R128 integrated loudness and sample peak
+pub fn analyze_track<P: AsRef<Path>>(path: P) -> Result<TrackLoudness, DecodeError> {
+    let path_ref = path.as_ref();
+    let mut meter: Option<EbuR128> = None;
+    let mut meter_error = None;
+
+    decode_file(path_ref, |format, samples| {
+        if meter_error.is_some() {
+            return;
+        }
+        let meter = match meter {
+            Some(ref mut meter) => meter,
+            None => match EbuR128::new(format.channels as u32, format.sample_rate, Mode::I | Mode::SAMPLE_PEAK) {
+                Ok(new_meter) => meter.insert(new_meter),
+                Err(e) => {
+                    meter_error = Some(e.to_string());
+                    return;
+                }
+            },
+        };
+        if let Err(e) = meter.add_frames_f32(samples) {
+            meter_error = Some(e.to_string());
+        }
+    })?;
+
+    if let Some(e) = meter_error {
+        return Err(DecodeError::DecodeFailed(e));
+    }
+    let meter = meter.ok_or_else(|| DecodeError::DecodeFailed("no audio frames decoded".to_string()))?;
+
+    let loudness = meter
+        .loudness_global()
+        .map_err(|e| DecodeError::DecodeFailed(e.to_string()))?;
+    let peak = (0..meter.channels())
+        .filter_map(|channel| meter.sample_peak(channel).ok())
+        .fold(0.0, f64::max);
+
+    log::info!("Analyzed {:?}: {:.2} LUFS, peak {:.6}", path_ref, loudness, peak);
+    Ok(TrackLoudness { loudness, peak, meter })
+}
+
+/// Integrated loudness and peak of an album, computed over all of its tracks
+pub fn album_loudness(tracks: &[TrackLoudness]) -> Result<(f64, f64), DecodeError> {
+    let loudness = EbuR128::loudness_global_multiple(tracks.iter().map(|track| &track.meter))
+        .map_err(|e| DecodeError::DecodeFailed(e.to_string()))?;
+    let peak = tracks.iter().map(|track| track.peak).fold(0.0, f64::max);
+    Ok((loudness, peak))
+}
@@ -1,21 +1,22 @@
+use crate::duplicates::DuplicateOptions;
+use crate::id3_parser::cleanup::CleanupRules;
+use crate::id3_parser::filename::DEFAULT_PATTERNS;
+use crate::id3_parser::preset::TagPreset;
+use crate::id3_parser::ListSeparator;
+use crate::report::ReportOptions;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
 /// Theme options for the application
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AppTheme {
+    #[default]
     Dark,
     Light,
 }
 
-impl Default for AppTheme {
-    fn default() -> Self {
-        AppTheme::Dark
-    }
-}
-
 impl AppTheme {
     /// Convert to iced::Theme
     pub fn to_iced_theme(self) -> iced::Theme {
@@ -26,6 +27,76 @@ impl AppTheme {
     }
 }
 
+/// Player conventions used when writing POPM ratings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RatingProfile {
+    #[default]
+    WindowsMediaPlayer,
+    Foobar2000,
+    MediaMonkey,
+}
+
+impl RatingProfile {
+    pub const ALL: [RatingProfile; 3] = [
+        RatingProfile::WindowsMediaPlayer,
+        RatingProfile::Foobar2000,
+        RatingProfile::MediaMonkey,
+    ];
+
+    /// The POPM email/user identifier the player reads and writes
+    pub fn email(self) -> &'static str {
+        match self {
+            RatingProfile::WindowsMediaPlayer => "Windows Media Player 9 Series",
+            RatingProfile::Foobar2000 => "foobar2000",
+            RatingProfile::MediaMonkey => "no@email",
+        }
+    }
+
+    /// Convert a 0-5 star rating to the player's 0-255 POPM value
+    ///
+    /// The players agree on whole-star values; they differ in the POPM email and in how
+    /// intermediate values are read back (see `stars_for_rating`).
+    pub fn rating_for_stars(self, stars: u8) -> u8 {
+        match stars {
+            0 => 0,
+            1 => 1,
+            2 => 64,
+            3 => 128,
+            4 => 196,
+            _ => 255,
+        }
+    }
+
+    /// Convert a 0-255 POPM value to a 0-5 star rating
+    pub fn stars_for_rating(self, rating: u8) -> u8 {
+        match (self, rating) {
+            (_, 0) => 0,
+            // MediaMonkey stores half stars (e.g. 54 = 1.5), round them up
+            (RatingProfile::MediaMonkey, 1..=13) => 1,
+            (RatingProfile::MediaMonkey, 14..=64) => 2,
+            (RatingProfile::MediaMonkey, 65..=128) => 3,
+            (RatingProfile::MediaMonkey, 129..=196) => 4,
+            (RatingProfile::MediaMonkey, _) => 5,
+            (_, 1..=31) => 1,
+            (_, 32..=95) => 2,
+            (_, 96..=159) => 3,
+            (_, 160..=223) => 4,
+            _ => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for RatingProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RatingProfile::WindowsMediaPlayer => write!(f, "Windows Media Player"),
+            RatingProfile::Foobar2000 => write!(f, "foobar2000"),
+            RatingProfile::MediaMonkey => write!(f, "MediaMonkey"),
+        }
+    }
+}
+
 /// Application configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -34,6 +105,26 @@ pub struct AppConfig {
     pub zoom: f32,     // Zoom level (1.0 = 100%, 1.5 = 150%, etc.)
     #[serde(default)]
     pub recent_files: Vec<String>,
+    #[serde(default)]
+    pub rating_profile: RatingProfile,
+    #[serde(default)]
+    pub lookup_database: Option<String>, // Local MusicBrainz-style JSON dump used for lookups
+    #[serde(default = "default_filename_patterns")]
+    pub filename_patterns: Vec<String>, // Saved tags-from-filename patterns
+    #[serde(default = "default_rename_template")]
+    pub rename_template: String, // Last template used to rename files
+    #[serde(default)]
+    pub cleanup_rules: CleanupRules, // Last rules used by the clean up tool
+    #[serde(default)]
+    pub favorite_genres: Vec<String>, // Genres listed first in the genre picker
+    #[serde(default)]
+    pub legacy_separator: ListSeparator, // Separator of multiple values in ID3v2.3 tags
+    #[serde(default)]
+    pub report_options: ReportOptions, // Checks of the tag health report
+    #[serde(default)]
+    pub duplicate_options: DuplicateOptions, // Criteria of the duplicate track finder
+    #[serde(default)]
+    pub tag_presets: Vec<TagPreset>, // Named field values applied to tracks together
 }
 
 impl Default for AppConfig {
@@ -42,10 +133,28 @@ impl Default for AppConfig {
             theme: AppTheme::default(),
             zoom: 1.0,
             recent_files: Vec::new(),
+            rating_profile: RatingProfile::default(),
+            lookup_database: None,
+            filename_patterns: default_filename_patterns(),
+            rename_template: default_rename_template(),
+            cleanup_rules: CleanupRules::default(),
+            favorite_genres: Vec::new(),
+            legacy_separator: ListSeparator::default(),
+            report_options: ReportOptions::default(),
+            duplicate_options: DuplicateOptions::default(),
+            tag_presets: Vec::new(),
         }
     }
 }
 
+fn default_filename_patterns() -> Vec<String> {
+    DEFAULT_PATTERNS.iter().map(|pattern| pattern.to_string()).collect()
+}
+
+fn default_rename_template() -> String {
+    "{album_artist}/{year} - {album}/{disc}{track:02} {title}.{ext}".to_string()
+}
+
 /// Get the path to the config directory
 fn get_config_dir() -> Option<PathBuf> {
     let config_dir = dirs::config_dir()?;
@@ -60,6 +169,16 @@ fn get_config_path() -> Option<PathBuf> {
     Some(get_config_dir()?.join("config.json"))
 }
 
+/// Get the path to the local fingerprint index
+pub fn get_fingerprint_index_path() -> Option<PathBuf> {
+    Some(get_config_dir()?.join("fingerprints.json"))
+}
+
+/// Get the path to the log of file renames that can be undone
+pub fn get_rename_log_path() -> Option<PathBuf> {
+    Some(get_config_dir()?.join("rename_log.json"))
+}
+
 /// Get the path to the recent files config file (for backward compatibility)
 fn get_recent_files_path() -> Option<PathBuf> {
     Some(get_config_dir()?.join("recent_files.json"))
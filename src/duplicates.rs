@@ -0,0 +1,465 @@
+use crate::audio::decode::probe_duration;
+use crate::id3_parser::{parse_id3, AudioMetadata, ListSeparator};
+use crate::rename::{PlannedRename, RenameStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hasher};
+use std::path::{Path, PathBuf};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Size of an ID3v1 tag at the end of a file
+const ID3V1_SIZE: usize = 128;
+
+/// Size of an "enhanced" ID3v1 tag ("TAG+") in front of the ID3v1 tag
+const ID3V1_ENHANCED_SIZE: usize = 227;
+
+/// Size of an APEv2 tag footer (and header)
+const APE_FOOTER_SIZE: usize = 32;
+
+/// Which properties make two files duplicates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicateOptions {
+    pub match_isrc: bool,          // Same ISRC
+    pub match_artist_title: bool,  // Same artist and title, ignoring case, accents and punctuation
+    pub check_duration: bool,      // Require ISRC and artist/title matches to have similar durations
+    pub duration_tolerance: u32,   // Largest duration difference, in seconds
+    pub match_content: bool,       // Same audio data, ignoring the tags (reads every file in full)
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            match_isrc: true,
+            match_artist_title: true,
+            check_duration: true,
+            duration_tolerance: 3,
+            match_content: false,
+        }
+    }
+}
+
+/// Why the files of a group were taken for duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchReason {
+    Isrc,
+    ArtistTitle,
+    Content,
+}
+
+impl MatchReason {
+    /// Human readable label
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchReason::Isrc => "Same ISRC",
+            MatchReason::ArtistTitle => "Same artist and title",
+            MatchReason::Content => "Same audio data",
+        }
+    }
+}
+
+/// A file of a duplicate group
+#[derive(Debug, Clone)]
+pub struct DuplicateFile {
+    pub path: PathBuf,
+    pub metadata: Option<AudioMetadata>, // Without the cover art data, to save memory
+    pub has_cover_art: bool,
+    pub duration: Option<u32>, // Seconds, from the tag or else the audio stream
+    pub size: u64,             // Bytes
+}
+
+/// Files that are likely copies of the same track
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub files: Vec<DuplicateFile>,
+    pub reasons: Vec<MatchReason>,
+}
+
+/// What to do with a file of a duplicate group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateAction {
+    #[default]
+    Keep,
+    Move,
+    Trash,
+}
+
+impl DuplicateAction {
+    pub const ALL: [DuplicateAction; 3] = [DuplicateAction::Keep, DuplicateAction::Move, DuplicateAction::Trash];
+}
+
+impl std::fmt::Display for DuplicateAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DuplicateAction::Keep => write!(f, "Keep"),
+            DuplicateAction::Move => write!(f, "Move"),
+            DuplicateAction::Trash => write!(f, "Trash"),
+        }
+    }
+}
+
+/// A key that files sharing it match on
+type MetadataKey = fn(&AudioMetadata) -> Option<String>;
+
+/// Group files that are likely duplicates
+///
+/// Files match when any enabled criterion matches; matches chain, so a group may hold files
+/// that match through a third one. Groups are sorted by the path of their first file.
+pub fn find_duplicate_tracks(
+    files: &[PathBuf],
+    options: &DuplicateOptions,
+    separator: ListSeparator,
+) -> Vec<DuplicateGroup> {
+    let mut entries: Vec<DuplicateFile> = files
+        .iter()
+        .map(|path| {
+            let mut metadata = parse_id3(path, separator).ok();
+            let has_cover_art = metadata.as_ref().is_some_and(|metadata| metadata.cover_art.is_some());
+            if let Some(ref mut metadata) = metadata {
+                metadata.cover_art = None;
+            }
+            DuplicateFile {
+                path: path.clone(),
+                // TLEN is in milliseconds
+                duration: metadata.as_ref().and_then(|metadata| metadata.duration).map(|ms| ms / 1000),
+                has_cover_art,
+                size: std::fs::metadata(path).map_or(0, |file| file.len()),
+                metadata,
+            }
+        })
+        .collect();
+
+    let mut groups = UnionFind::new(entries.len());
+    let mut reasons: Vec<(usize, MatchReason)> = Vec::new(); // A file of each match, and why
+    let mut link = |groups: &mut UnionFind, a: usize, b: usize, reason: MatchReason| {
+        groups.union(a, b);
+        reasons.push((a, reason));
+    };
+
+    let key_sets: [(MatchReason, bool, MetadataKey); 2] = [
+        (MatchReason::Isrc, options.match_isrc, isrc_key),
+        (MatchReason::ArtistTitle, options.match_artist_title, artist_title_key),
+    ];
+    for (reason, enabled, key) in key_sets {
+        if !enabled {
+            continue;
+        }
+        let buckets = bucket(entries.iter().map(|entry| entry.metadata.as_ref().and_then(key)));
+        for indices in buckets {
+            if options.check_duration {
+                for &index in &indices {
+                    fill_duration(&mut entries[index]);
+                }
+            }
+            for (position, &a) in indices.iter().enumerate() {
+                for &b in &indices[position + 1..] {
+                    if !options.check_duration || durations_match(&entries[a], &entries[b], options.duration_tolerance) {
+                        link(&mut groups, a, b, reason);
+                    }
+                }
+            }
+        }
+    }
+
+    if options.match_content {
+        let hashes = entries.iter().map(|entry| match content_hash(&entry.path) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                log::warn!("Could not hash the audio of {:?}: {}", entry.path, e);
+                None
+            }
+        });
+        let buckets = bucket(hashes);
+        for indices in buckets {
+            for &other in &indices[1..] {
+                link(&mut groups, indices[0], other, MatchReason::Content);
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..entries.len() {
+        members.entry(groups.find(index)).or_default().push(index);
+    }
+    let mut result: Vec<DuplicateGroup> = members
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|indices| {
+            let group_reasons: BTreeSet<MatchReason> = reasons
+                .iter()
+                .filter(|(index, _)| indices.contains(index))
+                .map(|(_, reason)| *reason)
+                .collect();
+            DuplicateGroup {
+                files: indices.iter().map(|&index| entries[index].clone()).collect(),
+                reasons: group_reasons.into_iter().collect(),
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.files[0].path.cmp(&b.files[0].path));
+    result
+}
+
+/// Plan moving files into a folder, numbering names that are already taken ("Song (2).mp3")
+pub fn plan_duplicate_moves(files: &[PathBuf], destination: &Path) -> Vec<PlannedRename> {
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+    files
+        .iter()
+        .map(|source| {
+            let stem = source.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let extension = source.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+            let mut target = destination.join(format!("{}{}", stem, extension));
+            let mut number = 2;
+            while (target.exists() && target != *source) || taken.contains(&target) {
+                target = destination.join(format!("{} ({}){}", stem, number, extension));
+                number += 1;
+            }
+            taken.insert(target.clone());
+            let status = if source == &target {
+                RenameStatus::Unchanged
+            } else {
+                RenameStatus::Ready
+            };
+            PlannedRename {
+                source: source.clone(),
+                target,
+                status,
+            }
+        })
+        .collect()
+}
+
+/// Indices of the keys shared by several entries, grouped by key
+fn bucket<K: std::hash::Hash + Eq>(keys: impl Iterator<Item = Option<K>>) -> Vec<Vec<usize>> {
+    let mut buckets: HashMap<K, Vec<usize>> = HashMap::new();
+    for (index, key) in keys.enumerate() {
+        if let Some(key) = key {
+            buckets.entry(key).or_default().push(index);
+        }
+    }
+    buckets.into_values().filter(|indices| indices.len() > 1).collect()
+}
+
+/// Read the duration from the audio stream if the tag has none
+fn fill_duration(entry: &mut DuplicateFile) {
+    if entry.duration.is_none() {
+        entry.duration = probe_duration(&entry.path).ok().flatten();
+    }
+}
+
+/// Whether two files are about as long; a file of unknown length matches any
+fn durations_match(a: &DuplicateFile, b: &DuplicateFile, tolerance: u32) -> bool {
+    match (a.duration, b.duration) {
+        (Some(a), Some(b)) => a.abs_diff(b) <= tolerance,
+        _ => true,
+    }
+}
+
+fn isrc_key(metadata: &AudioMetadata) -> Option<String> {
+    let isrc: String = metadata
+        .isrc
+        .as_deref()?
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    (!isrc.is_empty()).then_some(isrc)
+}
+
+fn artist_title_key(metadata: &AudioMetadata) -> Option<String> {
+    let title = normalize(metadata.title.as_deref()?);
+    let mut artists: Vec<String> = metadata.artists.iter().map(|artist| normalize(artist)).collect();
+    artists.sort();
+    artists.retain(|artist| !artist.is_empty());
+    (!title.is_empty() && !artists.is_empty()).then(|| format!("{}\n{}", artists.join("\n"), title))
+}
+
+/// Lowercase text without accents, punctuation or a leading "the"
+pub fn normalize(value: &str) -> String {
+    let cleaned: String = value
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let words: Vec<&str> = cleaned.split_whitespace().collect();
+    match words.split_first() {
+        Some((&"the", rest)) if !rest.is_empty() => rest.join(" "),
+        _ => words.join(" "),
+    }
+}
+
+/// Hash of a file's bytes without its ID3v2, ID3v1 and APEv2 tags
+fn content_hash(path: &Path) -> Result<u64, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(audio_data(&bytes));
+    Ok(hasher.finish())
+}
+
+/// The part of an MP3 file between its leading and trailing tags
+fn audio_data(bytes: &[u8]) -> &[u8] {
+    let mut start = 0;
+    // Several ID3v2 tags may follow each other
+    while let Some(header) = bytes.get(start..start + 10).filter(|header| header.starts_with(b"ID3")) {
+        let size = header[6..10].iter().fold(0usize, |size, byte| (size << 7) | usize::from(byte & 0x7f));
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        start = (start + 10 + size + footer).min(bytes.len());
+    }
+
+    let mut end = bytes.len();
+    loop {
+        let rest = &bytes[start..end];
+        if rest.len() >= ID3V1_SIZE && rest[rest.len() - ID3V1_SIZE..].starts_with(b"TAG") {
+            end -= ID3V1_SIZE;
+            if bytes[start..end].len() >= ID3V1_ENHANCED_SIZE
+                && bytes[end - ID3V1_ENHANCED_SIZE..end].starts_with(b"TAG+")
+            {
+                end -= ID3V1_ENHANCED_SIZE;
+            }
+        } else if rest.len() >= APE_FOOTER_SIZE && rest[rest.len() - APE_FOOTER_SIZE..].starts_with(b"APETAGEX") {
+            let footer = &rest[rest.len() - APE_FOOTER_SIZE..];
+            // The size covers the items and the footer; a header may precede them
+            let size = u32::from_le_bytes([footer[12], footer[13], footer[14], footer[15]]) as usize;
+            if size < APE_FOOTER_SIZE {
+                // Too small to hold its own footer, so not an APE tag (and nothing would be removed)
+                break;
+            }
+            let header = if footer[23] & 0x80 != 0 { APE_FOOTER_SIZE } else { 0 };
+            end -= (size + header).min(rest.len());
+        } else {
+            break;
+        }
+    }
+    &bytes[start..end]
+}
+
+/// Disjoint sets of indices
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let parent = self.parents[index];
+        if parent == index {
+            return index;
+        }
+        let root = self.find(parent);
+        self.parents[index] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[b] = a;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("id3-duplicates-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file(duration: Option<u32>) -> DuplicateFile {
+        DuplicateFile {
+            path: PathBuf::from("song.mp3"),
+            metadata: None,
+            has_cover_art: false,
+            duration,
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn normalize_ignores_case_accents_punctuation_and_the() {
+        assert_eq!(normalize("The Beatles"), "beatles");
+        assert_eq!(normalize("Beyoncé!"), "beyonce");
+        assert_eq!(normalize("  Don't   Stop "), "don t stop");
+        assert_eq!(normalize("The"), "the");
+    }
+
+    #[test]
+    fn artist_title_key_ignores_artist_order() {
+        let metadata = |artists: &[&str]| AudioMetadata {
+            title: Some("Song".to_string()),
+            artists: artists.iter().map(|artist| artist.to_string()).collect(),
+            ..AudioMetadata::default()
+        };
+        assert_eq!(artist_title_key(&metadata(&["B", "A"])), artist_title_key(&metadata(&["a", "b"])));
+        assert_eq!(artist_title_key(&metadata(&[])), None);
+    }
+
+    #[test]
+    fn isrc_key_ignores_hyphens_and_case() {
+        let metadata = AudioMetadata {
+            isrc: Some("gb-aye-69-00531".to_string()),
+            ..AudioMetadata::default()
+        };
+        assert_eq!(isrc_key(&metadata).as_deref(), Some("GBAYE6900531"));
+    }
+
+    #[test]
+    fn durations_match_within_the_tolerance() {
+        assert!(durations_match(&file(Some(200)), &file(Some(203)), 3));
+        assert!(!durations_match(&file(Some(200)), &file(Some(204)), 3));
+        assert!(durations_match(&file(None), &file(Some(204)), 3));
+    }
+
+    #[test]
+    fn audio_data_skips_tags() {
+        let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x02ab".to_vec();
+        bytes.extend_from_slice(b"audio");
+        let mut id3v1 = b"TAG".to_vec();
+        id3v1.resize(ID3V1_SIZE, 0);
+        bytes.extend_from_slice(&id3v1);
+        assert_eq!(audio_data(&bytes), b"audio");
+    }
+
+    #[test]
+    fn finds_files_with_the_same_audio() {
+        let dir = scratch_dir("content");
+        let mut id3v1 = b"TAG".to_vec();
+        id3v1.resize(ID3V1_SIZE, b'x');
+        let files = [dir.join("a.mp3"), dir.join("b.mp3"), dir.join("c.mp3")];
+        fs::write(&files[0], b"same audio").unwrap();
+        fs::write(&files[1], [b"same audio".as_slice(), &id3v1].concat()).unwrap();
+        fs::write(&files[2], b"other audio").unwrap();
+        let options = DuplicateOptions {
+            match_content: true,
+            ..DuplicateOptions::default()
+        };
+        let groups = find_duplicate_tracks(&files, &options, ListSeparator::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].reasons, [MatchReason::Content]);
+        let paths: Vec<&PathBuf> = groups[0].files.iter().map(|file| &file.path).collect();
+        assert_eq!(paths, [&files[0], &files[1]]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn moves_number_taken_names() {
+        let dir = scratch_dir("moves");
+        fs::write(dir.join("song.mp3"), b"").unwrap();
+        let files = [PathBuf::from("/a/song.mp3"), PathBuf::from("/b/song.mp3")];
+        let targets: Vec<PathBuf> = plan_duplicate_moves(&files, &dir).into_iter().map(|plan| plan.target).collect();
+        assert_eq!(targets, [dir.join("song (2).mp3"), dir.join("song (3).mp3")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
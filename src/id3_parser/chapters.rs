@@ -0,0 +1,235 @@
+use super::Chapter;
+use serde::{Deserialize, Serialize};
+
+/// A chapter entry in the Podlove Simple Chapters JSON format
+#[derive(Debug, Serialize, Deserialize)]
+struct PodloveChapter {
+    start: String,
+    title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    href: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+}
+
+/// Parse a chapter time (`HH:MM:SS.mmm`, `MM:SS`, `SS`, with optional fraction) into milliseconds
+pub fn parse_chapter_time(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let (clock, fraction) = match value.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (value, None),
+    };
+
+    let mut seconds: u32 = 0;
+    for part in clock.split(':') {
+        let part: u32 = part.trim().parse().ok()?;
+        seconds = seconds.checked_mul(60)?.checked_add(part)?;
+    }
+
+    let fraction_ms = match fraction {
+        Some(fraction) if !fraction.is_empty() && fraction.chars().all(|c| c.is_ascii_digit()) => {
+            let digits: String = fraction.chars().take(3).collect();
+            digits.parse::<u32>().ok()? * 10u32.pow(3 - digits.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    seconds.checked_mul(1000)?.checked_add(fraction_ms)
+}
+
+/// Format milliseconds as a chapter time (`HH:MM:SS.mmm`)
+pub fn format_chapter_time(time_ms: u32) -> String {
+    let hours = time_ms / 3_600_000;
+    let minutes = (time_ms % 3_600_000) / 60_000;
+    let seconds = (time_ms % 60_000) / 1000;
+    let millis = time_ms % 1000;
+    format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
+}
+
+/// Parse a plain text chapter list (`00:01:30.000 Title <https://link>` per line)
+pub fn parse_chapters_text(content: &str, duration_ms: Option<u32>) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (time, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let Some(start_ms) = parse_chapter_time(time) else {
+            log::debug!("Skipping chapter line without a valid time: {:?}", line);
+            continue;
+        };
+
+        // An optional trailing <url> is the chapter link
+        let mut title = rest.trim();
+        let mut url = None;
+        if title.ends_with('>') {
+            if let Some(open) = title.rfind('<') {
+                url = Some(title[open + 1..title.len() - 1].trim().to_string());
+                title = title[..open].trim();
+            }
+        }
+
+        chapters.push(new_chapter(&chapters, start_ms, title, url));
+    }
+
+    finalize_imported(chapters, duration_ms)
+}
+
+/// Format chapters as a plain text chapter list
+pub fn format_chapters_text(chapters: &[Chapter]) -> String {
+    chapters
+        .iter()
+        .map(|chapter| {
+            let mut line = format!(
+                "{} {}",
+                format_chapter_time(chapter.start_ms),
+                chapter.title.as_deref().unwrap_or_default()
+            );
+            if let Some(url) = chapter.url.as_ref().filter(|url| !url.is_empty()) {
+                line.push_str(&format!(" <{}>", url));
+            }
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+/// Parse a Podlove Simple Chapters JSON document
+pub fn parse_chapters_json(content: &str, duration_ms: Option<u32>) -> Result<Vec<Chapter>, String> {
+    let entries: Vec<PodloveChapter> = serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+    let mut chapters = Vec::new();
+    for entry in entries {
+        let start_ms = parse_chapter_time(&entry.start)
+            .ok_or_else(|| format!("Invalid chapter start time: {}", entry.start))?;
+        chapters.push(new_chapter(&chapters, start_ms, &entry.title, entry.href));
+    }
+
+    Ok(finalize_imported(chapters, duration_ms))
+}
+
+/// Format chapters as a Podlove Simple Chapters JSON document
+pub fn format_chapters_json(chapters: &[Chapter]) -> Result<String, String> {
+    let entries: Vec<PodloveChapter> = chapters
+        .iter()
+        .map(|chapter| PodloveChapter {
+            start: format_chapter_time(chapter.start_ms),
+            title: chapter.title.clone().unwrap_or_default(),
+            href: chapter.url.clone().filter(|url| !url.is_empty()),
+            image: None,
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())
+}
+
+/// Generate an element ID that isn't used by any of the given chapters
+pub fn next_element_id(chapters: &[Chapter]) -> String {
+    (0..)
+        .map(|n| format!("chp{}", n))
+        .find(|id| !chapters.iter().any(|chapter| &chapter.element_id == id))
+        .unwrap_or_default()
+}
+
+/// Whether a chapter ends after it starts (CHAP frames need a positive length)
+pub fn has_valid_times(chapter: &Chapter) -> bool {
+    chapter.end_ms > chapter.start_ms
+}
+
+/// Create a chapter without an end time (filled in by `finalize_imported`)
+fn new_chapter(existing: &[Chapter], start_ms: u32, title: &str, url: Option<String>) -> Chapter {
+    Chapter {
+        element_id: next_element_id(existing),
+        start_ms,
+        end_ms: start_ms,
+        title: (!title.is_empty()).then(|| title.to_string()),
+        url: url.filter(|url| !url.is_empty()),
+        image: None,
+        image_format: None,
+    }
+}
+
+/// Sort imported chapters and end each one where the next begins (the last at the track end)
+fn finalize_imported(mut chapters: Vec<Chapter>, duration_ms: Option<u32>) -> Vec<Chapter> {
+    chapters.sort_by_key(|chapter| chapter.start_ms);
+    let next_starts: Vec<Option<u32>> = chapters
+        .iter()
+        .skip(1)
+        .map(|chapter| Some(chapter.start_ms))
+        .chain(std::iter::once(duration_ms))
+        .collect();
+    for (chapter, next_start) in chapters.iter_mut().zip(next_starts) {
+        chapter.end_ms = next_start.unwrap_or(chapter.start_ms).max(chapter.start_ms);
+    }
+    log::debug!("Imported {} chapters", chapters.len());
+    chapters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chapter_times() {
+        assert_eq!(parse_chapter_time("45"), Some(45_000));
+        assert_eq!(parse_chapter_time("01:30"), Some(90_000));
+        assert_eq!(parse_chapter_time("01:02:03.5"), Some(3_723_500));
+        assert_eq!(parse_chapter_time("00:00:01.234"), Some(1_234));
+        assert_eq!(parse_chapter_time("1:x"), None);
+        assert_eq!(parse_chapter_time("00:01.ab"), None);
+        assert_eq!(parse_chapter_time("9999999:00:00"), None);
+    }
+
+    #[test]
+    fn formats_chapter_times() {
+        assert_eq!(format_chapter_time(3_723_500), "01:02:03.500");
+        assert_eq!(parse_chapter_time(&format_chapter_time(3_723_500)), Some(3_723_500));
+    }
+
+    #[test]
+    fn imports_text_chapters_in_time_order_ending_at_the_next_one() {
+        let chapters = parse_chapters_text("00:01:00 Second <https://example.com>\n\nbad line\n00:00:00 First\n", Some(90_000));
+        assert_eq!(chapters.len(), 2);
+        assert_eq!((chapters[0].start_ms, chapters[0].end_ms), (0, 60_000));
+        assert_eq!(chapters[0].title.as_deref(), Some("First"));
+        assert_eq!((chapters[1].start_ms, chapters[1].end_ms), (60_000, 90_000));
+        assert_eq!(chapters[1].url.as_deref(), Some("https://example.com"));
+        assert_ne!(chapters[0].element_id, chapters[1].element_id);
+    }
+
+    #[test]
+    fn last_chapter_stays_open_without_a_duration() {
+        let chapters = parse_chapters_text("00:00:00 Only\n", None);
+        assert!(!has_valid_times(&chapters[0]));
+    }
+
+    #[test]
+    fn text_chapters_round_trip() {
+        let chapters = parse_chapters_text("00:00:00.000 Intro\n00:02:00.000 Talk <https://example.com>\n", Some(300_000));
+        assert_eq!(parse_chapters_text(&format_chapters_text(&chapters), Some(300_000)), chapters);
+    }
+
+    #[test]
+    fn json_chapters_round_trip() {
+        let chapters = parse_chapters_json(
+            r#"[{"start": "00:00:00.000", "title": "Intro"}, {"start": "00:01:00.000", "title": "Talk", "href": "https://example.com"}]"#,
+            Some(120_000),
+        )
+        .unwrap();
+        assert_eq!(chapters[1].end_ms, 120_000);
+        assert_eq!(parse_chapters_json(&format_chapters_json(&chapters).unwrap(), Some(120_000)).unwrap(), chapters);
+    }
+
+    #[test]
+    fn rejects_json_with_invalid_times() {
+        assert!(parse_chapters_json(r#"[{"start": "soon", "title": "Intro"}]"#, None).is_err());
+    }
+
+    #[test]
+    fn next_element_id_skips_used_ids() {
+        let chapters = parse_chapters_text("0 A\n1 B\n", None);
+        assert_eq!(next_element_id(&chapters), "chp2");
+    }
+}
@@ -0,0 +1,259 @@
+use super::fields::{FieldChange, MetadataField};
+use super::AudioMetadata;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
+
+/// Words written as listed by Title Case unless they start the value
+pub const DEFAULT_CASE_EXCEPTIONS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "feat.", "for", "in", "nor", "of", "on", "or", "the", "to", "vs.",
+    "DJ", "MC", "EP", "LP", "II", "III", "IV",
+];
+
+/// Fields the clean up tool offers (text fields that hold names and titles)
+pub const CLEANUP_FIELDS: &[MetadataField] = &[
+    MetadataField::Title,
+    MetadataField::Artist,
+    MetadataField::Album,
+    MetadataField::AlbumArtist,
+    MetadataField::Composer,
+    MetadataField::Genre,
+    MetadataField::Comment,
+    MetadataField::Publisher,
+    MetadataField::Conductor,
+    MetadataField::Remixer,
+    MetadataField::Producer,
+    MetadataField::Grouping,
+    MetadataField::Subtitle,
+    MetadataField::OriginalArtist,
+    MetadataField::OriginalAlbum,
+];
+
+/// Text cleanup rules, applied in the order of the fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupRules {
+    pub unicode_nfc: bool,               // Compose characters (e.g. "e" + combining accent -> "é")
+    pub replace_chars: String,           // Characters to replace...
+    pub replace_with: String,            // ...with this text
+    pub collapse_whitespace: bool,       // Trim and collapse runs of whitespace
+    pub normalize_featuring: bool,       // "ft.", "feat", "featuring" -> "feat."
+    pub title_case: bool,                // Capitalize Every Word
+    pub case_exceptions: Vec<String>,    // Words Title Case writes as listed
+}
+
+impl Default for CleanupRules {
+    fn default() -> Self {
+        Self {
+            unicode_nfc: true,
+            replace_chars: String::new(),
+            replace_with: String::new(),
+            collapse_whitespace: true,
+            normalize_featuring: false,
+            title_case: false,
+            case_exceptions: DEFAULT_CASE_EXCEPTIONS.iter().map(|word| word.to_string()).collect(),
+        }
+    }
+}
+
+impl CleanupRules {
+    /// Apply the rules to a single value
+    pub fn clean(&self, value: &str) -> String {
+        let mut value = if self.unicode_nfc {
+            value.nfc().collect()
+        } else {
+            value.to_string()
+        };
+        if !self.replace_chars.is_empty() {
+            value = value
+                .chars()
+                .map(|c| {
+                    if self.replace_chars.contains(c) {
+                        self.replace_with.clone()
+                    } else {
+                        c.to_string()
+                    }
+                })
+                .collect();
+        }
+        if self.normalize_featuring {
+            value = normalize_featuring(&value);
+        }
+        if self.collapse_whitespace {
+            value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if self.title_case {
+            value = self.to_title_case(&value);
+        }
+        value
+    }
+
+    /// Changes the rules would make to the given fields of a track
+    pub fn changes(&self, metadata: &AudioMetadata, fields: &[MetadataField]) -> Vec<FieldChange> {
+        fields
+            .iter()
+            .filter_map(|&field| {
+                let before = field.value(metadata)?;
+                let after = self.clean(&before);
+                (after != before).then_some(FieldChange { field, before, after })
+            })
+            .collect()
+    }
+
+    /// Convert a value to Title Case, writing exception words as listed
+    ///
+    /// Words are capitalized after spaces, hyphens, slashes and opening brackets or quotes;
+    /// an exception that starts the value is still capitalized.
+    fn to_title_case(&self, value: &str) -> String {
+        let mut output = String::with_capacity(value.len());
+        let mut word = String::new();
+        let mut first_word = true;
+        for c in value.chars() {
+            if c.is_whitespace() || matches!(c, '-' | '/' | '(' | '[' | '{' | '"' | '“') {
+                if !word.is_empty() {
+                    output.push_str(&self.title_case_word(&word, first_word));
+                    word.clear();
+                    first_word = false;
+                }
+                output.push(c);
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            output.push_str(&self.title_case_word(&word, first_word));
+        }
+        output
+    }
+
+    fn title_case_word(&self, word: &str, first_word: bool) -> String {
+        if let Some(exception) = self
+            .case_exceptions
+            .iter()
+            .find(|exception| exception.to_lowercase() == word.to_lowercase())
+        {
+            return if first_word { capitalize(exception) } else { exception.clone() };
+        }
+        // Only the first letter changes so "McCartney" or "AC" keep their inner capitals
+        capitalize(word)
+    }
+}
+
+/// Uppercase the first letter of a word
+fn capitalize(word: &str) -> String {
+    let mut output = String::with_capacity(word.len());
+    let mut capitalized = false;
+    for c in word.chars() {
+        if !capitalized && c.is_alphabetic() {
+            output.extend(c.to_uppercase());
+            capitalized = true;
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// Matches the spellings of "featuring" that are normalized to "feat."
+///
+/// The first group is an opening bracket right before the word, the second the word itself.
+fn featuring_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)([(\[]?)\b(featuring\b|feat\.|ft\.|feat\b|ft\b)\s*").expect("valid featuring regex")
+    })
+}
+
+/// Write "featuring", "feat." and "ft." as "feat."
+///
+/// "feat" and "ft" without a dot are ordinary words ("Feat of Strength") unless they open
+/// a bracket, as in "(ft Someone)".
+fn normalize_featuring(value: &str) -> String {
+    featuring_regex()
+        .replace_all(value, |captures: &regex::Captures| {
+            let whole = captures.get(0).expect("whole match");
+            let bracket = &captures[1];
+            let word = captures[2].to_lowercase();
+            if bracket.is_empty() && (word == "feat" || word == "ft") {
+                return whole.as_str().to_string();
+            }
+            let separator = if whole.end() == value.len() { "" } else { " " };
+            format!("{}feat.{}", bracket, separator)
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> CleanupRules {
+        CleanupRules {
+            normalize_featuring: true,
+            title_case: true,
+            ..CleanupRules::default()
+        }
+    }
+
+    #[test]
+    fn collapses_whitespace_and_composes_characters() {
+        let rules = CleanupRules::default();
+        assert_eq!(rules.clean("  Cafe\u{301}   del  Mar "), "Café del Mar");
+    }
+
+    #[test]
+    fn replaces_characters() {
+        let rules = CleanupRules {
+            replace_chars: "_".to_string(),
+            replace_with: " ".to_string(),
+            ..CleanupRules::default()
+        };
+        assert_eq!(rules.clean("Some_Song_Title"), "Some Song Title");
+    }
+
+    #[test]
+    fn title_case_keeps_inner_capitals_and_exceptions() {
+        let rules = rules();
+        assert_eq!(rules.clean("the man of the year"), "The Man of the Year");
+        assert_eq!(rules.clean("back in black by AC/DC"), "Back in Black by AC/DC");
+        assert_eq!(rules.clean("paul McCartney"), "Paul McCartney");
+        assert_eq!(rules.clean("an EP (live-ish)"), "An EP (Live-Ish)");
+    }
+
+    #[test]
+    fn normalizes_featuring() {
+        let rules = CleanupRules {
+            normalize_featuring: true,
+            ..CleanupRules::default()
+        };
+        assert_eq!(rules.clean("Song ft. Someone"), "Song feat. Someone");
+        assert_eq!(rules.clean("Song featuring Someone"), "Song feat. Someone");
+        assert_eq!(rules.clean("Song (ft Someone)"), "Song (feat. Someone)");
+        assert_eq!(rules.clean("Song feat.Someone"), "Song feat. Someone");
+        assert_eq!(rules.clean("Song feat. "), "Song feat.");
+    }
+
+    #[test]
+    fn leaves_ordinary_words_alone() {
+        let rules = CleanupRules {
+            normalize_featuring: true,
+            ..CleanupRules::default()
+        };
+        assert_eq!(rules.clean("Feat of Strength"), "Feat of Strength");
+        assert_eq!(rules.clean("Five ft Tall"), "Five ft Tall");
+    }
+
+    #[test]
+    fn changes_lists_only_modified_fields() {
+        let metadata = AudioMetadata {
+            title: Some("the  song".to_string()),
+            album: Some("Album".to_string()),
+            ..AudioMetadata::default()
+        };
+        let changes = rules().changes(&metadata, &[MetadataField::Title, MetadataField::Album]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, MetadataField::Title);
+        assert_eq!(changes[0].after, "The Song");
+    }
+}
@@ -0,0 +1,316 @@
+use super::fields::{FieldChange, MetadataField};
+use super::AudioMetadata;
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Shortest run of non-ASCII characters considered a mis-encoded word
+const MIN_SUSPICIOUS_RUN: usize = 3;
+
+/// Legacy code pages whose bytes old taggers wrote into ISO-8859-1 frames
+///
+/// ISO-8859-1 text is decoded byte for byte, so such a frame reads as characters in
+/// U+0000-U+00FF that can be turned back into the original bytes and decoded again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LegacyEncoding {
+    #[default]
+    Windows1251,
+    Koi8R,
+    ShiftJis,
+    Gbk,
+    Big5,
+    EucKr,
+    Windows1250,
+    Windows1252,
+    Windows1253,
+    Utf8,
+}
+
+impl LegacyEncoding {
+    pub const ALL: [LegacyEncoding; 10] = [
+        LegacyEncoding::Windows1251,
+        LegacyEncoding::Koi8R,
+        LegacyEncoding::ShiftJis,
+        LegacyEncoding::Gbk,
+        LegacyEncoding::Big5,
+        LegacyEncoding::EucKr,
+        LegacyEncoding::Windows1250,
+        LegacyEncoding::Windows1252,
+        LegacyEncoding::Windows1253,
+        LegacyEncoding::Utf8,
+    ];
+
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            LegacyEncoding::Windows1251 => encoding_rs::WINDOWS_1251,
+            LegacyEncoding::Koi8R => encoding_rs::KOI8_R,
+            LegacyEncoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            LegacyEncoding::Gbk => encoding_rs::GBK,
+            LegacyEncoding::Big5 => encoding_rs::BIG5,
+            LegacyEncoding::EucKr => encoding_rs::EUC_KR,
+            LegacyEncoding::Windows1250 => encoding_rs::WINDOWS_1250,
+            LegacyEncoding::Windows1252 => encoding_rs::WINDOWS_1252,
+            LegacyEncoding::Windows1253 => encoding_rs::WINDOWS_1253,
+            LegacyEncoding::Utf8 => encoding_rs::UTF_8,
+        }
+    }
+
+    /// Decode a value as if its characters were bytes in this code page
+    ///
+    /// Returns None if the value isn't made of single bytes, the bytes aren't valid in the
+    /// code page or decoding doesn't change anything.
+    pub fn reinterpret(self, value: &str) -> Option<String> {
+        let bytes = latin1_bytes(value)?;
+        let (decoded, had_errors) = self.encoding().decode_without_bom_handling(&bytes);
+        (!had_errors && decoded != value).then(|| decoded.into_owned())
+    }
+}
+
+impl fmt::Display for LegacyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LegacyEncoding::Windows1251 => write!(f, "Cyrillic (Windows-1251)"),
+            LegacyEncoding::Koi8R => write!(f, "Cyrillic (KOI8-R)"),
+            LegacyEncoding::ShiftJis => write!(f, "Japanese (Shift-JIS)"),
+            LegacyEncoding::Gbk => write!(f, "Chinese Simplified (GBK)"),
+            LegacyEncoding::Big5 => write!(f, "Chinese Traditional (Big5)"),
+            LegacyEncoding::EucKr => write!(f, "Korean (EUC-KR)"),
+            LegacyEncoding::Windows1250 => write!(f, "Central European (Windows-1250)"),
+            LegacyEncoding::Windows1252 => write!(f, "Western (Windows-1252)"),
+            LegacyEncoding::Windows1253 => write!(f, "Greek (Windows-1253)"),
+            LegacyEncoding::Utf8 => write!(f, "UTF-8"),
+        }
+    }
+}
+
+/// The bytes of a value whose characters all fit in one byte (as read from ISO-8859-1)
+fn latin1_bytes(value: &str) -> Option<Vec<u8>> {
+    value.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+/// Whether a value looks like legacy code page bytes read as ISO-8859-1
+///
+/// Real ISO-8859-1 text uses accented letters sparingly. Mis-encoded text shows C1 control
+/// characters (U+0080-U+009F, common in Shift-JIS and Windows-125x bytes), whole runs or a
+/// large share of non-ASCII characters (Cyrillic or CJK words) or valid UTF-8 sequences
+/// ("CafÃ©").
+pub fn looks_mis_encoded(value: &str) -> bool {
+    let Some(bytes) = latin1_bytes(value) else {
+        return false;
+    };
+    if bytes.is_ascii() {
+        return false;
+    }
+    if bytes.iter().any(|byte| (0x80..=0x9f).contains(byte)) || std::str::from_utf8(&bytes).is_ok() {
+        return true;
+    }
+    // CJK characters may end in an ASCII byte, so count as well as look for runs
+    let non_ascii = bytes.iter().filter(|byte| !byte.is_ascii()).count();
+    let visible = bytes.iter().filter(|byte| !byte.is_ascii_whitespace()).count();
+    bytes
+        .split(|byte| byte.is_ascii())
+        .any(|run| run.len() >= MIN_SUSPICIOUS_RUN)
+        || (non_ascii >= MIN_SUSPICIOUS_RUN && non_ascii * 5 >= visible * 2)
+}
+
+/// Text fields of a track that look mis-encoded
+///
+/// Only fields read from ISO-8859-1 frames are checked; UTF-16 and UTF-8 frames can't
+/// hold code page bytes, and accented text in them is meant as written.
+pub fn suspicious_fields(metadata: &AudioMetadata) -> Vec<MetadataField> {
+    metadata
+        .latin1_fields
+        .iter()
+        .copied()
+        .filter(|field| field.value(metadata).is_some_and(|value| looks_mis_encoded(&value)))
+        .collect()
+}
+
+/// The repaired values of the mis-encoded fields of a track under a code page
+pub fn repair_changes(metadata: &AudioMetadata, encoding: LegacyEncoding) -> Vec<FieldChange> {
+    suspicious_fields(metadata)
+        .into_iter()
+        .filter_map(|field| {
+            let before = field.value(metadata)?;
+            let after = encoding.reinterpret(&before)?;
+            Some(FieldChange { field, before, after })
+        })
+        .collect()
+}
+
+/// Replace the mis-encoded fields of a track with their values under a code page
+///
+/// Returns whether anything changed. The repaired fields are no longer ISO-8859-1 text.
+pub fn repair(metadata: &mut AudioMetadata, encoding: LegacyEncoding) -> bool {
+    let changes = repair_changes(metadata, encoding);
+    for change in &changes {
+        change.field.set_value(metadata, &change.after);
+        metadata.latin1_fields.retain(|field| *field != change.field);
+    }
+    !changes.is_empty()
+}
+
+/// The code page that decodes the mis-encoded fields of a track most plausibly
+///
+/// Bytes that form valid UTF-8 are taken as UTF-8, as other text rarely does. Otherwise the
+/// code pages are ranked by how much their result looks like text, since single-byte code
+/// pages decode almost anything: letters of non-Latin scripts and common CJK characters
+/// score, while symbols and case changes inside a word (typical of the wrong Cyrillic code
+/// page) cost.
+pub fn guess_encoding(metadata: &AudioMetadata) -> Option<LegacyEncoding> {
+    let values: Vec<String> = suspicious_fields(metadata)
+        .into_iter()
+        .filter_map(|field| field.value(metadata))
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    if values.iter().all(|value| LegacyEncoding::Utf8.reinterpret(value).is_some()) {
+        return Some(LegacyEncoding::Utf8);
+    }
+    LegacyEncoding::ALL
+        .into_iter()
+        .filter_map(|encoding| {
+            let decoded: Vec<String> = values
+                .iter()
+                .map(|value| encoding.reinterpret(value))
+                .collect::<Option<_>>()?;
+            Some((encoding, decoded.iter().map(|value| plausibility(value, encoding)).sum::<i64>()))
+        })
+        .fold(None, |best: Option<(LegacyEncoding, i64)>, (encoding, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((encoding, score)),
+        })
+        .map(|(encoding, _)| encoding)
+}
+
+/// How much a value decoded from a code page looks like natural text
+fn plausibility(value: &str, encoding: LegacyEncoding) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    for c in value.chars() {
+        let code = c as u32;
+        score += if c.is_ascii() {
+            if c.is_control() { -3 } else { 0 }
+        } else if let Some(bytes) = double_byte_encoding(c, encoding) {
+            double_byte_score(encoding, &bytes)
+        } else if c.is_alphabetic() {
+            match code {
+                // Latin letters are what the mis-encoded text already shows
+                0x00..=0x036f => 0,
+                // Half-width katakana are rare in tags but come out of many Shift-JIS bytes
+                0xff61..=0xff9f => -1,
+                _ => 2,
+            }
+        } else if matches!(code, 0x2000..=0x206f) || c.is_whitespace() {
+            0
+        } else {
+            -3
+        };
+        if c.is_uppercase() && previous.is_some_and(char::is_lowercase) {
+            score -= 2;
+        }
+        previous = Some(c);
+    }
+    score
+}
+
+/// The two bytes a character was decoded from, for the CJK code pages
+fn double_byte_encoding(c: char, encoding: LegacyEncoding) -> Option<Vec<u8>> {
+    if !matches!(
+        encoding,
+        LegacyEncoding::ShiftJis | LegacyEncoding::Gbk | LegacyEncoding::Big5 | LegacyEncoding::EucKr
+    ) {
+        return None;
+    }
+    let mut buffer = [0; 4];
+    let (bytes, _, _) = encoding.encoding().encode(c.encode_utf8(&mut buffer));
+    (bytes.len() == 2).then(|| bytes.into_owned())
+}
+
+/// Score of a double-byte character by how common the characters of its byte range are
+///
+/// The common ranges hold symbols, kana, Hangul and the most frequent Hanzi/Kanji; any
+/// byte pair decodes to something, but rarely to a run of these.
+fn double_byte_score(encoding: LegacyEncoding, bytes: &[u8]) -> i64 {
+    let (lead, trail) = (bytes[0], bytes[1]);
+    let common = match encoding {
+        LegacyEncoding::ShiftJis => matches!(lead, 0x81..=0x83 | 0x88..=0x98),
+        LegacyEncoding::Gbk => matches!(lead, 0xa1..=0xa9 | 0xb0..=0xd7) && trail >= 0xa1,
+        LegacyEncoding::EucKr => matches!(lead, 0xa1..=0xac | 0xb0..=0xc8) && trail >= 0xa1,
+        LegacyEncoding::Big5 => matches!(lead, 0xa1..=0xc6),
+        _ => false,
+    };
+    // Two bytes per character, so this matches two letters of a single-byte code page
+    if common { 5 } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text as an ISO-8859-1 reader shows it after a tagger wrote it in a code page
+    fn as_latin1(text: &str, encoding: &'static Encoding) -> String {
+        let (bytes, _, _) = encoding.encode(text);
+        bytes.iter().map(|&byte| char::from(byte)).collect()
+    }
+
+    fn latin1_title(title: &str) -> AudioMetadata {
+        AudioMetadata {
+            title: Some(title.to_string()),
+            latin1_fields: vec![MetadataField::Title],
+            ..AudioMetadata::default()
+        }
+    }
+
+    #[test]
+    fn plain_latin1_text_is_not_suspicious() {
+        assert!(!looks_mis_encoded("Hello"));
+        assert!(!looks_mis_encoded("Café del Mar"));
+        assert!(!looks_mis_encoded("Привет"));
+    }
+
+    #[test]
+    fn code_page_text_is_suspicious() {
+        assert!(looks_mis_encoded("CafÃ©"));
+        assert!(looks_mis_encoded(&as_latin1("Привет мир", encoding_rs::WINDOWS_1251)));
+        assert!(looks_mis_encoded(&as_latin1("こんにちは", encoding_rs::SHIFT_JIS)));
+    }
+
+    #[test]
+    fn reinterprets_code_page_bytes() {
+        let mangled = as_latin1("Привет", encoding_rs::WINDOWS_1251);
+        assert_eq!(LegacyEncoding::Windows1251.reinterpret(&mangled).as_deref(), Some("Привет"));
+        assert_eq!(LegacyEncoding::Utf8.reinterpret("CafÃ©").as_deref(), Some("Café"));
+        assert_eq!(LegacyEncoding::Windows1251.reinterpret("Hello"), None);
+        assert_eq!(LegacyEncoding::Windows1251.reinterpret("Привет"), None);
+    }
+
+    #[test]
+    fn guesses_the_code_page() {
+        let cyrillic = latin1_title(&as_latin1("Кино Группа крови", encoding_rs::WINDOWS_1251));
+        assert_eq!(guess_encoding(&cyrillic), Some(LegacyEncoding::Windows1251));
+        assert_eq!(guess_encoding(&latin1_title("CafÃ©")), Some(LegacyEncoding::Utf8));
+        assert_eq!(guess_encoding(&latin1_title("Café")), None);
+    }
+
+    #[test]
+    fn only_checks_latin1_frames() {
+        let metadata = AudioMetadata {
+            latin1_fields: Vec::new(),
+            ..latin1_title("CafÃ©")
+        };
+        assert!(suspicious_fields(&metadata).is_empty());
+        assert_eq!(suspicious_fields(&latin1_title("CafÃ©")), vec![MetadataField::Title]);
+    }
+
+    #[test]
+    fn repair_replaces_the_fields() {
+        let mut metadata = latin1_title(&as_latin1("Привет", encoding_rs::WINDOWS_1251));
+        assert!(repair(&mut metadata, LegacyEncoding::Windows1251));
+        assert_eq!(metadata.title.as_deref(), Some("Привет"));
+        assert!(metadata.latin1_fields.is_empty());
+        assert!(!repair(&mut metadata, LegacyEncoding::Windows1251));
+    }
+}
@@ -0,0 +1,557 @@
+use super::timestamp::{from_year, parse_timestamp};
+use super::{AudioMetadata, TagVersion, Timestamp};
+
+/// Separator of the values of a list field in its text form (e.g. "Rock; Pop")
+pub const LIST_SEPARATOR: &str = "; ";
+
+/// An editable single-value field of `AudioMetadata`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Composer,
+    Genre,
+    Year,
+    Track,
+    TotalTracks,
+    Comment,
+    Disc,
+    TotalDiscs,
+    Publisher,
+    Encoder,
+    Language,
+    Copyright,
+    OriginalArtist,
+    OriginalAlbum,
+    OriginalYear,
+    Bpm,
+    Isrc,
+    Conductor,
+    Remixer,
+    Producer,
+    Grouping,
+    Subtitle,
+    TitleSort,
+    ArtistSort,
+    AlbumSort,
+    AlbumArtistSort,
+    ComposerSort,
+    ArtistUrl,
+    AudioFileUrl,
+    AudioSourceUrl,
+    PublisherUrl,
+    RecordingDate,
+    ReleaseDate,
+    OriginalReleaseDate,
+    EncodingDate,
+    TaggingDate,
+    MusicBrainzRecordingId,
+    MusicBrainzReleaseId,
+    MusicBrainzArtistId,
+    MusicBrainzReleaseGroupId,
+}
+
+impl MetadataField {
+    /// All editable fields, in display order
+    pub const ALL: [MetadataField; 44] = [
+        MetadataField::Title,
+        MetadataField::Artist,
+        MetadataField::Album,
+        MetadataField::AlbumArtist,
+        MetadataField::Composer,
+        MetadataField::Genre,
+        MetadataField::Year,
+        MetadataField::Track,
+        MetadataField::TotalTracks,
+        MetadataField::Comment,
+        MetadataField::Disc,
+        MetadataField::TotalDiscs,
+        MetadataField::Publisher,
+        MetadataField::Encoder,
+        MetadataField::Language,
+        MetadataField::Copyright,
+        MetadataField::OriginalArtist,
+        MetadataField::OriginalAlbum,
+        MetadataField::OriginalYear,
+        MetadataField::Bpm,
+        MetadataField::Isrc,
+        MetadataField::Conductor,
+        MetadataField::Remixer,
+        MetadataField::Producer,
+        MetadataField::Grouping,
+        MetadataField::Subtitle,
+        MetadataField::TitleSort,
+        MetadataField::ArtistSort,
+        MetadataField::AlbumSort,
+        MetadataField::AlbumArtistSort,
+        MetadataField::ComposerSort,
+        MetadataField::ArtistUrl,
+        MetadataField::AudioFileUrl,
+        MetadataField::AudioSourceUrl,
+        MetadataField::PublisherUrl,
+        MetadataField::RecordingDate,
+        MetadataField::ReleaseDate,
+        MetadataField::OriginalReleaseDate,
+        MetadataField::EncodingDate,
+        MetadataField::TaggingDate,
+        MetadataField::MusicBrainzRecordingId,
+        MetadataField::MusicBrainzReleaseId,
+        MetadataField::MusicBrainzArtistId,
+        MetadataField::MusicBrainzReleaseGroupId,
+    ];
+
+    /// Human readable label
+    pub fn label(self) -> &'static str {
+        match self {
+            MetadataField::Title => "Title",
+            MetadataField::Artist => "Artist",
+            MetadataField::Album => "Album",
+            MetadataField::AlbumArtist => "Album Artist",
+            MetadataField::Composer => "Composer",
+            MetadataField::Genre => "Genre",
+            MetadataField::Year => "Year",
+            MetadataField::Track => "Track",
+            MetadataField::TotalTracks => "Total Tracks",
+            MetadataField::Comment => "Comment",
+            MetadataField::Disc => "Disc",
+            MetadataField::TotalDiscs => "Total Discs",
+            MetadataField::Publisher => "Publisher",
+            MetadataField::Encoder => "Encoder",
+            MetadataField::Language => "Language",
+            MetadataField::Copyright => "Copyright",
+            MetadataField::OriginalArtist => "Original Artist",
+            MetadataField::OriginalAlbum => "Original Album",
+            MetadataField::OriginalYear => "Original Year",
+            MetadataField::Bpm => "BPM",
+            MetadataField::Isrc => "ISRC",
+            MetadataField::Conductor => "Conductor",
+            MetadataField::Remixer => "Remixer",
+            MetadataField::Producer => "Producer",
+            MetadataField::Grouping => "Grouping",
+            MetadataField::Subtitle => "Subtitle",
+            MetadataField::TitleSort => "Title Sort",
+            MetadataField::ArtistSort => "Artist Sort",
+            MetadataField::AlbumSort => "Album Sort",
+            MetadataField::AlbumArtistSort => "Album Artist Sort",
+            MetadataField::ComposerSort => "Composer Sort",
+            MetadataField::ArtistUrl => "Artist URL",
+            MetadataField::AudioFileUrl => "Audio File URL",
+            MetadataField::AudioSourceUrl => "Audio Source URL",
+            MetadataField::PublisherUrl => "Publisher URL",
+            MetadataField::RecordingDate => "Recording Date",
+            MetadataField::ReleaseDate => "Release Date",
+            MetadataField::OriginalReleaseDate => "Original Release Date",
+            MetadataField::EncodingDate => "Encoding Date",
+            MetadataField::TaggingDate => "Tagging Date",
+            MetadataField::MusicBrainzRecordingId => "MB Recording ID",
+            MetadataField::MusicBrainzReleaseId => "MB Release ID",
+            MetadataField::MusicBrainzArtistId => "MB Artist ID",
+            MetadataField::MusicBrainzReleaseGroupId => "MB Release Group ID",
+        }
+    }
+
+    /// Find a field by name, as used in filename patterns and templates
+    ///
+    /// Names are matched against the labels ignoring case, spaces and underscores, so
+    /// `albumartist`, `album_artist` and `Album Artist` all name the same field.
+    pub fn from_name(name: &str) -> Option<MetadataField> {
+        let name = normalize_name(name);
+        match name.as_str() {
+            "tracknumber" => return Some(MetadataField::Track),
+            "discnumber" => return Some(MetadataField::Disc),
+            "date" => return Some(MetadataField::RecordingDate),
+            _ => {}
+        }
+        MetadataField::ALL
+            .into_iter()
+            .find(|field| normalize_name(field.label()) == name)
+    }
+
+    /// Whether the field stores a number
+    pub fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            MetadataField::Year
+                | MetadataField::Track
+                | MetadataField::TotalTracks
+                | MetadataField::Disc
+                | MetadataField::TotalDiscs
+                | MetadataField::OriginalYear
+                | MetadataField::Bpm
+        )
+    }
+
+    /// Whether the field stores a timestamp (edited as ISO 8601 text, e.g. `2021-03-14T09:00`)
+    pub fn is_timestamp(self) -> bool {
+        matches!(
+            self,
+            MetadataField::RecordingDate
+                | MetadataField::ReleaseDate
+                | MetadataField::OriginalReleaseDate
+                | MetadataField::EncodingDate
+                | MetadataField::TaggingDate
+        )
+    }
+
+    /// Whether the field is stored in a frame that only exists in ID3v2.4 (TDRL, TDEN, TDTG)
+    pub fn is_id3v24_only(self) -> bool {
+        matches!(
+            self,
+            MetadataField::ReleaseDate | MetadataField::EncodingDate | MetadataField::TaggingDate
+        )
+    }
+
+    /// Whether the field holds a list of values (edited as text joined by `LIST_SEPARATOR`)
+    pub fn is_list(self) -> bool {
+        matches!(self, MetadataField::Artist | MetadataField::Composer | MetadataField::Genre)
+    }
+
+    /// Whether the field stores a URL (a W*** link frame)
+    pub fn is_url(self) -> bool {
+        matches!(
+            self,
+            MetadataField::ArtistUrl
+                | MetadataField::AudioFileUrl
+                | MetadataField::AudioSourceUrl
+                | MetadataField::PublisherUrl
+        )
+    }
+
+    /// The field a sort order field is the sort name of
+    pub fn sort_source(self) -> Option<MetadataField> {
+        match self {
+            MetadataField::TitleSort => Some(MetadataField::Title),
+            MetadataField::ArtistSort => Some(MetadataField::Artist),
+            MetadataField::AlbumSort => Some(MetadataField::Album),
+            MetadataField::AlbumArtistSort => Some(MetadataField::AlbumArtist),
+            MetadataField::ComposerSort => Some(MetadataField::Composer),
+            _ => None,
+        }
+    }
+
+    /// The MusicBrainz entity type of a MusicBrainz identifier field (used in URLs)
+    pub fn musicbrainz_entity(self) -> Option<&'static str> {
+        match self {
+            MetadataField::MusicBrainzRecordingId => Some("recording"),
+            MetadataField::MusicBrainzReleaseId => Some("release"),
+            MetadataField::MusicBrainzArtistId => Some("artist"),
+            MetadataField::MusicBrainzReleaseGroupId => Some("release-group"),
+            _ => None,
+        }
+    }
+
+    /// Current value of the field as text
+    pub fn value(self, metadata: &AudioMetadata) -> Option<String> {
+        match self {
+            MetadataField::Year => metadata.recorded.map(|value| value.year.to_string()),
+            MetadataField::Track => metadata.track.map(|value| value.to_string()),
+            MetadataField::TotalTracks => metadata.total_tracks.map(|value| value.to_string()),
+            MetadataField::Disc => metadata.disc.map(|value| value.to_string()),
+            MetadataField::TotalDiscs => metadata.total_discs.map(|value| value.to_string()),
+            MetadataField::OriginalYear => metadata.original_released.map(|value| value.year.to_string()),
+            MetadataField::Bpm => metadata.bpm.map(|value| value.to_string()),
+            _ if self.is_timestamp() => self.timestamp(metadata).map(|value| value.to_string()),
+            _ if self.is_list() => {
+                let values = self.list_values(metadata);
+                (!values.is_empty()).then(|| values.join(LIST_SEPARATOR))
+            }
+            _ => self.text_value(metadata).clone(),
+        }
+    }
+
+    /// Whether a text value can be stored in the field (numeric fields need a number,
+    /// timestamp fields an ISO 8601 date, or nothing)
+    pub fn accepts(self, value: &str) -> bool {
+        let value = value.trim();
+        if self.is_numeric() {
+            value.is_empty() || value.parse::<u32>().is_ok()
+        } else if self.is_timestamp() {
+            value.is_empty() || parse_timestamp(value).is_some()
+        } else {
+            true
+        }
+    }
+
+    /// Store a text value in the field; an empty value clears it
+    ///
+    /// Values that the field doesn't accept are ignored. The year fields change the year of
+    /// their timestamp and keep the rest of it.
+    pub fn set_value(self, metadata: &mut AudioMetadata, value: &str) {
+        if !self.accepts(value) {
+            return;
+        }
+        let number = value.trim().parse::<u32>().ok();
+        match self {
+            MetadataField::Year => metadata.recorded = with_year(metadata.recorded, number),
+            MetadataField::Track => metadata.track = number,
+            MetadataField::TotalTracks => metadata.total_tracks = number,
+            MetadataField::Disc => metadata.disc = number,
+            MetadataField::TotalDiscs => metadata.total_discs = number,
+            MetadataField::OriginalYear => {
+                metadata.original_released = with_year(metadata.original_released, number)
+            }
+            MetadataField::Bpm => metadata.bpm = number,
+            _ if self.is_timestamp() => *self.timestamp_mut(metadata) = parse_timestamp(value),
+            _ if self.is_list() => *self.list_values_mut(metadata) = split_list(value),
+            _ => *self.text_value_mut(metadata) = (!value.is_empty()).then(|| value.to_string()),
+        }
+    }
+
+    /// Copy the value of the field from one track to another
+    ///
+    /// Lists and timestamps are copied as stored, so list values that contain the separator
+    /// and the precision of dates survive.
+    pub fn copy_value(self, from: &AudioMetadata, to: &mut AudioMetadata) {
+        if self.is_list() {
+            *self.list_values_mut(to) = self.list_values(from).clone();
+        } else if self.is_timestamp() {
+            *self.timestamp_mut(to) = self.timestamp(from);
+        } else {
+            self.set_value(to, &self.value(from).unwrap_or_default());
+        }
+    }
+
+    /// The storage of a timestamp field
+    fn timestamp(self, metadata: &AudioMetadata) -> Option<Timestamp> {
+        match self {
+            MetadataField::RecordingDate => metadata.recorded,
+            MetadataField::ReleaseDate => metadata.released,
+            MetadataField::OriginalReleaseDate => metadata.original_released,
+            MetadataField::EncodingDate => metadata.encoded,
+            MetadataField::TaggingDate => metadata.tagged,
+            _ => unreachable!("field {:?} is not a timestamp", self),
+        }
+    }
+
+    /// The mutable storage of a timestamp field
+    fn timestamp_mut(self, metadata: &mut AudioMetadata) -> &mut Option<Timestamp> {
+        match self {
+            MetadataField::RecordingDate => &mut metadata.recorded,
+            MetadataField::ReleaseDate => &mut metadata.released,
+            MetadataField::OriginalReleaseDate => &mut metadata.original_released,
+            MetadataField::EncodingDate => &mut metadata.encoded,
+            MetadataField::TaggingDate => &mut metadata.tagged,
+            _ => unreachable!("field {:?} is not a timestamp", self),
+        }
+    }
+
+    /// The storage of a list field
+    pub fn list_values(self, metadata: &AudioMetadata) -> &Vec<String> {
+        match self {
+            MetadataField::Artist => &metadata.artists,
+            MetadataField::Composer => &metadata.composers,
+            MetadataField::Genre => &metadata.genres,
+            _ => unreachable!("field {:?} is not a list", self),
+        }
+    }
+
+    /// The mutable storage of a list field
+    pub fn list_values_mut(self, metadata: &mut AudioMetadata) -> &mut Vec<String> {
+        match self {
+            MetadataField::Artist => &mut metadata.artists,
+            MetadataField::Composer => &mut metadata.composers,
+            MetadataField::Genre => &mut metadata.genres,
+            _ => unreachable!("field {:?} is not a list", self),
+        }
+    }
+
+    /// The storage of a text field
+    fn text_value(self, metadata: &AudioMetadata) -> &Option<String> {
+        match self {
+            MetadataField::Title => &metadata.title,
+            MetadataField::Album => &metadata.album,
+            MetadataField::AlbumArtist => &metadata.album_artist,
+            MetadataField::Comment => &metadata.comment,
+            MetadataField::Publisher => &metadata.publisher,
+            MetadataField::Encoder => &metadata.encoder,
+            MetadataField::Language => &metadata.language,
+            MetadataField::Copyright => &metadata.copyright,
+            MetadataField::OriginalArtist => &metadata.original_artist,
+            MetadataField::OriginalAlbum => &metadata.original_album,
+            MetadataField::Isrc => &metadata.isrc,
+            MetadataField::Conductor => &metadata.conductor,
+            MetadataField::Remixer => &metadata.remixer,
+            MetadataField::Producer => &metadata.producer,
+            MetadataField::Grouping => &metadata.grouping,
+            MetadataField::Subtitle => &metadata.subtitle,
+            MetadataField::TitleSort => &metadata.title_sort,
+            MetadataField::ArtistSort => &metadata.artist_sort,
+            MetadataField::AlbumSort => &metadata.album_sort,
+            MetadataField::AlbumArtistSort => &metadata.album_artist_sort,
+            MetadataField::ComposerSort => &metadata.composer_sort,
+            MetadataField::ArtistUrl => &metadata.artist_url,
+            MetadataField::AudioFileUrl => &metadata.audio_file_url,
+            MetadataField::AudioSourceUrl => &metadata.audio_source_url,
+            MetadataField::PublisherUrl => &metadata.publisher_url,
+            MetadataField::MusicBrainzRecordingId => &metadata.musicbrainz_recording_id,
+            MetadataField::MusicBrainzReleaseId => &metadata.musicbrainz_release_id,
+            MetadataField::MusicBrainzArtistId => &metadata.musicbrainz_artist_id,
+            MetadataField::MusicBrainzReleaseGroupId => &metadata.musicbrainz_release_group_id,
+            MetadataField::Year
+            | MetadataField::Track
+            | MetadataField::TotalTracks
+            | MetadataField::Disc
+            | MetadataField::TotalDiscs
+            | MetadataField::OriginalYear
+            | MetadataField::Bpm
+            | MetadataField::RecordingDate
+            | MetadataField::ReleaseDate
+            | MetadataField::OriginalReleaseDate
+            | MetadataField::EncodingDate
+            | MetadataField::TaggingDate
+            | MetadataField::Artist
+            | MetadataField::Composer
+            | MetadataField::Genre => unreachable!("field {:?} has no text storage", self),
+        }
+    }
+
+    /// The mutable storage of a text field
+    fn text_value_mut(self, metadata: &mut AudioMetadata) -> &mut Option<String> {
+        match self {
+            MetadataField::Title => &mut metadata.title,
+            MetadataField::Album => &mut metadata.album,
+            MetadataField::AlbumArtist => &mut metadata.album_artist,
+            MetadataField::Comment => &mut metadata.comment,
+            MetadataField::Publisher => &mut metadata.publisher,
+            MetadataField::Encoder => &mut metadata.encoder,
+            MetadataField::Language => &mut metadata.language,
+            MetadataField::Copyright => &mut metadata.copyright,
+            MetadataField::OriginalArtist => &mut metadata.original_artist,
+            MetadataField::OriginalAlbum => &mut metadata.original_album,
+            MetadataField::Isrc => &mut metadata.isrc,
+            MetadataField::Conductor => &mut metadata.conductor,
+            MetadataField::Remixer => &mut metadata.remixer,
+            MetadataField::Producer => &mut metadata.producer,
+            MetadataField::Grouping => &mut metadata.grouping,
+            MetadataField::Subtitle => &mut metadata.subtitle,
+            MetadataField::TitleSort => &mut metadata.title_sort,
+            MetadataField::ArtistSort => &mut metadata.artist_sort,
+            MetadataField::AlbumSort => &mut metadata.album_sort,
+            MetadataField::AlbumArtistSort => &mut metadata.album_artist_sort,
+            MetadataField::ComposerSort => &mut metadata.composer_sort,
+            MetadataField::ArtistUrl => &mut metadata.artist_url,
+            MetadataField::AudioFileUrl => &mut metadata.audio_file_url,
+            MetadataField::AudioSourceUrl => &mut metadata.audio_source_url,
+            MetadataField::PublisherUrl => &mut metadata.publisher_url,
+            MetadataField::MusicBrainzRecordingId => &mut metadata.musicbrainz_recording_id,
+            MetadataField::MusicBrainzReleaseId => &mut metadata.musicbrainz_release_id,
+            MetadataField::MusicBrainzArtistId => &mut metadata.musicbrainz_artist_id,
+            MetadataField::MusicBrainzReleaseGroupId => &mut metadata.musicbrainz_release_group_id,
+            MetadataField::Year
+            | MetadataField::Track
+            | MetadataField::TotalTracks
+            | MetadataField::Disc
+            | MetadataField::TotalDiscs
+            | MetadataField::OriginalYear
+            | MetadataField::Bpm
+            | MetadataField::RecordingDate
+            | MetadataField::ReleaseDate
+            | MetadataField::OriginalReleaseDate
+            | MetadataField::EncodingDate
+            | MetadataField::TaggingDate
+            | MetadataField::Artist
+            | MetadataField::Composer
+            | MetadataField::Genre => unreachable!("field {:?} has no text storage", self),
+        }
+    }
+}
+
+impl std::fmt::Display for MetadataField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// A proposed change of a field value (shown as before/after in previews)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: MetadataField,
+    pub before: String,
+    pub after: String,
+}
+
+/// Change the year of a timestamp, keeping the rest of it; no year clears the timestamp
+fn with_year(timestamp: Option<Timestamp>, year: Option<u32>) -> Option<Timestamp> {
+    let year = i32::try_from(year?).ok()?;
+    Some(match timestamp {
+        Some(timestamp) => Timestamp { year, ..timestamp },
+        None => from_year(year),
+    })
+}
+
+/// Leading articles moved to the end of sort names
+const SORT_ARTICLES: &[&str] = &["The", "A", "An"];
+
+/// The sort name of a value, with a leading article moved to the end
+///
+/// "The Beatles" becomes "Beatles, The". The values of a list ("The Who; The Band") are
+/// converted one by one.
+pub fn sort_name(value: &str) -> String {
+    split_list(value)
+        .iter()
+        .map(|value| {
+            SORT_ARTICLES
+                .iter()
+                .find_map(|article| {
+                    let rest = value.get(article.len()..)?;
+                    (value[..article.len()].eq_ignore_ascii_case(article) && rest.starts_with(' '))
+                        .then(|| format!("{}, {}", rest.trim_start(), &value[..article.len()]))
+                })
+                .unwrap_or_else(|| value.clone())
+        })
+        .collect::<Vec<_>>()
+        .join(LIST_SEPARATOR)
+}
+
+/// ID3v2.4-only fields changed from `original` that the track's older tag can't store
+///
+/// TDRL, TDEN and TDTG aren't written to ID3v2.3 (or v2.2) tags, so these changes are lost
+/// on save unless the tag is converted first.
+pub fn unwritable_changes(original: &AudioMetadata, metadata: &AudioMetadata) -> Vec<MetadataField> {
+    if !matches!(metadata.tag_version, Some(TagVersion::Id3v22 | TagVersion::Id3v23)) {
+        return Vec::new();
+    }
+    MetadataField::ALL
+        .into_iter()
+        .filter(|field| field.is_id3v24_only() && field.value(original) != field.value(metadata))
+        .collect()
+}
+
+/// Split the text form of a list field into its values
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Lowercase a field name and drop spaces and underscores
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether a value is a well-formed UUID (`xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`)
+pub fn is_valid_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Split a MusicBrainz identifier field into its individual IDs (multi-artist values use "/" or ";")
+pub fn musicbrainz_ids(value: &str) -> Vec<&str> {
+    value
+        .split(['/', ';', '\0'])
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect()
+}
@@ -0,0 +1,219 @@
+use super::fields::MetadataField;
+use super::AudioMetadata;
+use regex::Regex;
+use std::path::Path;
+
+/// Placeholder that matches part of a file name without storing it
+const IGNORE_PLACEHOLDER: &str = "ignore";
+
+/// Patterns offered before the user saves any of their own
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "%artist% - %title%",
+    "%track% - %title%",
+    "%track%. %artist% - %title%",
+    "%albumartist%/%album%/%track% - %title%",
+    "%artist%/%album% (%year%)/%track% %title%",
+];
+
+/// A compiled pattern that extracts field values from file paths
+///
+/// Patterns use `%field%` placeholders (`%albumartist%/%album%/%track% - %title%`), where
+/// each `/` matches one parent folder. Patterns containing named groups
+/// (`(?P<artist>.+) - (?P<title>.+)`) are used as regular expressions, the group names
+/// naming the fields. `%ignore%` / `(?P<ignore>..)` match text that isn't stored.
+#[derive(Debug, Clone)]
+pub struct FilenamePattern {
+    regex: Regex,
+    fields: Vec<Option<MetadataField>>, // Field of each capture group, in group order
+    components: usize,                  // Number of trailing path components matched
+}
+
+impl FilenamePattern {
+    /// Compile a placeholder or regular expression pattern
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        if pattern.trim().is_empty() {
+            return Err("Pattern is empty".to_string());
+        }
+        let components = pattern.matches('/').count() + 1;
+
+        let (regex, fields) = if pattern.contains("(?P<") || pattern.contains("(?<") {
+            let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| e.to_string())?;
+            let fields = regex
+                .capture_names()
+                .skip(1)
+                .map(|name| name.map_or(Ok(None), field_for_placeholder))
+                .collect::<Result<_, _>>()?;
+            (regex, fields)
+        } else {
+            placeholder_regex(pattern)?
+        };
+
+        Ok(Self {
+            regex,
+            fields,
+            components,
+        })
+    }
+
+    /// Field values the pattern extracts from a path, or None if the path doesn't match
+    ///
+    /// The extension is ignored. Values a field can't hold (e.g. text for Track) are skipped.
+    pub fn extract(&self, path: &Path) -> Option<Vec<(MetadataField, String)>> {
+        let stem = path.file_stem()?.to_string_lossy();
+        let mut parts: Vec<String> = path
+            .parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .filter_map(|component| match component {
+                        std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        parts.push(stem.to_string());
+        if parts.len() < self.components {
+            return None;
+        }
+        let subject = parts[parts.len() - self.components..].join("/");
+
+        let captures = self.regex.captures(&subject)?;
+        let values = captures
+            .iter()
+            .skip(1)
+            .zip(&self.fields)
+            .filter_map(|(capture, field)| {
+                let field = (*field)?;
+                let value = capture?.as_str().trim();
+                (!value.is_empty() && field.accepts(value)).then(|| (field, value.to_string()))
+            })
+            .collect();
+        Some(values)
+    }
+}
+
+/// Store extracted values in metadata
+pub fn apply_values(metadata: &mut AudioMetadata, values: &[(MetadataField, String)]) {
+    for (field, value) in values {
+        field.set_value(metadata, value);
+    }
+}
+
+/// Translate a `%placeholder%` pattern into an anchored regular expression
+fn placeholder_regex(pattern: &str) -> Result<(Regex, Vec<Option<MetadataField>>), String> {
+    let parts: Vec<&str> = pattern.split('%').collect();
+    if parts.len().is_multiple_of(2) {
+        return Err("Unmatched % in pattern".to_string());
+    }
+
+    let mut expression = String::from("^");
+    let mut fields = Vec::new();
+    // Even parts are literal text, odd parts are placeholder names
+    for (index, part) in parts.iter().enumerate() {
+        if index.is_multiple_of(2) {
+            expression.push_str(&regex::escape(part));
+            continue;
+        }
+        let field = field_for_placeholder(part)?;
+        if field.is_some_and(MetadataField::is_numeric) {
+            expression.push_str(r"(\d+)");
+        } else {
+            expression.push_str("([^/]+?)");
+        }
+        fields.push(field);
+    }
+    expression.push('$');
+
+    let regex = Regex::new(&expression).map_err(|e| e.to_string())?;
+    Ok((regex, fields))
+}
+
+/// The field a placeholder names (None for `ignore`)
+fn field_for_placeholder(name: &str) -> Result<Option<MetadataField>, String> {
+    if name.eq_ignore_ascii_case(IGNORE_PLACEHOLDER) {
+        return Ok(None);
+    }
+    MetadataField::from_name(name)
+        .map(Some)
+        .ok_or_else(|| format!("Unknown field in pattern: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(pattern: &str, path: &str) -> Option<Vec<(MetadataField, String)>> {
+        FilenamePattern::parse(pattern).unwrap().extract(Path::new(path))
+    }
+
+    fn values(pairs: &[(MetadataField, &str)]) -> Vec<(MetadataField, String)> {
+        pairs.iter().map(|(field, value)| (*field, value.to_string())).collect()
+    }
+
+    #[test]
+    fn extracts_placeholders_from_the_file_name() {
+        assert_eq!(
+            extract("%track% - %title%", "/music/03 - Song Name.mp3"),
+            Some(values(&[(MetadataField::Track, "03"), (MetadataField::Title, "Song Name")]))
+        );
+    }
+
+    #[test]
+    fn slashes_match_parent_folders() {
+        assert_eq!(
+            extract("%albumartist%/%album%/%track% - %title%", "/music/Band/Record/01 - Intro.mp3"),
+            Some(values(&[
+                (MetadataField::AlbumArtist, "Band"),
+                (MetadataField::Album, "Record"),
+                (MetadataField::Track, "01"),
+                (MetadataField::Title, "Intro"),
+            ]))
+        );
+        assert_eq!(extract("%artist%/%album%/%title%", "Intro.mp3"), None);
+    }
+
+    #[test]
+    fn ignores_text_matched_by_ignore() {
+        assert_eq!(
+            extract("%ignore% - %title%", "Somebody - Song.mp3"),
+            Some(values(&[(MetadataField::Title, "Song")]))
+        );
+    }
+
+    #[test]
+    fn numeric_placeholders_only_match_digits() {
+        assert_eq!(extract("%track% - %title%", "Intro - Song.mp3"), None);
+    }
+
+    #[test]
+    fn regular_expression_patterns_use_group_names() {
+        assert_eq!(
+            extract(r"(?P<artist>.+?) _ (?P<title>.+)", "Band _ Song.flac"),
+            Some(values(&[(MetadataField::Artist, "Band"), (MetadataField::Title, "Song")]))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(FilenamePattern::parse("  ").is_err());
+        assert!(FilenamePattern::parse("%artist - %title%").is_err());
+        assert!(FilenamePattern::parse("%nonsense% - %title%").is_err());
+        assert!(FilenamePattern::parse("(?P<artist>.+").is_err());
+    }
+
+    #[test]
+    fn default_patterns_parse() {
+        for pattern in DEFAULT_PATTERNS {
+            assert!(FilenamePattern::parse(pattern).is_ok(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn applies_extracted_values() {
+        let mut metadata = AudioMetadata::default();
+        apply_values(&mut metadata, &values(&[(MetadataField::Track, "7"), (MetadataField::Title, "Song")]));
+        assert_eq!(metadata.track, Some(7));
+        assert_eq!(metadata.title.as_deref(), Some("Song"));
+    }
+}
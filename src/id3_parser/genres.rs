@@ -0,0 +1,157 @@
+use super::ListSeparator;
+
+/// The ID3v1 genres with the Winamp extensions, indexed by their ID3v1 number
+pub const ID3V1_GENRES: [&str; 192] = [
+    "Blues", "Classic Rock", "Country", "Dance", "Disco", "Funk", "Grunge", "Hip-Hop", "Jazz", "Metal",
+    "New Age", "Oldies", "Other", "Pop", "R&B", "Rap", "Reggae", "Rock", "Techno", "Industrial",
+    "Alternative", "Ska", "Death Metal", "Pranks", "Soundtrack", "Euro-Techno", "Ambient", "Trip-Hop", "Vocal",
+    "Jazz+Funk", "Fusion", "Trance", "Classical", "Instrumental", "Acid", "House", "Game", "Sound Clip",
+    "Gospel", "Noise", "Alternative Rock", "Bass", "Soul", "Punk", "Space", "Meditative", "Instrumental Pop",
+    "Instrumental Rock", "Ethnic", "Gothic", "Darkwave", "Techno-Industrial", "Electronic", "Pop-Folk",
+    "Eurodance", "Dream", "Southern Rock", "Comedy", "Cult", "Gangsta", "Top 40", "Christian Rap", "Pop/Funk",
+    "Jungle", "Native American", "Cabaret", "New Wave", "Psychedelic", "Rave", "Showtunes", "Trailer", "Lo-Fi",
+    "Tribal", "Acid Punk", "Acid Jazz", "Polka", "Retro", "Musical", "Rock & Roll", "Hard Rock", "Folk",
+    "Folk-Rock", "National Folk", "Swing", "Fast Fusion", "Bebop", "Latin", "Revival", "Celtic", "Bluegrass",
+    "Avantgarde", "Gothic Rock", "Progressive Rock", "Psychedelic Rock", "Symphonic Rock", "Slow Rock",
+    "Big Band", "Chorus", "Easy Listening", "Acoustic", "Humour", "Speech", "Chanson", "Opera",
+    "Chamber Music", "Sonata", "Symphony", "Booty Bass", "Primus", "Porn Groove", "Satire", "Slow Jam", "Club",
+    "Tango", "Samba", "Folklore", "Ballad", "Power Ballad", "Rhythmic Soul", "Freestyle", "Duet", "Punk Rock",
+    "Drum Solo", "A Cappella", "Euro-House", "Dance Hall", "Goa", "Drum & Bass", "Club-House",
+    "Hardcore Techno", "Terror", "Indie", "BritPop", "Negerpunk", "Polsk Punk", "Beat", "Christian Gangsta Rap",
+    "Heavy Metal", "Black Metal", "Crossover", "Contemporary Christian", "Christian Rock", "Merengue", "Salsa",
+    "Thrash Metal", "Anime", "JPop", "Synthpop", "Abstract", "Art Rock", "Baroque", "Bhangra", "Big Beat",
+    "Breakbeat", "Chillout", "Downtempo", "Dub", "EBM", "Eclectic", "Electro", "Electroclash", "Emo",
+    "Experimental", "Garage", "Global", "IDM", "Illbient", "Industro-Goth", "Jam Band", "Krautrock",
+    "Leftfield", "Lounge", "Math Rock", "New Romantic", "Nu-Breakz", "Post-Punk", "Post-Rock", "Psytrance",
+    "Shoegaze", "Space Rock", "Trop Rock", "World Music", "Neoclassical", "Audiobook", "Audio Theatre",
+    "Neue Deutsche Welle", "Podcast", "Indie Rock", "G-Funk", "Dubstep", "Garage Rock", "Psybient",
+];
+
+/// Decode the values of a TCON frame into genre names
+///
+/// Handles the ID3v2.3 forms "(17)", "(17)Rock" (a reference followed by a refinement),
+/// "(17)(18)", "(RX)" (remix), "(CR)" (cover) and "((" (an escaped opening bracket), as well
+/// as bare ID3v1 numbers used by ID3v2.4. Duplicates are dropped, ignoring case.
+pub fn decode_genres<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut genres: Vec<String> = Vec::new();
+    for value in values {
+        for genre in decode_value(value.trim()) {
+            if !genre.is_empty() && !genres.iter().any(|existing| existing.eq_ignore_ascii_case(&genre)) {
+                genres.push(genre);
+            }
+        }
+    }
+    genres
+}
+
+fn decode_value(value: &str) -> Vec<String> {
+    if let Ok(index) = value.parse::<usize>() {
+        return vec![genre_name(index).map_or_else(|| value.to_string(), str::to_string)];
+    }
+
+    let mut genres = Vec::new();
+    let mut rest = value;
+    while let Some(reference) = rest.strip_prefix('(').filter(|reference| !reference.starts_with('(')) {
+        let Some((code, after)) = reference.split_once(')') else {
+            break;
+        };
+        let genre = match code {
+            "RX" => "Remix".to_string(),
+            "CR" => "Cover".to_string(),
+            _ => match code.parse::<usize>().ok().and_then(genre_name) {
+                Some(name) => name.to_string(),
+                // Not a reference after all, keep the text as it is
+                None => break,
+            },
+        };
+        genres.push(genre);
+        rest = after;
+    }
+
+    // The refinement (or a plain genre name); "((" stands for a literal "("
+    let refinement = rest.strip_prefix('(').filter(|escaped| escaped.starts_with('(')).unwrap_or(rest);
+    genres.push(refinement.trim().to_string());
+    genres
+}
+
+/// Split an ID3v2.3 TCON value into the genres written joined by the separator
+///
+/// ID3v2.3 has no multi-value text frames, so several genres are stored as "Rock/Shoegaze".
+/// ID3v1 genre names containing a slash ("Pop/Funk") are kept whole.
+pub fn split_legacy_genres(value: &str, separator: ListSeparator) -> Vec<&str> {
+    if ID3V1_GENRES.iter().any(|genre| genre.eq_ignore_ascii_case(value.trim())) {
+        return vec![value];
+    }
+    separator.split(value)
+}
+
+/// The name of an ID3v1 genre number
+pub fn genre_name(index: usize) -> Option<&'static str> {
+    ID3V1_GENRES.get(index).copied()
+}
+
+/// Genres of the ID3v1 list and the favorites that contain a search text, favorites first
+///
+/// Names starting with the search come first; an empty search lists the favorites only.
+pub fn search_genres<'a>(search: &str, favorites: &'a [String]) -> Vec<&'a str> {
+    let search = search.trim().to_lowercase();
+    let mut results: Vec<&str> = favorites
+        .iter()
+        .map(String::as_str)
+        .filter(|genre| genre.to_lowercase().contains(&search))
+        .collect();
+    if search.is_empty() {
+        return results;
+    }
+    let mut list: Vec<&str> = ID3V1_GENRES
+        .into_iter()
+        .filter(|genre| genre.to_lowercase().contains(&search))
+        .filter(|genre| !favorites.iter().any(|favorite| favorite.eq_ignore_ascii_case(genre)))
+        .collect();
+    list.sort_by_key(|genre| (!genre.to_lowercase().starts_with(&search), *genre));
+    results.extend(list);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_id3v1_references() {
+        assert_eq!(decode_genres(["(17)"]), ["Rock"]);
+        assert_eq!(decode_genres(["17"]), ["Rock"]);
+        assert_eq!(decode_genres(["(17)(18)"]), ["Rock", "Techno"]);
+        assert_eq!(decode_genres(["(RX)(CR)"]), ["Remix", "Cover"]);
+    }
+
+    #[test]
+    fn keeps_refinements_and_plain_names() {
+        assert_eq!(decode_genres(["(17)Indie Rock"]), ["Rock", "Indie Rock"]);
+        assert_eq!(decode_genres(["((Live)"]), ["(Live)"]);
+        assert_eq!(decode_genres(["Shoegaze"]), ["Shoegaze"]);
+        assert_eq!(decode_genres(["(abc)"]), ["(abc)"]);
+        assert_eq!(decode_genres(["999"]), ["999"]);
+    }
+
+    #[test]
+    fn drops_duplicates_ignoring_case() {
+        assert_eq!(decode_genres(["Rock", "(17)", "rock", " "]), ["Rock"]);
+    }
+
+    #[test]
+    fn splits_legacy_values_but_keeps_id3v1_names() {
+        assert_eq!(split_legacy_genres("Rock/Shoegaze", ListSeparator::Slash), ["Rock", "Shoegaze"]);
+        assert_eq!(split_legacy_genres("Pop/Funk", ListSeparator::Slash), ["Pop/Funk"]);
+        assert_eq!(split_legacy_genres("Rock/Shoegaze", ListSeparator::Semicolon), ["Rock/Shoegaze"]);
+    }
+
+    #[test]
+    fn searches_favorites_first() {
+        let favorites = vec!["Rock Steady".to_string()];
+        assert_eq!(search_genres("", &favorites), ["Rock Steady"]);
+        let results = search_genres("rock", &favorites);
+        assert_eq!(results[..3], ["Rock Steady", "Rock", "Rock & Roll"]);
+        assert!(results.contains(&"Hard Rock"));
+    }
+}
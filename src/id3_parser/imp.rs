@@ -1,10 +1,17 @@
-use id3::TagLike;
+use id3::frame::{
+    Comment, ExtendedLink, ExtendedText, InvolvedPeopleList, InvolvedPeopleListItem, Lyrics, Picture, PictureType,
+    SynchronisedLyrics, SynchronisedLyricsType, TableOfContents, TimestampFormat, UniqueFileIdentifier, Unknown,
+};
+use id3::{Frame, Tag, TagLike, Version};
 
-use super::{AudioMetadata, ParseError};
+use super::fields::MetadataField;
+use super::genres::{decode_genres, split_legacy_genres};
+use super::timestamp::{from_year, parse_timestamp, TimestampParts};
+use super::{AudioMetadata, ListSeparator, Chapter, Credit, ParseError, Popularimeter, ReplayGain, SyncedLyricLine, Timestamp};
 use std::path::Path;
 
 /// Internal implementation of ID3 parsing
-pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseError> {
+pub fn parse_id3_impl<P: AsRef<Path>>(path: P, separator: ListSeparator) -> Result<AudioMetadata, ParseError> {
     let path_ref = path.as_ref();
     
     log::debug!("Parsing ID3 tags from: {:?}", path_ref);
@@ -31,7 +38,72 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
         }
     };
 
-    let mut metadata = AudioMetadata::default();
+    Ok(extract_metadata(&tag, separator))
+}
+
+pub fn supports_id3_impl<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ["mp3", "aac", "wav", "aif", "aiff"].iter().any(|id3_ext| ext.eq_ignore_ascii_case(id3_ext)))
+}
+
+pub fn has_id3v1_impl<P: AsRef<Path>>(path: P) -> bool {
+    match std::fs::File::open(path.as_ref()) {
+        Ok(file) => id3::v1::Tag::is_candidate(file).unwrap_or(false),
+        Err(e) => {
+            log::warn!("Could not open {:?} to look for an ID3v1 tag: {}", path.as_ref(), e);
+            false
+        }
+    }
+}
+
+/// Build an `AudioMetadata` from an already loaded tag
+/// Text frames of the editable fields whose encoding is checked for mis-encoded text
+const TEXT_FIELD_FRAMES: &[(&str, MetadataField)] = &[
+    ("TIT2", MetadataField::Title),
+    ("TPE1", MetadataField::Artist),
+    ("TALB", MetadataField::Album),
+    ("TPE2", MetadataField::AlbumArtist),
+    ("TCOM", MetadataField::Composer),
+    ("TCON", MetadataField::Genre),
+    ("COMM", MetadataField::Comment),
+    ("TPUB", MetadataField::Publisher),
+    ("TENC", MetadataField::Encoder),
+    ("TLAN", MetadataField::Language),
+    ("TCOP", MetadataField::Copyright),
+    ("TOPE", MetadataField::OriginalArtist),
+    ("TOAL", MetadataField::OriginalAlbum),
+    ("TSRC", MetadataField::Isrc),
+    ("TPE3", MetadataField::Conductor),
+    ("TPE4", MetadataField::Remixer),
+    ("TPRO", MetadataField::Producer),
+    ("TIT1", MetadataField::Grouping),
+    ("TIT3", MetadataField::Subtitle),
+    ("TSOT", MetadataField::TitleSort),
+    ("TSOP", MetadataField::ArtistSort),
+    ("TSOA", MetadataField::AlbumSort),
+    ("TSO2", MetadataField::AlbumArtistSort),
+    ("TSOC", MetadataField::ComposerSort),
+];
+
+fn extract_metadata(tag: &Tag, separator: ListSeparator) -> AudioMetadata {
+    let mut metadata = AudioMetadata {
+        tag_version: Some(tag.version().into()),
+        ..AudioMetadata::default()
+    };
+
+    // Fields stored as ISO-8859-1, the only frames that can hold legacy code page bytes
+    // (the first frame counts, as it is the one read)
+    metadata.latin1_fields = TEXT_FIELD_FRAMES
+        .iter()
+        .filter(|(frame_id, _)| {
+            tag.frames()
+                .find(|frame| frame.id() == *frame_id)
+                .is_some_and(|frame| frame.encoding() == Some(id3::Encoding::Latin1))
+        })
+        .map(|(_, field)| *field)
+        .collect();
 
     // Extract title
     if let Some(title) = tag.title() {
@@ -40,43 +112,46 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
 
     // Extract duration
     if let Some(duration) = tag.duration() {
-        metadata.duration = Some(duration as u32);
+        metadata.duration = Some(duration);
     }
 
-    // Extract artist
-    if let Some(artist) = tag.artist() {
-        metadata.artist = Some(artist.to_string());
-    }
+    // Extract artists (TPE1, possibly several values)
+    metadata.artists = read_text_values(tag, "TPE1", separator);
 
     // Extract album
     if let Some(album) = tag.album() {
         metadata.album = Some(album.to_string());
     }
 
-    // Extract year
-    if let Some(year) = tag.year() {
-        metadata.year = Some(year as u32);
-    }
+    // Extract recording time (TDRC in ID3v2.4, TYER + TDAT + TIME in ID3v2.3; either is
+    // used if the tag only has the other)
+    metadata.recorded = if tag.version() == Version::Id3v24 {
+        read_timestamp(tag, "TDRC").or_else(|| read_legacy_recording_time(tag))
+    } else {
+        read_legacy_recording_time(tag).or_else(|| read_timestamp(tag, "TDRC"))
+    };
 
-    // Extract genre
-    if let Some(genre) = tag.genre() {
-        metadata.genre = Some(genre.to_string());
-    }
+    // Extract genres (TCON, decoding ID3v1 genre numbers such as "(17)")
+    let genres = tag.genres().unwrap_or_default();
+    metadata.genres = if tag.version() == Version::Id3v24 {
+        decode_genres(genres)
+    } else {
+        decode_genres(genres.into_iter().flat_map(|genre| split_legacy_genres(genre, separator)))
+    };
 
     // Extract track number
     if let Some(track) = tag.track() {
-        metadata.track = Some(track as u32);
+        metadata.track = Some(track);
     }
+    metadata.total_tracks = tag.total_tracks();
 
     // Extract album artist
     if let Some(album_artist) = tag.album_artist() {
         metadata.album_artist = Some(album_artist.to_string());
     }
 
-    // Extract composer
-    if let Some(composer) = tag.get("TCOM").and_then(|frame| frame.content().text()) {
-        metadata.composer = Some(composer.to_string());
-    }
+    // Extract composers (TCOM, possibly several values)
+    metadata.composers = read_text_values(tag, "TCOM", separator);
 
     // Extract comment (first comment frame)
     if let Some(comment) = tag.comments().next() {
@@ -85,8 +160,9 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
 
     // Extract disc number
     if let Some(disc) = tag.disc() {
-        metadata.disc = Some(disc as u32);
+        metadata.disc = Some(disc);
     }
+    metadata.total_discs = tag.total_discs();
 
     // Extract publisher/record label (TPUB frame)
     if let Some(publisher) = tag.get("TPUB").and_then(|frame| frame.content().text()) {
@@ -118,12 +194,22 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
         metadata.original_album = Some(original_album.to_string());
     }
 
-    // Extract original year (TORY frame)
-    if let Some(original_year) = tag.get("TORY").and_then(|frame| frame.content().text()) {
-        if let Ok(year) = original_year.parse::<u32>() {
-            metadata.original_year = Some(year);
-        }
-    }
+    // Extract original release time (TDOR frame, or the year of TORY in ID3v2.3)
+    let original_year = tag
+        .get("TORY")
+        .and_then(|frame| frame.content().text())
+        .and_then(|year| year.trim().parse().ok())
+        .map(from_year);
+    metadata.original_released = if tag.version() == Version::Id3v24 {
+        read_timestamp(tag, "TDOR").or(original_year)
+    } else {
+        original_year.or_else(|| read_timestamp(tag, "TDOR"))
+    };
+
+    // Extract release, encoding and tagging times (TDRL, TDEN, TDTG frames)
+    metadata.released = read_timestamp(tag, "TDRL");
+    metadata.encoded = read_timestamp(tag, "TDEN");
+    metadata.tagged = read_timestamp(tag, "TDTG");
 
     // Extract BPM (TBPM frame)
     if let Some(bpm) = tag.get("TBPM").and_then(|frame| frame.content().text()) {
@@ -138,9 +224,24 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
     }
 
     // Extract lyrics (USLT frame - Unsynchronized lyrics/text transcription)
-    if let Some(lyrics_frame) = tag.get("USLT") {
-        if let Some(lyrics_text) = lyrics_frame.content().text() {
-            metadata.lyrics = Some(lyrics_text.to_string());
+    if let Some(lyrics) = tag.lyrics().next() {
+        metadata.lyrics = Some(lyrics.text.clone());
+    }
+
+    // Extract synchronized lyrics (SYLT frame - first lyrics frame with millisecond timestamps)
+    if let Some(sylt) = tag.synchronised_lyrics().next() {
+        if sylt.timestamp_format == TimestampFormat::Ms {
+            metadata.synced_lyrics = sylt
+                .content
+                .iter()
+                .map(|(time_ms, text)| SyncedLyricLine {
+                    time_ms: *time_ms,
+                    text: text.clone(),
+                })
+                .collect();
+            metadata.synced_lyrics.sort_by_key(|line| line.time_ms);
+        } else {
+            log::warn!("SYLT frame uses MPEG frame timestamps, which are not supported");
         }
     }
 
@@ -173,11 +274,60 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
         metadata.subtitle = Some(subtitle.to_string());
     }
 
-    // Extract date (TDAT frame - Date)
-    if let Some(date) = tag.get("TDAT").and_then(|frame| frame.content().text()) {
-        metadata.date = Some(date.to_string());
+    // Extract sort order names (TSOT, TSOP, TSOA, and the iTunes frames TSO2 and TSOC)
+    let text = |frame_id: &str| tag.get(frame_id).and_then(|frame| frame.content().text()).map(str::to_string);
+    metadata.title_sort = text("TSOT");
+    metadata.artist_sort = text("TSOP");
+    metadata.album_sort = text("TSOA");
+    metadata.album_artist_sort = text("TSO2");
+    metadata.composer_sort = text("TSOC");
+
+    // Extract URL link frames
+    let link = |frame_id: &str| tag.get(frame_id).and_then(|frame| frame.content().link()).map(str::to_string);
+    metadata.artist_url = link("WOAR");
+    metadata.audio_file_url = link("WOAF");
+    metadata.audio_source_url = link("WOAS");
+    metadata.publisher_url = link("WPUB");
+
+    // Extract chapters (CHAP frames, ordered by the top-level CTOC frame when present)
+    metadata.chapters = extract_chapters(tag);
+
+    // Extract credits (TIPL and TMCL, or IPLS in ID3v2.3)
+    metadata.involved_people = [read_credits(tag, "TIPL"), read_credits(tag, "IPLS")].concat();
+    metadata.musician_credits = read_credits(tag, "TMCL");
+
+    // Extract ratings (POPM frames)
+    metadata.popularimeters = tag
+        .frames()
+        .filter_map(|frame| frame.content().popularimeter())
+        .map(|popm| Popularimeter {
+            email: popm.user.clone(),
+            rating: popm.rating,
+            counter: popm.counter,
+        })
+        .collect();
+
+    // Extract play counter (PCNT frame - big-endian counter of at least 32 bits)
+    if let Some(Ok(pcnt)) = tag.get("PCNT").map(|frame| frame.content().to_unknown()) {
+        if !pcnt.data.is_empty() && pcnt.data.len() <= 8 {
+            metadata.play_count = Some(pcnt.data.iter().fold(0u64, |count, byte| (count << 8) | u64::from(*byte)));
+        }
     }
 
+    // Extract MusicBrainz identifiers (recording ID in UFID, the others in TXXX frames)
+    metadata.musicbrainz_recording_id = tag
+        .unique_file_identifiers()
+        .find(|ufid| ufid.owner_identifier == MUSICBRAINZ_UFID_OWNER)
+        .map(|ufid| String::from_utf8_lossy(&ufid.identifier).trim_end_matches('\0').to_string())
+        .or_else(|| extended_text(tag, MUSICBRAINZ_TRACK_ID));
+    metadata.musicbrainz_release_id = extended_text(tag, MUSICBRAINZ_RELEASE_ID);
+    metadata.musicbrainz_artist_id = extended_text(tag, MUSICBRAINZ_ARTIST_ID);
+    metadata.musicbrainz_release_group_id = extended_text(tag, MUSICBRAINZ_RELEASE_GROUP_ID);
+    metadata.acoustid_fingerprint = extended_text(tag, ACOUSTID_FINGERPRINT);
+
+    // Extract ReplayGain values (TXXX frames, descriptions are matched case-insensitively)
+    metadata.replay_gain = extract_replay_gain(tag);
+
     // Extract encoded by (TENC frame - already extracted as encoder)
     // This is the same as encoder
 
@@ -195,15 +345,858 @@ pub fn parse_id3_impl<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseErr
     for frame in tag.frames() {
         let frame_id = frame.id();
         // Skip frames we've already extracted
-        if !matches!(frame_id, "TIT2" | "TPE1" | "TALB" | "TYER" | "TDRC" | "TCON" | "TRCK" | "TPE2" | "TCOM" | "COMM" | "TPOS" | "TPUB" | "TENC" | "TLAN" | "TCOP" | "TOPE" | "TOAL" | "TORY" | "TBPM" | "TSRC" | "USLT" | "TPE3" | "TPE4" | "TPRO" | "TIT1" | "TIT3" | "TDAT" | "APIC") {
+        if !matches!(frame_id, "TIT2" | "TPE1" | "TALB" | "TYER" | "TDRC" | "TCON" | "TRCK" | "TPE2" | "TCOM" | "COMM" | "TPOS" | "TPUB" | "TENC" | "TLAN" | "TCOP" | "TOPE" | "TOAL" | "TORY" | "TDOR" | "TBPM" | "TSRC" | "USLT" | "TPE3" | "TPE4" | "TPRO" | "TIT1" | "TIT3" | "TSOT" | "TSOP" | "TSOA" | "TSO2" | "TSOC" | "TDAT" | "TIME" | "TDRL" | "TDEN" | "TDTG" | "APIC" | "SYLT" | "CHAP" | "CTOC" | "POPM" | "PCNT" | "RVA2") {
             if let Some(text) = frame.content().text() {
                 metadata.custom_fields.push((frame_id.to_string(), text.to_string()));
             }
         }
     }
 
-    log::debug!("Successfully extracted metadata: title={:?}, artist={:?}, album={:?}, {} custom fields", 
-        metadata.title, metadata.artist, metadata.album, metadata.custom_fields.len());
+    log::debug!("Successfully extracted metadata: title={:?}, artists={:?}, album={:?}, {} custom fields", 
+        metadata.title, metadata.artists, metadata.album, metadata.custom_fields.len());
     
-    Ok(metadata)
-}
\ No newline at end of file
+    metadata
+}
+
+/// Extract the chapter list from CHAP frames
+fn extract_chapters(tag: &Tag) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = tag
+        .chapters()
+        .map(|chapter| {
+            let picture = chapter.frames.iter().find_map(|frame| frame.content().picture());
+            Chapter {
+                element_id: chapter.element_id.clone(),
+                start_ms: chapter.start_time,
+                end_ms: chapter.end_time,
+                title: chapter.title().map(|title| title.to_string()),
+                url: chapter
+                    .frames
+                    .iter()
+                    .find_map(|frame| frame.content().extended_link())
+                    .map(|link| link.link.clone()),
+                image: picture.map(|picture| picture.data.clone()),
+                image_format: picture.map(|picture| picture.mime_type.clone()),
+            }
+        })
+        .collect();
+
+    // Follow the order of the top-level table of contents, falling back to start times
+    let toc_order: Vec<String> = tag
+        .tables_of_contents()
+        .find(|toc| toc.top_level)
+        .map(|toc| toc.elements.clone())
+        .unwrap_or_default();
+    chapters.sort_by_key(|chapter| {
+        let position = toc_order
+            .iter()
+            .position(|id| id == &chapter.element_id)
+            .unwrap_or(usize::MAX);
+        (position, chapter.start_ms)
+    });
+
+    if !chapters.is_empty() {
+        log::debug!("Found {} chapters", chapters.len());
+    }
+    chapters
+}
+
+/// Read the credits of an involved people list frame
+fn read_credits(tag: &Tag, frame_id: &str) -> Vec<Credit> {
+    tag.get(frame_id)
+        .and_then(|frame| frame.content().involved_people_list())
+        .map(|list| {
+            list.items
+                .iter()
+                .map(|item| Credit {
+                    role: item.involvement.clone(),
+                    name: item.involvee.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Write the credits as TIPL + TMCL (ID3v2.4) or a single IPLS frame (ID3v2.3)
+///
+/// Rows with neither a role nor a name are skipped.
+fn write_credits(tag: &mut Tag, involved_people: &[Credit], musician_credits: &[Credit]) {
+    for frame_id in ["TIPL", "TMCL", "IPLS"] {
+        tag.remove(frame_id);
+    }
+    let lists: Vec<(&str, Vec<&Credit>)> = if tag.version() == Version::Id3v24 {
+        vec![
+            ("TIPL", involved_people.iter().collect()),
+            ("TMCL", musician_credits.iter().collect()),
+        ]
+    } else {
+        vec![("IPLS", involved_people.iter().chain(musician_credits).collect())]
+    };
+    for (frame_id, credits) in lists {
+        let items: Vec<InvolvedPeopleListItem> = credits
+            .into_iter()
+            .filter(|credit| !credit.role.trim().is_empty() || !credit.name.trim().is_empty())
+            .map(|credit| InvolvedPeopleListItem {
+                involvement: credit.role.trim().to_string(),
+                involvee: credit.name.trim().to_string(),
+            })
+            .collect();
+        if !items.is_empty() {
+            tag.add_frame(Frame::with_content(
+                frame_id,
+                id3::Content::InvolvedPeopleList(InvolvedPeopleList { items }),
+            ));
+        }
+    }
+}
+
+/// UFID owner and TXXX descriptions used by MusicBrainz Picard
+const MUSICBRAINZ_UFID_OWNER: &str = "http://musicbrainz.org";
+const MUSICBRAINZ_TRACK_ID: &str = "MusicBrainz Track Id";
+const MUSICBRAINZ_RELEASE_ID: &str = "MusicBrainz Album Id";
+const MUSICBRAINZ_ARTIST_ID: &str = "MusicBrainz Artist Id";
+const MUSICBRAINZ_RELEASE_GROUP_ID: &str = "MusicBrainz Release Group Id";
+const ACOUSTID_FINGERPRINT: &str = "Acoustid Fingerprint";
+
+/// Value of the first TXXX frame with the given description (case-insensitive)
+fn extended_text(tag: &Tag, description: &str) -> Option<String> {
+    tag.extended_texts()
+        .find(|text| text.description.eq_ignore_ascii_case(description))
+        .map(|text| text.value.clone())
+}
+
+/// TXXX descriptions used for ReplayGain values
+const REPLAYGAIN_TRACK_GAIN: &str = "REPLAYGAIN_TRACK_GAIN";
+const REPLAYGAIN_TRACK_PEAK: &str = "REPLAYGAIN_TRACK_PEAK";
+const REPLAYGAIN_ALBUM_GAIN: &str = "REPLAYGAIN_ALBUM_GAIN";
+const REPLAYGAIN_ALBUM_PEAK: &str = "REPLAYGAIN_ALBUM_PEAK";
+
+/// Extract ReplayGain values from TXXX frames
+fn extract_replay_gain(tag: &Tag) -> ReplayGain {
+    let value_for = |description: &str| {
+        extended_text(tag, description).and_then(|value| parse_replay_gain_value(&value))
+    };
+
+    ReplayGain {
+        track_gain: value_for(REPLAYGAIN_TRACK_GAIN),
+        track_peak: value_for(REPLAYGAIN_TRACK_PEAK),
+        album_gain: value_for(REPLAYGAIN_ALBUM_GAIN),
+        album_peak: value_for(REPLAYGAIN_ALBUM_PEAK),
+        rva2: tag.get("RVA2").is_some(),
+    }
+}
+
+/// Parse a ReplayGain value such as "-6.54 dB" or "0.988553"
+fn parse_replay_gain_value(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let number = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("db"))
+        .or_else(|| value.strip_suffix("DB"))
+        .unwrap_or(value);
+    number.trim().parse().ok()
+}
+
+/// Internal implementation of ID3 writing
+///
+/// Only the frames whose values differ from what is currently stored in the file are touched,
+/// so frames the application doesn't model are preserved as-is.
+pub fn write_id3_impl<P: AsRef<Path>>(path: P, metadata: &AudioMetadata, separator: ListSeparator) -> Result<(), ParseError> {
+    let path_ref = path.as_ref();
+
+    log::debug!("Writing ID3 tags to: {:?}", path_ref);
+
+    if !path_ref.exists() {
+        log::error!("File not found: {:?}", path_ref);
+        return Err(ParseError::FileNotFound);
+    }
+
+    // Only containers that can legitimately carry an ID3v2 tag are written to
+    if !supports_id3_impl(path_ref) {
+        log::error!("Refusing to write ID3 tag to unsupported container: {:?}", path_ref);
+        return Err(ParseError::InvalidFormat);
+    }
+
+    let mut tag = match id3::Tag::read_from_path(path_ref) {
+        Ok(tag) => tag,
+        Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => {
+            log::debug!("No existing ID3 tag, creating a new ID3v2.4 tag");
+            Tag::with_version(Version::Id3v24)
+        }
+        Err(e) => {
+            log::error!("Error reading ID3 tag from {:?}: {}", path_ref, e);
+            return Err(ParseError::IoError(e.to_string()));
+        }
+    };
+
+    let original = extract_metadata(&tag, separator);
+    apply_metadata(&mut tag, &original, metadata, separator);
+
+    let version = tag.version();
+    tag.write_to_path(path_ref, version).map_err(|e| {
+        log::error!("Error writing ID3 tag to {:?}: {}", path_ref, e);
+        ParseError::IoError(e.to_string())
+    })?;
+
+    log::info!("ID3 tag written successfully to {:?}", path_ref);
+    Ok(())
+}
+
+/// Read a timestamp frame (TDRC, TDOR, TDRL, TDEN, TDTG)
+fn read_timestamp(tag: &Tag, frame_id: &str) -> Option<Timestamp> {
+    let text = tag.get(frame_id)?.content().text()?;
+    parse_timestamp(text).or_else(|| text.trim().parse().ok())
+}
+
+/// Combine the ID3v2.3 recording time frames: TYER (yyyy), TDAT (DDMM) and TIME (HHMM)
+fn read_legacy_recording_time(tag: &Tag) -> Option<Timestamp> {
+    let year = tag.year()?;
+    let pair = |frame_id: &str| -> Option<(u32, u32)> {
+        let value = tag.get(frame_id)?.content().text()?.trim();
+        if value.len() != 4 || !value.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some((value[..2].parse().ok()?, value[2..].parse().ok()?))
+    };
+    let timestamp = from_year(year);
+    let Some((day, month)) = pair("TDAT") else {
+        return Some(timestamp);
+    };
+    let Some(date) = timestamp.with_part(1, month).and_then(|timestamp| timestamp.with_part(2, day)) else {
+        return Some(timestamp);
+    };
+    Some(
+        pair("TIME")
+            .and_then(|(hour, minute)| date.with_part(3, hour)?.with_part(4, minute))
+            .unwrap_or(date),
+    )
+}
+
+/// Write a timestamp frame, or remove it when there is no timestamp
+fn write_timestamp(tag: &mut Tag, frame_id: &str, timestamp: Option<Timestamp>) {
+    match timestamp {
+        Some(timestamp) => tag.set_text(frame_id, timestamp.to_string()),
+        None => {
+            tag.remove(frame_id);
+        }
+    }
+}
+
+/// Write the recording time as TDRC (ID3v2.4) or TYER + TDAT + TIME (ID3v2.3)
+///
+/// ID3v2.3 times have minute precision, so seconds are not written there.
+fn write_recording_time(tag: &mut Tag, timestamp: Option<Timestamp>) {
+    for frame_id in ["TYER", "TDAT", "TIME", "TDRC"] {
+        tag.remove(frame_id);
+    }
+    if tag.version() == Version::Id3v24 {
+        write_timestamp(tag, "TDRC", timestamp);
+        return;
+    }
+    let Some(timestamp) = timestamp else {
+        return;
+    };
+    tag.set_text("TYER", format!("{:04}", timestamp.year));
+    if let (Some(month), Some(day)) = (timestamp.month, timestamp.day) {
+        tag.set_text("TDAT", format!("{:02}{:02}", day, month));
+        if let (Some(hour), Some(minute)) = (timestamp.hour, timestamp.minute) {
+            tag.set_text("TIME", format!("{:02}{:02}", hour, minute));
+        }
+    }
+}
+
+/// Apply the fields that changed between `original` and `metadata` to the tag
+fn apply_metadata(tag: &mut Tag, original: &AudioMetadata, metadata: &AudioMetadata, separator: ListSeparator) {
+    // Plain text frames
+    update_text_frame(tag, "TIT2", &original.title, &metadata.title);
+    update_text_frame(tag, "TALB", &original.album, &metadata.album);
+    update_text_frame(tag, "TPE2", &original.album_artist, &metadata.album_artist);
+    update_text_frame(tag, "TPUB", &original.publisher, &metadata.publisher);
+    update_text_frame(tag, "TENC", &original.encoder, &metadata.encoder);
+    update_text_frame(tag, "TLAN", &original.language, &metadata.language);
+    update_text_frame(tag, "TCOP", &original.copyright, &metadata.copyright);
+    update_text_frame(tag, "TOPE", &original.original_artist, &metadata.original_artist);
+    update_text_frame(tag, "TOAL", &original.original_album, &metadata.original_album);
+    update_text_frame(tag, "TSRC", &original.isrc, &metadata.isrc);
+    update_text_frame(tag, "TPE3", &original.conductor, &metadata.conductor);
+    update_text_frame(tag, "TPE4", &original.remixer, &metadata.remixer);
+    update_text_frame(tag, "TPRO", &original.producer, &metadata.producer);
+    update_text_frame(tag, "TIT1", &original.grouping, &metadata.grouping);
+    update_text_frame(tag, "TIT3", &original.subtitle, &metadata.subtitle);
+    update_text_frame(tag, "TSOT", &original.title_sort, &metadata.title_sort);
+    update_text_frame(tag, "TSOP", &original.artist_sort, &metadata.artist_sort);
+    update_text_frame(tag, "TSOA", &original.album_sort, &metadata.album_sort);
+    update_text_frame(tag, "TSO2", &original.album_artist_sort, &metadata.album_artist_sort);
+    update_text_frame(tag, "TSOC", &original.composer_sort, &metadata.composer_sort);
+    update_link_frame(tag, "WOAR", &original.artist_url, &metadata.artist_url);
+    update_link_frame(tag, "WOAF", &original.audio_file_url, &metadata.audio_file_url);
+    update_link_frame(tag, "WOAS", &original.audio_source_url, &metadata.audio_source_url);
+    update_link_frame(tag, "WPUB", &original.publisher_url, &metadata.publisher_url);
+
+    // Frames with several values
+    update_text_values(tag, "TPE1", &original.artists, &metadata.artists, separator);
+    update_text_values(tag, "TCOM", &original.composers, &metadata.composers, separator);
+    update_text_values(tag, "TCON", &original.genres, &metadata.genres, separator);
+
+    // Timestamps (TDRL, TDEN and TDTG have no ID3v2.3 counterpart and are only written to ID3v2.4)
+    if original.recorded != metadata.recorded {
+        write_recording_time(tag, metadata.recorded);
+    }
+    if original.original_released != metadata.original_released {
+        tag.remove("TORY");
+        tag.remove("TDOR");
+        if tag.version() == Version::Id3v24 {
+            write_timestamp(tag, "TDOR", metadata.original_released);
+        } else if let Some(original_released) = metadata.original_released {
+            tag.set_text("TORY", format!("{:04}", original_released.year));
+        }
+    }
+    for (frame_id, before, after) in [
+        ("TDRL", original.released, metadata.released),
+        ("TDEN", original.encoded, metadata.encoded),
+        ("TDTG", original.tagged, metadata.tagged),
+    ] {
+        if before == after {
+            continue;
+        }
+        if tag.version() == Version::Id3v24 {
+            write_timestamp(tag, frame_id, after);
+        } else {
+            log::warn!("Not writing {} to an {} tag", frame_id, tag.version());
+        }
+    }
+
+    // Numeric text frames
+    update_text_frame(
+        tag,
+        "TBPM",
+        &original.bpm.map(|bpm| bpm.to_string()),
+        &metadata.bpm.map(|bpm| bpm.to_string()),
+    );
+
+    if (original.track, original.total_tracks) != (metadata.track, metadata.total_tracks) {
+        update_number_pair(tag, "TRCK", metadata.track, metadata.total_tracks);
+    }
+
+    if (original.disc, original.total_discs) != (metadata.disc, metadata.total_discs) {
+        update_number_pair(tag, "TPOS", metadata.disc, metadata.total_discs);
+    }
+
+    // Comment (replaces the first COMM frame, keeping its language and description)
+    if original.comment != metadata.comment {
+        let existing = tag.comments().next().cloned();
+        if let Some(ref existing) = existing {
+            tag.remove_comment(Some(&existing.description), Some(&existing.text));
+        }
+        if let Some(text) = metadata.comment.as_ref().filter(|text| !text.is_empty()) {
+            let (lang, description) = existing
+                .map(|comment| (comment.lang, comment.description))
+                .unwrap_or_else(|| ("eng".to_string(), String::new()));
+            tag.add_frame(Comment {
+                lang,
+                description,
+                text: text.clone(),
+            });
+        }
+    }
+
+    // Unsynchronized lyrics (USLT)
+    if original.lyrics != metadata.lyrics {
+        // Only the frame that was read is replaced; lyrics in other languages or with
+        // other descriptions stay
+        let existing = tag.lyrics().next().cloned();
+        if let Some(ref existing) = existing {
+            if let Some(index) = tag.frames_vec().iter().position(|frame| frame.content().lyrics() == Some(existing)) {
+                tag.frames_vec_mut().remove(index);
+            }
+        }
+        if let Some(text) = metadata.lyrics.as_ref().filter(|text| !text.is_empty()) {
+            let (lang, description) = existing
+                .map(|lyrics| (lyrics.lang, lyrics.description))
+                .unwrap_or_else(|| ("eng".to_string(), String::new()));
+            tag.add_frame(Lyrics {
+                lang,
+                description,
+                text: text.clone(),
+            });
+        }
+    }
+
+    // Synchronized lyrics (SYLT)
+    if original.synced_lyrics != metadata.synced_lyrics {
+        let (lang, description) = tag
+            .synchronised_lyrics()
+            .next()
+            .map(|sylt| (sylt.lang.clone(), sylt.description.clone()))
+            .unwrap_or_else(|| ("eng".to_string(), String::new()));
+        tag.remove_all_synchronised_lyrics();
+        if !metadata.synced_lyrics.is_empty() {
+            tag.add_frame(SynchronisedLyrics {
+                lang,
+                timestamp_format: TimestampFormat::Ms,
+                content_type: SynchronisedLyricsType::Lyrics,
+                description,
+                content: metadata
+                    .synced_lyrics
+                    .iter()
+                    .map(|line| (line.time_ms, line.text.clone()))
+                    .collect(),
+            });
+        }
+    }
+
+    // Cover art (APIC): the picture that was read and any other front cover are replaced
+    if (&original.cover_art, &original.cover_art_format) != (&metadata.cover_art, &metadata.cover_art_format) {
+        write_cover_art(tag, metadata.cover_art.as_ref(), metadata.cover_art_format.as_deref());
+    }
+
+    // Involved people and musician credits (TIPL + TMCL, or IPLS in ID3v2.3)
+    if (&original.involved_people, &original.musician_credits) != (&metadata.involved_people, &metadata.musician_credits) {
+        write_credits(tag, &metadata.involved_people, &metadata.musician_credits);
+    }
+
+    // Chapters (CHAP frames plus a top-level CTOC listing them in order)
+    if original.chapters != metadata.chapters {
+        write_chapters(tag, &metadata.chapters);
+    }
+
+    // Ratings (POPM frames)
+    if original.popularimeters != metadata.popularimeters {
+        tag.remove("POPM");
+        for popm in &metadata.popularimeters {
+            tag.add_frame(id3::frame::Popularimeter {
+                user: popm.email.clone(),
+                rating: popm.rating,
+                counter: popm.counter,
+            });
+        }
+    }
+
+    // MusicBrainz identifiers
+    if original.musicbrainz_recording_id != metadata.musicbrainz_recording_id {
+        tag.remove_unique_file_identifier_by_owner_identifier(MUSICBRAINZ_UFID_OWNER);
+        update_extended_text(tag, MUSICBRAINZ_TRACK_ID, &None);
+        if let Some(id) = metadata.musicbrainz_recording_id.as_ref().filter(|id| !id.is_empty()) {
+            tag.add_frame(UniqueFileIdentifier {
+                owner_identifier: MUSICBRAINZ_UFID_OWNER.to_string(),
+                identifier: id.as_bytes().to_vec(),
+            });
+        }
+    }
+    if original.musicbrainz_release_id != metadata.musicbrainz_release_id {
+        update_extended_text(tag, MUSICBRAINZ_RELEASE_ID, &metadata.musicbrainz_release_id);
+    }
+    if original.musicbrainz_artist_id != metadata.musicbrainz_artist_id {
+        update_extended_text(tag, MUSICBRAINZ_ARTIST_ID, &metadata.musicbrainz_artist_id);
+    }
+    if original.musicbrainz_release_group_id != metadata.musicbrainz_release_group_id {
+        update_extended_text(tag, MUSICBRAINZ_RELEASE_GROUP_ID, &metadata.musicbrainz_release_group_id);
+    }
+    if original.acoustid_fingerprint != metadata.acoustid_fingerprint {
+        update_extended_text(tag, ACOUSTID_FINGERPRINT, &metadata.acoustid_fingerprint);
+    }
+
+    // ReplayGain (TXXX frames, plus RVA2 frames when requested)
+    if original.replay_gain != metadata.replay_gain {
+        let replay_gain = &metadata.replay_gain;
+        let existing: Vec<String> = tag
+            .extended_texts()
+            .filter(|text| text.description.to_uppercase().starts_with("REPLAYGAIN_"))
+            .map(|text| text.description.clone())
+            .collect();
+        for description in existing {
+            tag.remove_extended_text(Some(&description), None);
+        }
+        let values = [
+            (REPLAYGAIN_TRACK_GAIN, replay_gain.track_gain.map(|gain| format!("{:+.2} dB", gain))),
+            (REPLAYGAIN_TRACK_PEAK, replay_gain.track_peak.map(|peak| format!("{:.6}", peak))),
+            (REPLAYGAIN_ALBUM_GAIN, replay_gain.album_gain.map(|gain| format!("{:+.2} dB", gain))),
+            (REPLAYGAIN_ALBUM_PEAK, replay_gain.album_peak.map(|peak| format!("{:.6}", peak))),
+        ];
+        for (description, value) in values {
+            if let Some(value) = value {
+                tag.add_frame(ExtendedText {
+                    description: description.to_string(),
+                    value,
+                });
+            }
+        }
+
+        // RVA2 is an ID3v2.4 frame, so ID3v2.3 tags only get the TXXX values
+        tag.remove("RVA2");
+        if replay_gain.rva2 && tag.version() != Version::Id3v24 {
+            log::warn!("Not writing RVA2 to an {} tag", tag.version());
+        } else if replay_gain.rva2 {
+            let version = tag.version();
+            for (identification, gain, peak) in [
+                ("track", replay_gain.track_gain, replay_gain.track_peak),
+                ("album", replay_gain.album_gain, replay_gain.album_peak),
+            ] {
+                if let Some(gain) = gain {
+                    tag.add_frame(Frame::with_content(
+                        "RVA2",
+                        id3::Content::Unknown(Unknown {
+                            data: build_rva2_data(identification, gain, peak),
+                            version,
+                        }),
+                    ));
+                }
+            }
+        }
+    }
+
+    // Custom text frames
+    if original.custom_fields != metadata.custom_fields {
+        for (frame_id, _) in &original.custom_fields {
+            if !metadata.custom_fields.iter().any(|(id, _)| id == frame_id) {
+                tag.remove(frame_id);
+            }
+        }
+        for (frame_id, value) in &metadata.custom_fields {
+            tag.set_text(frame_id, value.clone());
+        }
+    }
+}
+
+/// Replace the cover art with a front cover picture, or remove it when there is no image
+///
+/// The first picture is the one read as the cover art, whatever its type, so it is removed
+/// along with the front covers, and the new picture takes its place.
+fn write_cover_art(tag: &mut Tag, data: Option<&Vec<u8>>, format: Option<&str>) {
+    let first_picture = tag.frames_vec().iter().position(|frame| frame.content().picture().is_some());
+    let mut index = 0;
+    tag.frames_vec_mut().retain(|frame| {
+        let replaced = Some(index) == first_picture
+            || frame
+                .content()
+                .picture()
+                .is_some_and(|picture| picture.picture_type == PictureType::CoverFront);
+        index += 1;
+        !replaced
+    });
+
+    if let Some(data) = data.filter(|data| !data.is_empty()) {
+        let picture = Frame::from(Picture {
+            mime_type: format.unwrap_or("image/jpeg").to_string(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: data.clone(),
+        });
+        // Only pictures were removed, so the position of the first one is still valid
+        let position = first_picture.unwrap_or(tag.frames_vec().len());
+        tag.frames_vec_mut().insert(position, picture);
+    }
+}
+
+/// Replace the CHAP frames and the top-level CTOC with the given chapters
+///
+/// Parts of the chapter frames that aren't edited are kept: sub-frames of existing chapters,
+/// nested tables of contents and top-level CTOC entries that aren't chapters.
+fn write_chapters(tag: &mut Tag, chapters: &[Chapter]) {
+    let existing_chapters: Vec<id3::frame::Chapter> = tag.chapters().cloned().collect();
+    let existing_tocs: Vec<TableOfContents> = tag.tables_of_contents().cloned().collect();
+    tag.remove_all_chapters();
+    tag.remove_all_tables_of_contents();
+
+    for chapter in chapters {
+        let existing = existing_chapters.iter().find(|frame| frame.element_id == chapter.element_id);
+        tag.add_frame(build_chapter_frame(chapter, existing));
+    }
+
+    // Entries of removed chapters are dropped from every table of contents
+    let removed: Vec<&str> = existing_chapters
+        .iter()
+        .map(|frame| frame.element_id.as_str())
+        .filter(|id| !chapters.iter().any(|chapter| chapter.element_id == *id))
+        .collect();
+    let mut top_level = None;
+    for mut toc in existing_tocs {
+        toc.elements.retain(|id| !removed.contains(&id.as_str()));
+        if toc.top_level && top_level.is_none() {
+            top_level = Some(toc);
+        } else {
+            tag.add_frame(toc);
+        }
+    }
+
+    let mut toc = top_level.unwrap_or_else(|| TableOfContents {
+        element_id: "toc".to_string(),
+        top_level: true,
+        ordered: true,
+        elements: Vec::new(),
+        frames: Vec::new(),
+    });
+    // The chapters come first, in their edited order, followed by any other entries
+    let others: Vec<String> = toc
+        .elements
+        .iter()
+        .filter(|id| !chapters.iter().any(|chapter| &chapter.element_id == *id))
+        .cloned()
+        .collect();
+    toc.elements = chapters.iter().map(|chapter| chapter.element_id.clone()).chain(others).collect();
+    if !toc.elements.is_empty() {
+        tag.add_frame(toc);
+    }
+}
+
+/// Build a CHAP frame with its TIT2, WXXX and APIC sub-frames
+///
+/// Sub-frames of the existing frame that aren't edited (other than its title, first link and
+/// first picture) are kept.
+fn build_chapter_frame(chapter: &Chapter, existing: Option<&id3::frame::Chapter>) -> id3::frame::Chapter {
+    let mut kept: Vec<Frame> = existing.map(|frame| frame.frames.clone()).unwrap_or_default();
+    kept.retain(|frame| frame.id() != "TIT2");
+    if let Some(position) = kept.iter().position(|frame| frame.content().extended_link().is_some()) {
+        kept.remove(position);
+    }
+    if let Some(position) = kept.iter().position(|frame| frame.content().picture().is_some()) {
+        kept.remove(position);
+    }
+
+    let mut frames = Vec::new();
+    if let Some(title) = chapter.title.as_ref().filter(|title| !title.is_empty()) {
+        frames.push(Frame::text("TIT2", title.clone()));
+    }
+    if let Some(url) = chapter.url.as_ref().filter(|url| !url.is_empty()) {
+        frames.push(Frame::from(ExtendedLink {
+            description: String::new(),
+            link: url.clone(),
+        }));
+    }
+    if let Some(ref image) = chapter.image {
+        frames.push(Frame::from(Picture {
+            mime_type: chapter.image_format.clone().unwrap_or_else(|| "image/jpeg".to_string()),
+            picture_type: PictureType::Other,
+            description: String::new(),
+            data: image.clone(),
+        }));
+    }
+    frames.extend(kept);
+
+    id3::frame::Chapter {
+        element_id: chapter.element_id.clone(),
+        start_time: chapter.start_ms,
+        end_time: chapter.end_ms,
+        // Byte offsets are unused, times are authoritative
+        start_offset: 0xFFFF_FFFF,
+        end_offset: 0xFFFF_FFFF,
+        frames,
+    }
+}
+
+/// Write a TRCK/TPOS style `n/total` frame; without a number or total the frame is removed
+///
+/// A total can't be written without a number, so the frame is left as it is then
+/// (validation reports it).
+fn update_number_pair(tag: &mut Tag, frame_id: &str, number: Option<u32>, total: Option<u32>) {
+    match (number, total) {
+        (Some(number), Some(total)) => tag.set_text(frame_id, format!("{}/{}", number, total)),
+        (Some(number), None) => tag.set_text(frame_id, number.to_string()),
+        (None, Some(total)) => {
+            log::warn!("Not writing {}: total {} without a number", frame_id, total);
+        }
+        (None, None) => {
+            tag.remove(frame_id);
+        }
+    }
+}
+
+/// Replace or remove the TXXX frame with the given description (matched case-insensitively)
+fn update_extended_text(tag: &mut Tag, description: &str, value: &Option<String>) {
+    let existing: Vec<String> = tag
+        .extended_texts()
+        .filter(|text| text.description.eq_ignore_ascii_case(description))
+        .map(|text| text.description.clone())
+        .collect();
+    for existing_description in existing {
+        tag.remove_extended_text(Some(&existing_description), None);
+    }
+    if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+        tag.add_frame(ExtendedText {
+            description: description.to_string(),
+            value: value.clone(),
+        });
+    }
+}
+
+/// Build the body of an RVA2 frame adjusting the master volume channel
+fn build_rva2_data(identification: &str, gain: f64, peak: Option<f64>) -> Vec<u8> {
+    let mut data = identification.as_bytes().to_vec();
+    data.push(0);
+    data.push(0x01); // Channel type: master volume
+    // Volume adjustment is a signed 16-bit value in 1/512 dB
+    let adjustment = (gain * 512.0).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+    data.extend_from_slice(&adjustment.to_be_bytes());
+    match peak {
+        Some(peak) => {
+            data.push(16); // Bits representing peak
+            let peak = (peak * 32768.0).round().clamp(0.0, u16::MAX as f64) as u16;
+            data.extend_from_slice(&peak.to_be_bytes());
+        }
+        None => data.push(0),
+    }
+    data
+}
+
+/// Read the values of a text frame
+///
+/// ID3v2.4 separates values with a null character; ID3v2.3 values are split at the
+/// configured separator.
+fn read_text_values(tag: &Tag, frame_id: &str, separator: ListSeparator) -> Vec<String> {
+    let Some(values) = tag.get(frame_id).and_then(|frame| frame.content().text_values()) else {
+        return Vec::new();
+    };
+    if tag.version() == Version::Id3v24 {
+        return values
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    // The id3 crate already splits ID3v2.3 TPE1/TCOM at "/", so the text is joined back
+    // before splitting at the configured separator ("AC/DC; Queen" is two artists)
+    let text = values.collect::<Vec<_>>().join("/");
+    separator
+        .split(&text)
+        .into_iter()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Set, replace or remove a text frame with several values when they changed
+///
+/// ID3v2.4 separates the values with a null character, ID3v2.3 with the configured separator.
+fn update_text_values(tag: &mut Tag, frame_id: &str, original: &[String], values: &[String], separator: ListSeparator) {
+    if original == values {
+        return;
+    }
+    tag.remove(frame_id);
+    if values.is_empty() {
+        return;
+    }
+    if tag.version() == Version::Id3v24 {
+        tag.set_text_values(frame_id, values.to_vec());
+    } else {
+        tag.set_text(frame_id, values.join(separator.as_str()));
+    }
+}
+
+/// Set, replace or remove a text frame when its value changed
+fn update_text_frame(tag: &mut Tag, frame_id: &str, original: &Option<String>, value: &Option<String>) {
+    if original == value {
+        return;
+    }
+    match value.as_deref() {
+        Some(text) if !text.is_empty() => tag.set_text(frame_id, text),
+        _ => {
+            tag.remove(frame_id);
+        }
+    }
+}
+
+/// Update a URL link frame (W***) only if its value changed; an empty value removes it
+fn update_link_frame(tag: &mut Tag, frame_id: &str, original: &Option<String>, value: &Option<String>) {
+    if original == value {
+        return;
+    }
+    tag.remove(frame_id);
+    if let Some(url) = value.as_deref().filter(|url| !url.is_empty()) {
+        tag.add_frame(Frame::link(frame_id, url));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(element_id: &str, start_ms: u32, end_ms: u32, title: &str) -> Chapter {
+        Chapter {
+            element_id: element_id.to_string(),
+            start_ms,
+            end_ms,
+            title: Some(title.to_string()),
+            url: None,
+            image: None,
+            image_format: None,
+        }
+    }
+
+    fn tag_with_chapters() -> Tag {
+        let mut tag = Tag::with_version(Version::Id3v24);
+        let mut first = build_chapter_frame(&chapter("chp0", 0, 1000, "One"), None);
+        first.frames.push(Frame::text("TIT3", "Kept subtitle"));
+        tag.add_frame(first);
+        tag.add_frame(build_chapter_frame(&chapter("chp1", 1000, 2000, "Two"), None));
+        tag.add_frame(TableOfContents {
+            element_id: "toc".to_string(),
+            top_level: true,
+            ordered: true,
+            elements: vec!["chp0".to_string(), "part1".to_string(), "chp1".to_string()],
+            frames: vec![Frame::text("TIT2", "Contents")],
+        });
+        tag.add_frame(TableOfContents {
+            element_id: "part1".to_string(),
+            top_level: false,
+            ordered: true,
+            elements: vec!["chp1".to_string()],
+            frames: Vec::new(),
+        });
+        tag
+    }
+
+    #[test]
+    fn write_chapters_keeps_unedited_sub_frames() {
+        let mut tag = tag_with_chapters();
+        write_chapters(&mut tag, &[chapter("chp0", 0, 1500, "Renamed"), chapter("chp1", 1500, 2000, "Two")]);
+
+        let first = tag.chapters().find(|frame| frame.element_id == "chp0").unwrap();
+        assert_eq!(first.end_time, 1500);
+        assert_eq!(first.title(), Some("Renamed"));
+        assert!(first.frames.iter().any(|frame| frame.id() == "TIT3"));
+        assert_eq!(first.frames.iter().filter(|frame| frame.id() == "TIT2").count(), 1);
+    }
+
+    #[test]
+    fn write_chapters_keeps_nested_tables_of_contents() {
+        let mut tag = tag_with_chapters();
+        write_chapters(&mut tag, &[chapter("chp1", 1000, 2000, "Two"), chapter("chp0", 0, 1000, "One")]);
+
+        let top_level = tag.tables_of_contents().find(|toc| toc.top_level).unwrap();
+        assert_eq!(top_level.elements, vec!["chp1", "chp0", "part1"]);
+        assert_eq!(top_level.frames.len(), 1);
+        let nested = tag.tables_of_contents().find(|toc| toc.element_id == "part1").unwrap();
+        assert_eq!(nested.elements, vec!["chp1"]);
+    }
+
+    #[test]
+    fn write_chapters_drops_removed_chapters_everywhere() {
+        let mut tag = tag_with_chapters();
+        write_chapters(&mut tag, &[chapter("chp0", 0, 1000, "One")]);
+
+        assert_eq!(tag.chapters().count(), 1);
+        let top_level = tag.tables_of_contents().find(|toc| toc.top_level).unwrap();
+        assert_eq!(top_level.elements, vec!["chp0", "part1"]);
+        let nested = tag.tables_of_contents().find(|toc| toc.element_id == "part1").unwrap();
+        assert!(nested.elements.is_empty());
+    }
+
+    #[test]
+    fn write_chapters_without_chapters_removes_an_empty_table_of_contents() {
+        let mut tag = Tag::with_version(Version::Id3v24);
+        tag.add_frame(build_chapter_frame(&chapter("chp0", 0, 1000, "One"), None));
+        write_chapters(&mut tag, &[]);
+
+        assert_eq!(tag.chapters().count(), 0);
+        assert_eq!(tag.tables_of_contents().count(), 0);
+    }
+
+    #[test]
+    fn extract_metadata_lists_latin1_fields() {
+        let mut tag = Tag::with_version(Version::Id3v23);
+        tag.add_frame(Frame::text("TIT2", "Title").set_encoding(Some(id3::Encoding::Latin1)));
+        tag.add_frame(Frame::text("TALB", "Album").set_encoding(Some(id3::Encoding::UTF16)));
+        let metadata = extract_metadata(&tag, ListSeparator::default());
+        assert_eq!(metadata.latin1_fields, vec![MetadataField::Title]);
+    }
+}
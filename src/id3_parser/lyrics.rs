@@ -0,0 +1,179 @@
+use super::fields::MetadataField;
+use super::{AudioMetadata, SyncedLyricLine};
+
+/// Parse the contents of an `.lrc` file into time-coded lines
+///
+/// Lines may carry several timestamps (`[00:12.00][00:45.10]Chorus`), and the `[offset:]`
+/// header is applied to every timestamp. Other ID tags (`[ar:]`, `[ti:]`, ...) are ignored.
+pub fn parse_lrc(content: &str) -> Vec<SyncedLyricLine> {
+    let mut lines = Vec::new();
+    let mut offset_ms: i64 = 0;
+
+    for raw_line in content.lines() {
+        let mut rest = raw_line.trim();
+        let mut timestamps = Vec::new();
+
+        // Collect every leading [..] tag on the line
+        while let Some(stripped) = rest.strip_prefix('[') {
+            let Some(end) = stripped.find(']') else {
+                break;
+            };
+            let tag = &stripped[..end];
+            if let Some(time_ms) = parse_lrc_timestamp(tag) {
+                timestamps.push(time_ms);
+            } else if let Some(offset) = tag.strip_prefix("offset:") {
+                offset_ms = offset.trim().parse().unwrap_or(0);
+            }
+            rest = &stripped[end + 1..];
+        }
+
+        let text = rest.trim().to_string();
+        for time_ms in timestamps {
+            lines.push(SyncedLyricLine {
+                time_ms,
+                text: text.clone(),
+            });
+        }
+    }
+
+    // A positive offset shifts the lyrics earlier
+    if offset_ms != 0 {
+        for line in &mut lines {
+            line.time_ms = (i64::from(line.time_ms) - offset_ms).clamp(0, i64::from(u32::MAX)) as u32;
+        }
+    }
+
+    lines.sort_by_key(|line| line.time_ms);
+    log::debug!("Parsed {} LRC lines", lines.len());
+    lines
+}
+
+/// Format the synchronized lyrics of a track as an `.lrc` file, with ID tags from its metadata
+pub fn format_lrc(metadata: &AudioMetadata) -> String {
+    let mut output = String::new();
+
+    let artist = MetadataField::Artist.value(metadata);
+    for (tag, value) in [("ti", &metadata.title), ("ar", &artist), ("al", &metadata.album)] {
+        if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+            output.push_str(&format!("[{}:{}]\n", tag, value));
+        }
+    }
+
+    output.push_str(&format_lrc_lines(&metadata.synced_lyrics));
+    output
+}
+
+/// Format time-coded lines as LRC body lines (no ID tags)
+pub fn format_lrc_lines(lines: &[SyncedLyricLine]) -> String {
+    lines
+        .iter()
+        .map(|line| format!("[{}]{}\n", format_timestamp(line.time_ms), line.text))
+        .collect()
+}
+
+/// Format milliseconds as an LRC timestamp (`mm:ss.xx`)
+pub fn format_timestamp(time_ms: u32) -> String {
+    let minutes = time_ms / 60_000;
+    let seconds = (time_ms % 60_000) / 1000;
+    let hundredths = (time_ms % 1000) / 10;
+    format!("{:02}:{:02}.{:02}", minutes, seconds, hundredths)
+}
+
+/// Parse an LRC timestamp (`mm:ss`, `mm:ss.xx`, `mm:ss.xxx` or `mm:ss:xx`) into milliseconds
+fn parse_lrc_timestamp(tag: &str) -> Option<u32> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let minutes: u32 = minutes.trim().parse().ok()?;
+
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, Some(fraction)),
+        None => (rest, None),
+    };
+    let seconds: u32 = seconds.trim().parse().ok()?;
+    if seconds >= 60 {
+        return None;
+    }
+
+    let fraction_ms = match fraction {
+        Some(fraction) if !fraction.is_empty() && fraction.chars().all(|c| c.is_ascii_digit()) => {
+            // Scale the fraction to milliseconds based on its number of digits
+            let digits: String = fraction.chars().take(3).collect();
+            let value: u32 = digits.parse().ok()?;
+            value * 10u32.pow(3 - digits.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    // Timestamps too large for u32 milliseconds are rejected instead of wrapping
+    minutes.checked_mul(60_000)?.checked_add(seconds * 1000 + fraction_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(time_ms: u32, text: &str) -> SyncedLyricLine {
+        SyncedLyricLine {
+            time_ms,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_timestamp_formats() {
+        assert_eq!(parse_lrc_timestamp("01:02"), Some(62_000));
+        assert_eq!(parse_lrc_timestamp("01:02.5"), Some(62_500));
+        assert_eq!(parse_lrc_timestamp("01:02.34"), Some(62_340));
+        assert_eq!(parse_lrc_timestamp("01:02.345"), Some(62_345));
+        assert_eq!(parse_lrc_timestamp("01:02:34"), Some(62_340));
+    }
+
+    #[test]
+    fn rejects_invalid_timestamps() {
+        assert_eq!(parse_lrc_timestamp("ar:Artist"), None);
+        assert_eq!(parse_lrc_timestamp("01:60"), None);
+        assert_eq!(parse_lrc_timestamp("01:02.x"), None);
+        assert_eq!(parse_lrc_timestamp("99999999:00"), None);
+    }
+
+    #[test]
+    fn parses_lines_with_several_timestamps_in_time_order() {
+        let lines = parse_lrc("[ti:Song]\n[00:45.10][00:12.00]Chorus\n[00:20.00]Verse\n");
+        assert_eq!(lines, vec![line(12_000, "Chorus"), line(20_000, "Verse"), line(45_100, "Chorus")]);
+    }
+
+    #[test]
+    fn applies_the_offset_header() {
+        let lines = parse_lrc("[offset:500]\n[00:00.20]First\n[00:10.00]Second\n");
+        assert_eq!(lines, vec![line(0, "First"), line(9_500, "Second")]);
+    }
+
+    #[test]
+    fn skips_lines_with_overflowing_timestamps() {
+        assert_eq!(parse_lrc("[99999999:00]Too late\n[00:01.00]Fine\n"), vec![line(1_000, "Fine")]);
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_timestamp(0), "00:00.00");
+        assert_eq!(format_timestamp(62_345), "01:02.34");
+        assert_eq!(format_timestamp(6_000_000), "100:00.00");
+    }
+
+    #[test]
+    fn formatted_lines_parse_back() {
+        let lines = vec![line(1_230, "One"), line(61_000, "Two")];
+        assert_eq!(parse_lrc(&format_lrc_lines(&lines)), lines);
+    }
+
+    #[test]
+    fn format_lrc_adds_id_tags() {
+        let metadata = AudioMetadata {
+            title: Some("Song".to_string()),
+            album: Some(String::new()),
+            synced_lyrics: vec![line(1_000, "Hello")],
+            ..AudioMetadata::default()
+        };
+        assert_eq!(format_lrc(&metadata), "[ti:Song]\n[00:01.00]Hello\n");
+    }
+}
@@ -1,5 +1,19 @@
+pub mod chapters;
+pub mod cleanup;
+pub mod encoding;
+pub mod fields;
+pub mod filename;
+pub mod genres;
 pub mod imp;
+pub mod lyrics;
+pub mod preset;
+pub mod snapshot;
+pub mod template;
+pub mod timestamp;
+pub mod validation;
+pub mod version;
 
+use fields::MetadataField;
 use std::path::Path;
 
 /// Represents the metadata extracted from an ID3 tag
@@ -7,16 +21,18 @@ use std::path::Path;
 pub struct AudioMetadata {
     // Basic information
     pub title: Option<String>,
-    pub artist: Option<String>,
+    pub artists: Vec<String>, // TPE1 values
     pub album: Option<String>,
-    pub year: Option<u32>,
-    pub genre: Option<String>,
+    pub recorded: Option<Timestamp>, // Recording time (TDRC, or TYER + TDAT + TIME in ID3v2.3)
+    pub genres: Vec<String>, // TCON values, with ID3v1 genre numbers decoded
     pub track: Option<u32>,
+    pub total_tracks: Option<u32>, // "/total" part of TRCK
     pub disc: Option<u32>, // Disc number
+    pub total_discs: Option<u32>, // "/total" part of TPOS
     pub album_artist: Option<String>,
-    pub composer: Option<String>,
+    pub composers: Vec<String>, // TCOM values
     pub comment: Option<String>,
-    pub duration: Option<u32>, // in seconds
+    pub duration: Option<u32>, // in milliseconds (TLEN)
     
     // Additional metadata
     pub publisher: Option<String>, // Record label/Publisher
@@ -25,7 +41,7 @@ pub struct AudioMetadata {
     pub copyright: Option<String>,
     pub original_artist: Option<String>,
     pub original_album: Option<String>,
-    pub original_year: Option<u32>,
+    pub original_released: Option<Timestamp>, // Original release time (TDOR, or TORY in ID3v2.3)
     pub bpm: Option<u32>, // Beats per minute
     pub isrc: Option<String>, // International Standard Recording Code
     pub lyrics: Option<String>,
@@ -34,7 +50,36 @@ pub struct AudioMetadata {
     pub producer: Option<String>,
     pub grouping: Option<String>, // Content group description
     pub subtitle: Option<String>, // Subtitle/Description refinement
-    pub date: Option<String>, // Recording date
+    pub title_sort: Option<String>, // TSOT
+    pub artist_sort: Option<String>, // TSOP
+    pub album_sort: Option<String>, // TSOA
+    pub album_artist_sort: Option<String>, // TSO2 (iTunes)
+    pub composer_sort: Option<String>, // TSOC (iTunes)
+    pub artist_url: Option<String>, // WOAR - Official artist webpage
+    pub audio_file_url: Option<String>, // WOAF - Official audio file webpage
+    pub audio_source_url: Option<String>, // WOAS - Official audio source webpage
+    pub publisher_url: Option<String>, // WPUB - Publisher's official webpage
+    pub released: Option<Timestamp>, // Release time (TDRL)
+    pub encoded: Option<Timestamp>, // Encoding time (TDEN)
+    pub tagged: Option<Timestamp>, // Tagging time (TDTG)
+    pub synced_lyrics: Vec<SyncedLyricLine>, // Time-coded lyrics (SYLT)
+    pub chapters: Vec<Chapter>, // Podcast/audiobook chapters (CHAP/CTOC)
+    pub involved_people: Vec<Credit>, // Production credits (TIPL, or IPLS in ID3v2.3)
+    pub musician_credits: Vec<Credit>, // Musicians and their instruments (TMCL, merged into IPLS in ID3v2.3)
+
+    // Ratings and play counts
+    pub popularimeters: Vec<Popularimeter>, // POPM frames, one per rating application
+    pub play_count: Option<u64>, // PCNT
+
+    // MusicBrainz identifiers (UFID and TXXX frames written by MusicBrainz Picard)
+    pub musicbrainz_recording_id: Option<String>,
+    pub musicbrainz_release_id: Option<String>,
+    pub musicbrainz_artist_id: Option<String>,
+    pub musicbrainz_release_group_id: Option<String>,
+    pub acoustid_fingerprint: Option<String>, // Chromaprint fingerprint (TXXX "Acoustid Fingerprint")
+
+    // Loudness normalization
+    pub replay_gain: ReplayGain, // TXXX REPLAYGAIN_* frames
     
     // Cover art
     pub cover_art: Option<Vec<u8>>, // Album cover image data
@@ -42,10 +87,121 @@ pub struct AudioMetadata {
     
     // Custom/Extended fields (stored as key-value pairs)
     pub custom_fields: Vec<(String, String)>,
+
+    pub tag_version: Option<TagVersion>, // Version of the tag read from the file (None = no tag yet)
+    pub latin1_fields: Vec<MetadataField>, // Text fields read from ISO-8859-1 frames
+}
+
+/// ID3v2 tag versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagVersion {
+    Id3v22,
+    Id3v23,
+    Id3v24,
+}
+
+impl std::fmt::Display for TagVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagVersion::Id3v22 => write!(f, "ID3v2.2"),
+            TagVersion::Id3v23 => write!(f, "ID3v2.3"),
+            TagVersion::Id3v24 => write!(f, "ID3v2.4"),
+        }
+    }
+}
+
+/// How multiple values of a text frame are joined in ID3v2.3, which has no multi-value frames
+///
+/// ID3v2.4 separates values with a null character. The ID3v2.3 standard uses "/" for some
+/// frames, which splits names such as "AC/DC", so "; " can be used instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListSeparator {
+    #[default]
+    Slash,
+    Semicolon,
+}
+
+impl ListSeparator {
+    pub const ALL: [ListSeparator; 2] = [ListSeparator::Slash, ListSeparator::Semicolon];
+
+    /// The text written between two values
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ListSeparator::Slash => "/",
+            ListSeparator::Semicolon => "; ",
+        }
+    }
+
+    /// Split a value read from an ID3v2.3 frame
+    pub fn split(self, value: &str) -> Vec<&str> {
+        match self {
+            ListSeparator::Slash => value.split('/').collect(),
+            ListSeparator::Semicolon => value.split(';').collect(),
+        }
+    }
 }
 
-/// Errors that can occur during ID3 parsing
-#[clippy::allow(unused_self)]
+impl std::fmt::Display for ListSeparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListSeparator::Slash => write!(f, "Slash \"/\" (ID3v2.3 standard)"),
+            ListSeparator::Semicolon => write!(f, "Semicolon \"; \""),
+        }
+    }
+}
+
+/// A date and time as stored in ID3v2.4 timestamp frames, down to the precision that is known
+///
+/// A part is only set if all coarser parts are (e.g. no day without a month); see
+/// `timestamp` for parsing and editing.
+pub use id3::Timestamp;
+
+/// A single time-coded line of synchronized lyrics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedLyricLine {
+    pub time_ms: u32, // Start time in milliseconds
+    pub text: String,
+}
+
+/// A chapter of a podcast or audiobook (CHAP frame and its sub-frames)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub element_id: String,
+    pub start_ms: u32,
+    pub end_ms: u32,
+    pub title: Option<String>, // TIT2 sub-frame
+    pub url: Option<String>,   // WXXX sub-frame
+    pub image: Option<Vec<u8>>, // APIC sub-frame
+    pub image_format: Option<String>,
+}
+
+/// A credit of an involved people list (TIPL, TMCL or IPLS frame)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credit {
+    pub role: String, // Involvement (e.g. "producer") or instrument (e.g. "bass")
+    pub name: String,
+}
+
+/// A rating and play counter stored by a player (POPM frame)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Popularimeter {
+    pub email: String, // Identifies the application/user that wrote the rating
+    pub rating: u8,    // 1 (worst) to 255 (best), 0 is unknown
+    pub counter: u64,
+}
+
+/// ReplayGain values (TXXX REPLAYGAIN_* frames, optionally mirrored in RVA2 frames)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f64>, // dB
+    pub track_peak: Option<f64>, // Linear, 1.0 = full scale
+    pub album_gain: Option<f64>, // dB
+    pub album_peak: Option<f64>, // Linear, 1.0 = full scale
+    pub rva2: bool,              // Whether RVA2 frames are present/written
+}
+
+/// Errors that can occur during ID3 parsing or writing
 #[derive(Debug, Clone)]
 pub enum ParseError {
     #[allow(unused)]
@@ -72,6 +228,25 @@ impl std::fmt::Display for ParseError {
 impl std::error::Error for ParseError {}
 
 /// Parse ID3 tags from an audio file
-pub fn parse_id3<P: AsRef<Path>>(path: P) -> Result<AudioMetadata, ParseError> {
-    imp::parse_id3_impl(path)
-}
\ No newline at end of file
+///
+/// `separator` splits the multiple values of ID3v2.3 frames (see `ListSeparator`).
+pub fn parse_id3<P: AsRef<Path>>(path: P, separator: ListSeparator) -> Result<AudioMetadata, ParseError> {
+    imp::parse_id3_impl(path, separator)
+}
+
+/// Write the metadata back to the ID3 tag of an audio file
+///
+/// `separator` joins multiple values in ID3v2.3 frames (see `ListSeparator`).
+pub fn write_id3<P: AsRef<Path>>(path: P, metadata: &AudioMetadata, separator: ListSeparator) -> Result<(), ParseError> {
+    imp::write_id3_impl(path, metadata, separator)
+}
+
+/// Whether a file is in a container that can carry an ID3v2 tag, so `write_id3` accepts it
+pub fn supports_id3<P: AsRef<Path>>(path: P) -> bool {
+    imp::supports_id3_impl(path)
+}
+
+/// Whether an audio file ends with an ID3v1 tag
+pub fn has_id3v1<P: AsRef<Path>>(path: P) -> bool {
+    imp::has_id3v1_impl(path)
+}
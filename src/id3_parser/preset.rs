@@ -0,0 +1,83 @@
+use super::fields::{FieldChange, MetadataField};
+use super::template::render_template;
+use super::AudioMetadata;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A named set of field values applied to tracks together (e.g. a label's publisher,
+/// copyright and encoder)
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagPreset {
+    pub name: String,
+    pub values: Vec<PresetValue>,
+}
+
+/// A value of a preset; an empty value clears the field
+///
+/// Values are templates (see `render_template`) rendered for each file, such as
+/// `℗ {year} {publisher}`, `{album_artist} - {album}` or `{parent_dir}`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetValue {
+    pub field: String, // Field name (see `MetadataField::from_name`)
+    pub value: String,
+}
+
+impl TagPreset {
+    pub fn new(name: impl Into<String>) -> Self {
+        TagPreset {
+            name: name.into(),
+            values: Vec::new(),
+        }
+    }
+
+    /// The fields of the preset and their values (unknown field names are skipped)
+    pub fn fields(&self) -> impl Iterator<Item = (MetadataField, &str)> {
+        self.values
+            .iter()
+            .filter_map(|value| Some((MetadataField::from_name(&value.field)?, value.value.as_str())))
+    }
+
+    /// Store the values of the preset, rendered for the file at `path`, in a track
+    ///
+    /// Values are set in the order of the preset, so a template sees the values set before
+    /// it (a copyright after the publisher uses the new publisher). Nothing is changed if a
+    /// template is invalid.
+    pub fn apply(&self, metadata: &mut AudioMetadata, path: &Path) -> Result<(), String> {
+        let mut result = metadata.clone();
+        for (field, template) in self.fields() {
+            let value = render_template(template, &result, path, str::to_string)?;
+            field.set_value(&mut result, &value);
+        }
+        *metadata = result;
+        Ok(())
+    }
+
+    /// The changes applying the preset would make to the track of the file at `path`
+    pub fn changes(&self, metadata: &AudioMetadata, path: &Path) -> Result<Vec<FieldChange>, String> {
+        let mut after = metadata.clone();
+        self.apply(&mut after, path)?;
+        let mut changes: Vec<FieldChange> = Vec::new();
+        for (field, _) in self.fields() {
+            let before = field.value(metadata).unwrap_or_default();
+            let value = field.value(&after).unwrap_or_default();
+            if before != value && !changes.iter().any(|change| change.field == field) {
+                changes.push(FieldChange {
+                    field,
+                    before,
+                    after: value,
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// A name for a new preset that no other preset has ("Preset 1", "Preset 2", ...)
+pub fn new_preset_name(presets: &[TagPreset]) -> String {
+    (1..)
+        .map(|number| format!("Preset {}", number))
+        .find(|name| presets.iter().all(|preset| preset.name != *name))
+        .unwrap_or_default()
+}
@@ -0,0 +1,203 @@
+use super::fields::{MetadataField, LIST_SEPARATOR};
+use super::AudioMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Items that belong to a single recording, so pasting them to other tracks is opt-in
+pub const TRACK_ITEMS: &[TagItem] = &[
+    TagItem::Field(MetadataField::Title),
+    TagItem::Field(MetadataField::TitleSort),
+    TagItem::Field(MetadataField::Subtitle),
+    TagItem::Field(MetadataField::Track),
+    TagItem::Field(MetadataField::Bpm),
+    TagItem::Field(MetadataField::Isrc),
+    TagItem::Field(MetadataField::MusicBrainzRecordingId),
+    TagItem::Lyrics,
+];
+
+/// What can be copied from one track to another: a field, the lyrics or the cover art
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagItem {
+    Field(MetadataField),
+    Lyrics,
+    CoverArt,
+}
+
+impl TagItem {
+    /// All items, in display order
+    pub fn all() -> impl Iterator<Item = TagItem> {
+        MetadataField::ALL
+            .into_iter()
+            .map(TagItem::Field)
+            .chain([TagItem::Lyrics, TagItem::CoverArt])
+    }
+
+    /// Human readable label
+    pub fn label(self) -> &'static str {
+        match self {
+            TagItem::Field(field) => field.label(),
+            TagItem::Lyrics => "Lyrics",
+            TagItem::CoverArt => "Cover Art",
+        }
+    }
+
+    /// The value as text (long lyrics are cut short, cover art has none)
+    pub fn value(self, metadata: &AudioMetadata) -> String {
+        match self {
+            TagItem::Field(field) => field.value(metadata).unwrap_or_default(),
+            TagItem::Lyrics => {
+                let lyrics = metadata.lyrics.as_deref().unwrap_or_default();
+                if lyrics.chars().count() > 200 {
+                    format!("{}...", lyrics.chars().take(200).collect::<String>())
+                } else {
+                    lyrics.to_string()
+                }
+            }
+            TagItem::CoverArt => String::new(),
+        }
+    }
+
+    /// Whether the track has a value for the item
+    pub fn is_set(self, metadata: &AudioMetadata) -> bool {
+        !self.same(metadata, &AudioMetadata::default())
+    }
+
+    /// Whether two tracks have the same value (a missing value equals an empty one)
+    pub fn same(self, left: &AudioMetadata, right: &AudioMetadata) -> bool {
+        let text = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+        match self {
+            TagItem::Field(field) if field.is_list() => field.list_values(left) == field.list_values(right),
+            TagItem::Field(field) => text(&field.value(left)) == text(&field.value(right)),
+            TagItem::Lyrics => text(&left.lyrics) == text(&right.lyrics),
+            TagItem::CoverArt => left.cover_art == right.cover_art,
+        }
+    }
+
+    /// Copy the value from one track to another
+    pub fn copy(self, from: &AudioMetadata, to: &mut AudioMetadata) {
+        match self {
+            TagItem::Field(field) => field.copy_value(from, to),
+            TagItem::Lyrics => to.lyrics = from.lyrics.clone(),
+            TagItem::CoverArt => {
+                to.cover_art = from.cover_art.clone();
+                to.cover_art_format = from.cover_art_format.clone();
+            }
+        }
+    }
+}
+
+/// Copied tags in their JSON form: fields by label and the lyrics
+///
+/// The cover art is left out, as megabytes of base64 don't belong on the clipboard.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TagsJson {
+    #[serde(default)]
+    fields: BTreeMap<String, FieldJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lyrics: Option<String>,
+}
+
+/// The value of a field; list fields keep their values apart
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum FieldJson {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// Format the items of a track that have a value as JSON for the clipboard, without the
+/// cover art
+pub fn format_tags_json(metadata: &AudioMetadata) -> Result<String, String> {
+    let mut json = TagsJson::default();
+    for field in MetadataField::ALL.into_iter().filter(|field| TagItem::Field(*field).is_set(metadata)) {
+        let value = if field.is_list() {
+            FieldJson::List(field.list_values(metadata).clone())
+        } else {
+            FieldJson::Text(field.value(metadata).unwrap_or_default())
+        };
+        json.fields.insert(field.label().to_string(), value);
+    }
+    json.lyrics = metadata.lyrics.clone().filter(|lyrics| !lyrics.is_empty());
+    serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+}
+
+/// Parse tags formatted by `format_tags_json`
+///
+/// Unknown field names and values a field doesn't accept are skipped.
+pub fn parse_tags_json(content: &str) -> Result<AudioMetadata, String> {
+    let json: TagsJson = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let mut metadata = AudioMetadata::default();
+    // Labels sort "... Date" before "... Year", so a year keeps the rest of its date
+    for (name, value) in json.fields {
+        let Some(field) = MetadataField::from_name(&name) else {
+            log::warn!("Skipping unknown field in copied tags: {}", name);
+            continue;
+        };
+        match value {
+            FieldJson::List(values) if field.is_list() => *field.list_values_mut(&mut metadata) = values,
+            FieldJson::List(values) => field.set_value(&mut metadata, &values.join(LIST_SEPARATOR)),
+            FieldJson::Text(value) => field.set_value(&mut metadata, &value),
+        }
+    }
+    metadata.lyrics = json.lyrics;
+    if !TagItem::all().any(|item| item.is_set(&metadata)) {
+        return Err("no tags found".to_string());
+    }
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged() -> AudioMetadata {
+        AudioMetadata {
+            title: Some("Song".to_string()),
+            artists: vec!["AC/DC".to_string(), "Guest".to_string()],
+            lyrics: Some("La la".to_string()),
+            cover_art: Some(vec![1, 2, 3]),
+            cover_art_format: Some("image/png".to_string()),
+            ..AudioMetadata::default()
+        }
+    }
+
+    #[test]
+    fn json_round_trip_keeps_lists_apart_and_leaves_out_the_cover() {
+        let json = format_tags_json(&tagged()).unwrap();
+        assert!(!json.contains("image/png"));
+        let parsed = parse_tags_json(&json).unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Song"));
+        assert_eq!(parsed.artists, ["AC/DC", "Guest"]);
+        assert_eq!(parsed.lyrics.as_deref(), Some("La la"));
+        assert_eq!(parsed.cover_art, None);
+    }
+
+    #[test]
+    fn parse_rejects_json_without_tags() {
+        assert!(parse_tags_json("{}").is_err());
+        assert!(parse_tags_json("not json").is_err());
+        assert!(parse_tags_json(r#"{"fields": {"Nonsense": "x"}}"#).is_err());
+    }
+
+    #[test]
+    fn copy_and_compare_items() {
+        let source = tagged();
+        let mut target = AudioMetadata::default();
+        assert!(!TagItem::CoverArt.same(&source, &target));
+        TagItem::CoverArt.copy(&source, &mut target);
+        assert!(TagItem::CoverArt.same(&source, &target));
+        assert_eq!(target.cover_art_format.as_deref(), Some("image/png"));
+        assert!(TagItem::Field(MetadataField::Album).same(&source, &target));
+        assert!(!TagItem::Field(MetadataField::Album).is_set(&source));
+    }
+
+    #[test]
+    fn long_lyrics_are_cut_short() {
+        let metadata = AudioMetadata {
+            lyrics: Some("a".repeat(300)),
+            ..AudioMetadata::default()
+        };
+        assert_eq!(TagItem::Lyrics.value(&metadata).len(), 203);
+    }
+}
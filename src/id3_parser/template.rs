@@ -0,0 +1,147 @@
+use super::fields::MetadataField;
+use super::AudioMetadata;
+use std::path::Path;
+
+/// Render a template such as `{album_artist}/{year} - {album}/{disc}{track:02} {title}.{ext}`
+///
+/// Variables are metadata field names (see `MetadataField::from_name`) and the file
+/// variables `ext`, `filename` (name without extension) and `parent_dir`. A `:0N` suffix
+/// pads a value with zeros to N characters. Missing values render as empty text.
+/// `{{` and `}}` produce literal braces. `filter` is applied to every substituted value.
+pub fn render_template(
+    template: &str,
+    metadata: &AudioMetadata,
+    path: &Path,
+    filter: impl Fn(&str) -> String,
+) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut variable = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => variable.push(c),
+                        None => return Err(format!("Unclosed {{ in template: {{{}", variable)),
+                    }
+                }
+                let value = variable_value(&variable, metadata, path)?;
+                output.push_str(&filter(&value));
+            }
+            '}' => return Err("Unmatched } in template".to_string()),
+            _ => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Value of a `name` or `name:0N` template variable
+fn variable_value(variable: &str, metadata: &AudioMetadata, path: &Path) -> Result<String, String> {
+    let (name, format) = match variable.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format.trim())),
+        None => (variable.trim(), None),
+    };
+
+    let value = match name {
+        "ext" => path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        "filename" => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        "parent_dir" => path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        _ => MetadataField::from_name(name)
+            .ok_or_else(|| format!("Unknown template variable: {}", name))?
+            .value(metadata)
+            .unwrap_or_default(),
+    };
+
+    match format {
+        None => Ok(value),
+        Some(format) => {
+            let width: usize = format
+                .strip_prefix('0')
+                .and_then(|width| width.parse().ok())
+                .ok_or_else(|| format!("Invalid format in template: {{{}}}", variable))?;
+            if value.is_empty() {
+                Ok(value)
+            } else {
+                Ok(format!("{:0>width$}", value, width = width))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> AudioMetadata {
+        AudioMetadata {
+            title: Some("Song".to_string()),
+            album: Some("Album".to_string()),
+            album_artist: Some("Band".to_string()),
+            track: Some(3),
+            ..AudioMetadata::default()
+        }
+    }
+
+    fn render(template: &str) -> Result<String, String> {
+        render_template(template, &metadata(), Path::new("/music/Folder/old name.mp3"), str::to_string)
+    }
+
+    #[test]
+    fn renders_fields_and_file_variables() {
+        assert_eq!(
+            render("{album_artist}/{album}/{track:02} {title}.{ext}").unwrap(),
+            "Band/Album/03 Song.mp3"
+        );
+        assert_eq!(render("{parent_dir} - {filename}").unwrap(), "Folder - old name");
+    }
+
+    #[test]
+    fn missing_values_render_empty_without_padding() {
+        assert_eq!(render("[{disc:02}]").unwrap(), "[]");
+    }
+
+    #[test]
+    fn double_braces_are_literal() {
+        assert_eq!(render("{{{title}}}").unwrap(), "{Song}");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(render("{title").is_err());
+        assert!(render("title}").is_err());
+        assert!(render("{nonsense}").is_err());
+        assert!(render("{track:2}").is_err());
+    }
+
+    #[test]
+    fn filters_substituted_values_only() {
+        let metadata = AudioMetadata {
+            title: Some("A/B".to_string()),
+            ..AudioMetadata::default()
+        };
+        let rendered = render_template("x/{title}", &metadata, Path::new("a.mp3"), |value| value.replace('/', "_"));
+        assert_eq!(rendered.unwrap(), "x/A_B");
+    }
+}
@@ -0,0 +1,170 @@
+use id3::Timestamp;
+
+/// Names of the parts of a timestamp, from the coarsest to the finest
+pub const TIMESTAMP_PARTS: [&str; 6] = ["Year", "Month", "Day", "Hour", "Minute", "Second"];
+
+/// Largest value of each part (days are checked against the month as well)
+const PART_MAX: [u32; 6] = [9999, 12, 31, 23, 59, 59];
+
+/// A timestamp holding only a year
+pub fn from_year(year: i32) -> Timestamp {
+    Timestamp {
+        year,
+        month: None,
+        day: None,
+        hour: None,
+        minute: None,
+        second: None,
+    }
+}
+
+/// Parse an ISO 8601 timestamp as written by ID3v2.4: `yyyy`, `yyyy-MM`, `yyyy-MM-dd`,
+/// `yyyy-MM-ddTHH`, `yyyy-MM-ddTHH:mm` or `yyyy-MM-ddTHH:mm:ss`
+///
+/// Stricter than the `FromStr` of `id3::Timestamp`, which reads any leading digits and
+/// doesn't check ranges: the text must be exactly what `Display` writes back (a space may
+/// separate the date from the time) and every part must be in range.
+pub fn parse_timestamp(text: &str) -> Option<Timestamp> {
+    let text = text.trim().replacen(' ', "T", 1);
+    let parsed: Timestamp = text.parse().ok()?;
+    if parsed.to_string() != text {
+        return None;
+    }
+    if !(0..=9999).contains(&parsed.year) {
+        return None;
+    }
+    // Rebuild part by part so each one is checked
+    let mut timestamp = from_year(parsed.year);
+    for (index, part) in parsed.parts().into_iter().enumerate().skip(1) {
+        let Some(part) = part else {
+            break;
+        };
+        timestamp = timestamp.with_part(index, part)?;
+    }
+    Some(timestamp)
+}
+
+/// Part-wise access to a timestamp, as the date editor shows it
+pub trait TimestampParts: Sized {
+    /// The parts of the timestamp in the order of `TIMESTAMP_PARTS` (None for a negative year)
+    fn parts(&self) -> [Option<u32>; 6];
+
+    /// Number of parts that are set (1 = year only, 6 = down to the second)
+    fn precision(&self) -> usize {
+        self.parts().iter().take_while(|part| part.is_some()).count()
+    }
+
+    /// Keep only the first `precision` parts; keeping none clears the timestamp
+    fn truncated(self, precision: usize) -> Option<Self>;
+
+    /// Set a part (indexed as in `TIMESTAMP_PARTS`)
+    ///
+    /// Returns None if the value is out of range or a coarser part isn't set. A day past
+    /// the end of a changed month is moved to its last day.
+    fn with_part(self, index: usize, value: u32) -> Option<Self>;
+}
+
+impl TimestampParts for Timestamp {
+    fn parts(&self) -> [Option<u32>; 6] {
+        [
+            u32::try_from(self.year).ok(),
+            self.month.map(u32::from),
+            self.day.map(u32::from),
+            self.hour.map(u32::from),
+            self.minute.map(u32::from),
+            self.second.map(u32::from),
+        ]
+    }
+
+    fn truncated(self, precision: usize) -> Option<Self> {
+        if precision == 0 {
+            return None;
+        }
+        let keep = |index: usize, part: Option<u8>| part.filter(|_| index < precision);
+        Some(Timestamp {
+            year: self.year,
+            month: keep(1, self.month),
+            day: keep(2, self.day),
+            hour: keep(3, self.hour),
+            minute: keep(4, self.minute),
+            second: keep(5, self.second),
+        })
+    }
+
+    fn with_part(self, index: usize, value: u32) -> Option<Self> {
+        if index > self.precision() || value > *PART_MAX.get(index)? {
+            return None;
+        }
+        if matches!(index, 1 | 2) && value == 0 {
+            return None;
+        }
+        let mut timestamp = self;
+        let small = value as u8;
+        match index {
+            0 => timestamp.year = value as i32,
+            1 => {
+                timestamp.month = Some(small);
+                timestamp.day = timestamp.day.map(|day| day.min(days_in_month(small)));
+            }
+            2 if value > u32::from(days_in_month(self.month?)) => return None,
+            2 => timestamp.day = Some(small),
+            3 => timestamp.hour = Some(small),
+            4 => timestamp.minute = Some(small),
+            _ => timestamp.second = Some(small),
+        }
+        Some(timestamp)
+    }
+}
+
+/// Days of a month, counting February 29 as valid since the year may still be typed
+fn days_in_month(month: u8) -> u8 {
+    match month {
+        2 => 29,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_precision() {
+        for text in ["2021", "2021-03", "2021-03-14", "2021-03-14T09", "2021-03-14T09:05", "2021-03-14T09:05:30"] {
+            let timestamp = parse_timestamp(text).unwrap();
+            assert_eq!(timestamp.to_string(), text);
+        }
+        assert_eq!(parse_timestamp("2021-03-14 09:05").unwrap().to_string(), "2021-03-14T09:05");
+    }
+
+    #[test]
+    fn rejects_what_the_id3_parser_lets_through() {
+        for text in ["2021-13", "2021-02-30", "2021-03-14T24", "21", "2021-3", "2021abc", "2021-00", ""] {
+            assert_eq!(parse_timestamp(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn precision_counts_the_set_parts() {
+        assert_eq!(from_year(2021).precision(), 1);
+        assert_eq!(parse_timestamp("2021-03-14T09").unwrap().precision(), 4);
+    }
+
+    #[test]
+    fn truncated_drops_finer_parts() {
+        let timestamp = parse_timestamp("2021-03-14T09:05").unwrap();
+        assert_eq!(timestamp.truncated(2), parse_timestamp("2021-03"));
+        assert_eq!(timestamp.truncated(0), None);
+    }
+
+    #[test]
+    fn with_part_checks_ranges_and_order() {
+        let timestamp = parse_timestamp("2021-01-31").unwrap();
+        assert_eq!(timestamp.with_part(1, 4), parse_timestamp("2021-04-30"));
+        assert_eq!(timestamp.with_part(1, 13), None);
+        assert_eq!(timestamp.with_part(2, 0), None);
+        assert_eq!(timestamp.with_part(4, 30), None);
+        assert_eq!(from_year(2021).with_part(0, 1999), Some(from_year(1999)));
+    }
+}
@@ -0,0 +1,259 @@
+use super::fields::{is_valid_uuid, musicbrainz_ids, MetadataField};
+use super::AudioMetadata;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Earliest year accepted for recordings and releases (the first phonograph recordings)
+const MIN_YEAR: i32 = 1860;
+
+/// ISO 639-2 language codes, with both the bibliographic and terminology forms, and the
+/// special codes "mis", "mul", "und" and "zxx"
+const ISO_639_2_CODES: &[&str] = &[
+    "aar", "abk", "ace", "ach", "ada", "ady", "afa", "afh", "afr", "ain", "aka", "akk", "alb", "ale", "alg",
+    "alt", "amh", "ang", "anp", "apa", "ara", "arc", "arg", "arm", "arn", "arp", "art", "arw", "asm", "ast",
+    "ath", "aus", "ava", "ave", "awa", "aym", "aze", "bad", "bai", "bak", "bal", "bam", "ban", "baq", "bas",
+    "bat", "bej", "bel", "bem", "ben", "ber", "bho", "bih", "bik", "bin", "bis", "bla", "bnt", "bod", "bos",
+    "bra", "bre", "btk", "bua", "bug", "bul", "bur", "byn", "cad", "cai", "car", "cat", "cau", "ceb", "cel",
+    "ces", "cha", "chb", "che", "chg", "chi", "chk", "chm", "chn", "cho", "chp", "chr", "chu", "chv", "chy",
+    "cmc", "cnr", "cop", "cor", "cos", "cpe", "cpf", "cpp", "cre", "crh", "crp", "csb", "cus", "cym", "cze",
+    "dak", "dan", "dar", "day", "del", "den", "deu", "dgr", "din", "div", "doi", "dra", "dsb", "dua", "dum",
+    "dut", "dyu", "dzo", "efi", "egy", "eka", "ell", "elx", "eng", "enm", "epo", "est", "eus", "ewe", "ewo",
+    "fan", "fao", "fas", "fat", "fij", "fil", "fin", "fiu", "fon", "fra", "fre", "frm", "fro", "frr", "frs",
+    "fry", "ful", "fur", "gaa", "gay", "gba", "gem", "geo", "ger", "gez", "gil", "gla", "gle", "glg", "glv",
+    "gmh", "goh", "gon", "gor", "got", "grb", "grc", "gre", "grn", "gsw", "guj", "gwi", "hai", "hat", "hau",
+    "haw", "heb", "her", "hil", "him", "hin", "hit", "hmn", "hmo", "hrv", "hsb", "hun", "hup", "hye", "iba",
+    "ibo", "ice", "ido", "iii", "ijo", "iku", "ile", "ilo", "ina", "inc", "ind", "ine", "inh", "ipk", "ira",
+    "iro", "isl", "ita", "jav", "jbo", "jpn", "jpr", "jrb", "kaa", "kab", "kac", "kal", "kam", "kan", "kar",
+    "kas", "kat", "kau", "kaw", "kaz", "kbd", "kha", "khi", "khm", "kho", "kik", "kin", "kir", "kmb", "kok",
+    "kom", "kon", "kor", "kos", "kpe", "krc", "krl", "kro", "kru", "kua", "kum", "kur", "kut", "lad", "lah",
+    "lam", "lao", "lat", "lav", "lez", "lim", "lin", "lit", "lol", "loz", "ltz", "lua", "lub", "lug", "lui",
+    "lun", "luo", "lus", "mac", "mad", "mag", "mah", "mai", "mak", "mal", "man", "mao", "map", "mar", "mas",
+    "may", "mdf", "mdr", "men", "mga", "mic", "min", "mis", "mkd", "mkh", "mlg", "mlt", "mnc", "mni", "mno",
+    "moh", "mon", "mos", "mri", "msa", "mul", "mun", "mus", "mwl", "mwr", "mya", "myn", "myv", "nah", "nai",
+    "nap", "nau", "nav", "nbl", "nde", "ndo", "nds", "nep", "new", "nia", "nic", "niu", "nld", "nno", "nob",
+    "nog", "non", "nor", "nqo", "nso", "nub", "nwc", "nya", "nym", "nyn", "nyo", "nzi", "oci", "oji", "ori",
+    "orm", "osa", "oss", "ota", "oto", "paa", "pag", "pal", "pam", "pan", "pap", "pau", "peo", "per", "phi",
+    "phn", "pli", "pol", "pon", "por", "pra", "pro", "pus", "que", "raj", "rap", "rar", "roa", "roh", "rom",
+    "ron", "rum", "run", "rup", "rus", "sad", "sag", "sah", "sai", "sal", "sam", "san", "sas", "sat", "scn",
+    "sco", "sel", "sem", "sga", "sgn", "shn", "sid", "sin", "sio", "sit", "sla", "slk", "slo", "slv", "sma",
+    "sme", "smi", "smj", "smn", "smo", "sms", "sna", "snd", "snk", "sog", "som", "son", "sot", "spa", "sqi",
+    "srd", "srn", "srp", "srr", "ssa", "ssw", "suk", "sun", "sus", "sux", "swa", "swe", "syc", "syr", "tah",
+    "tai", "tam", "tat", "tel", "tem", "ter", "tet", "tgk", "tgl", "tha", "tib", "tig", "tir", "tiv", "tkl",
+    "tlh", "tli", "tmh", "tog", "ton", "tpi", "tsi", "tsn", "tso", "tuk", "tum", "tup", "tur", "tut", "tvl",
+    "twi", "tyv", "udm", "uga", "uig", "ukr", "umb", "und", "urd", "uzb", "vai", "ven", "vie", "vol", "vot",
+    "wak", "wal", "war", "was", "wel", "wen", "wln", "wol", "xal", "xho", "yao", "yap", "yid", "yor", "ypk",
+    "zap", "zbl", "zen", "zgh", "zha", "zho", "znd", "zul", "zun", "zxx", "zza",
+];
+
+/// A problem with the value of a field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldIssue {
+    pub field: MetadataField,
+    pub message: String,
+}
+
+impl FieldIssue {
+    fn new(field: MetadataField, message: impl Into<String>) -> Self {
+        FieldIssue {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+/// Check the values of a track's fields
+///
+/// Covers ISRC codes, years, BPM, track and disc numbers against their totals, TLAN
+/// language codes, URLs and MusicBrainz IDs. Empty fields are never an issue.
+pub fn validate(metadata: &AudioMetadata) -> Vec<FieldIssue> {
+    let mut issues = Vec::new();
+
+    if let Some(isrc) = metadata.isrc.as_deref().filter(|isrc| !isrc.trim().is_empty()) {
+        if !is_valid_isrc(isrc) {
+            issues.push(FieldIssue::new(MetadataField::Isrc, "Not a valid ISRC (CC-XXX-YY-NNNNN)"));
+        }
+    }
+
+    let max_year = current_year() + 1;
+    let years = [
+        (MetadataField::Year, metadata.recorded),
+        (MetadataField::OriginalYear, metadata.original_released),
+    ];
+    for (field, timestamp) in years {
+        if timestamp.is_some_and(|timestamp| !(MIN_YEAR..=max_year).contains(&timestamp.year)) {
+            issues.push(FieldIssue::new(field, format!("Year must be between {} and {}", MIN_YEAR, max_year)));
+        }
+    }
+
+    if metadata.bpm == Some(0) {
+        issues.push(FieldIssue::new(MetadataField::Bpm, "BPM must be a positive number"));
+    }
+
+    let numbers = [
+        (MetadataField::Track, metadata.track, metadata.total_tracks, "track"),
+        (MetadataField::Disc, metadata.disc, metadata.total_discs, "disc"),
+    ];
+    for (field, number, total, name) in numbers {
+        match (number, total) {
+            (Some(number), Some(total)) if number > total => {
+                issues.push(FieldIssue::new(field, format!("The {} number is larger than the total ({})", name, total)));
+            }
+            (None, Some(_)) => {
+                issues.push(FieldIssue::new(field, format!("A {} total needs a {} number", name, name)));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(language) = metadata.language.as_deref() {
+        let invalid: Vec<&str> = language_codes(language).filter(|code| !is_valid_language(code)).collect();
+        if !invalid.is_empty() {
+            issues.push(FieldIssue::new(
+                MetadataField::Language,
+                format!("Not an ISO 639-2 language code: {}", invalid.join(", ")),
+            ));
+        }
+    }
+
+    for field in MetadataField::ALL {
+        let Some(value) = field.value(metadata).filter(|value| !value.trim().is_empty()) else {
+            continue;
+        };
+        if field.is_url() && !is_valid_url(&value) {
+            issues.push(FieldIssue::new(field, "Not a well-formed URL"));
+        } else if field.musicbrainz_entity().is_some() && musicbrainz_ids(&value).iter().any(|id| !is_valid_uuid(id)) {
+            issues.push(FieldIssue::new(field, "Not a valid MusicBrainz ID (UUID)"));
+        }
+    }
+
+    issues
+}
+
+/// Issues of a track that aren't among the issues it had before a change
+///
+/// Batch edits use this so files with values that were already invalid can still be
+/// tagged, as long as the edit doesn't add problems of its own.
+pub fn new_issues(before: &[FieldIssue], metadata: &AudioMetadata) -> Vec<FieldIssue> {
+    validate(metadata)
+        .into_iter()
+        .filter(|issue| !before.contains(issue))
+        .collect()
+}
+
+/// Whether a value is an ISRC: country code, registrant, year and designation code,
+/// with or without hyphens (`GB-AYE-69-00531` or `GBAYE6900531`)
+pub fn is_valid_isrc(value: &str) -> bool {
+    let value = value.trim();
+    let code: String = value.chars().filter(|c| *c != '-').collect();
+    let hyphens_ok = !value.contains('-') || value.split('-').map(str::len).eq([2, 3, 2, 5]);
+    hyphens_ok
+        && code.is_ascii()
+        && code.len() == 12
+        && code[..2].chars().all(|c| c.is_ascii_uppercase())
+        && code[2..5].chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && code[5..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Whether a value is an ISO 639-2 code (lowercase, as the ID3 specification requires);
+/// the range qaa-qtz is reserved for local use
+pub fn is_valid_language(code: &str) -> bool {
+    ISO_639_2_CODES.contains(&code) || (code.len() == 3 && ("qaa"..="qtz").contains(&code))
+}
+
+/// The codes of a TLAN value, which may hold several languages
+fn language_codes(value: &str) -> impl Iterator<Item = &str> {
+    value.split(['/', ';', '\0']).map(str::trim).filter(|code| !code.is_empty())
+}
+
+/// Whether a value is an absolute URL with a scheme and no whitespace
+///
+/// URLs with an authority (`https://host/...`) need a host; `mailto:` URLs need an address.
+pub fn is_valid_url(value: &str) -> bool {
+    let value = value.trim();
+    let Some((scheme, rest)) = value.split_once(':') else {
+        return false;
+    };
+    let scheme_ok = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !scheme_ok || rest.is_empty() || value.chars().any(char::is_whitespace) {
+        return false;
+    }
+    match rest.strip_prefix("//") {
+        Some(authority) => {
+            let host = authority.split(['/', '?', '#']).next().unwrap_or_default();
+            let host = host.rsplit('@').next().unwrap_or_default();
+            !host.is_empty() && !host.starts_with(':')
+        }
+        None if scheme.eq_ignore_ascii_case("mailto") => rest.contains('@'),
+        None => !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https"),
+    }
+}
+
+/// The current year in UTC, close enough for a range check
+fn current_year() -> i32 {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    1970 + (seconds / 31_556_952) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(issues: &[FieldIssue]) -> Vec<MetadataField> {
+        issues.iter().map(|issue| issue.field).collect()
+    }
+
+    #[test]
+    fn empty_metadata_is_valid() {
+        assert!(validate(&AudioMetadata::default()).is_empty());
+    }
+
+    #[test]
+    fn checks_isrc_codes() {
+        assert!(is_valid_isrc("GB-AYE-69-00531"));
+        assert!(is_valid_isrc("GBAYE6900531"));
+        assert!(!is_valid_isrc("GBAYE69"));
+        assert!(!is_valid_isrc("12AYE6900531"));
+    }
+
+    #[test]
+    fn checks_languages_and_urls() {
+        assert!(is_valid_language("eng"));
+        assert!(!is_valid_language("english"));
+        assert!(is_valid_url("https://example.com/artist"));
+        assert!(!is_valid_url("example"));
+    }
+
+    #[test]
+    fn checks_numbers_against_totals() {
+        let metadata = AudioMetadata {
+            track: Some(12),
+            total_tracks: Some(10),
+            total_discs: Some(2),
+            ..AudioMetadata::default()
+        };
+        assert_eq!(fields(&validate(&metadata)), [MetadataField::Track, MetadataField::Disc]);
+    }
+
+    #[test]
+    fn checks_years_and_bpm() {
+        let metadata = AudioMetadata {
+            recorded: Some(crate::id3_parser::timestamp::from_year(1700)),
+            bpm: Some(0),
+            ..AudioMetadata::default()
+        };
+        assert_eq!(fields(&validate(&metadata)), [MetadataField::Year, MetadataField::Bpm]);
+    }
+
+    #[test]
+    fn new_issues_ignores_existing_problems() {
+        let mut metadata = AudioMetadata {
+            isrc: Some("bad".to_string()),
+            ..AudioMetadata::default()
+        };
+        let before = validate(&metadata);
+        metadata.title = Some("Edited".to_string());
+        assert!(new_issues(&before, &metadata).is_empty());
+        metadata.bpm = Some(0);
+        assert_eq!(fields(&new_issues(&before, &metadata)), [MetadataField::Bpm]);
+    }
+}
@@ -0,0 +1,349 @@
+use super::genres::split_legacy_genres;
+use super::{ListSeparator, ParseError, TagVersion};
+use id3::frame::{Content, InvolvedPeopleList};
+use id3::{Encoding, Frame, Tag, TagLike, Timestamp, Version};
+use std::path::Path;
+
+/// Frames of ID3v2.4 that have no ID3v2.3 counterpart
+///
+/// The sort order frames TSOA/TSOP/TSOT are also new in ID3v2.4, but iTunes writes them to
+/// ID3v2.3 tags as well and players read them there, so they are kept.
+const V24_ONLY_FRAMES: &[&str] = &[
+    "ASPI", "EQU2", "RVA2", "SEEK", "SIGN", "TDEN", "TDRL", "TDTG", "TMOO", "TPRO", "TSST",
+];
+
+/// Text frames whose ID3v2.3 values are lists joined by the configured separator
+const LIST_FRAMES: &[&str] = &["TPE1", "TCOM", "TCON"];
+
+/// Frames of ID3v2.3 that were removed from ID3v2.4 without a replacement
+const V23_ONLY_FRAMES: &[&str] = &["TRDA", "TSIZ", "EQUA", "RVAD"];
+
+impl From<Version> for TagVersion {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::Id3v22 => TagVersion::Id3v22,
+            Version::Id3v23 => TagVersion::Id3v23,
+            Version::Id3v24 => TagVersion::Id3v24,
+        }
+    }
+}
+
+impl From<TagVersion> for Version {
+    fn from(version: TagVersion) -> Self {
+        match version {
+            TagVersion::Id3v22 => Version::Id3v22,
+            TagVersion::Id3v23 => Version::Id3v23,
+            TagVersion::Id3v24 => Version::Id3v24,
+        }
+    }
+}
+
+/// What converting a tag to another version does to its frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionReport {
+    pub from: TagVersion,
+    pub to: TagVersion,
+    pub mapped: Vec<String>,  // Frames rewritten for the target version (e.g. "TORY → TDOR")
+    pub dropped: Vec<String>, // Frames the target version can't represent, with the reason
+}
+
+impl ConversionReport {
+    /// Whether converting changes anything
+    pub fn is_empty(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+/// Report what converting the tag of a file would do, without writing it
+pub fn preview_conversion<P: AsRef<Path>>(
+    path: P,
+    target: TagVersion,
+    separator: ListSeparator,
+) -> Result<ConversionReport, ParseError> {
+    read_tag(path.as_ref()).map(|tag| convert_tag(&tag, target, separator).1)
+}
+
+/// Convert the tag of a file to another ID3v2 version and write it back
+///
+/// Only ID3v2.3 and ID3v2.4 are written; ID3v2.2 tags can be converted from. `separator`
+/// splits or joins the multiple values of ID3v2.3 frames.
+pub fn convert_file<P: AsRef<Path>>(
+    path: P,
+    target: TagVersion,
+    separator: ListSeparator,
+) -> Result<ConversionReport, ParseError> {
+    let path = path.as_ref();
+    if target == TagVersion::Id3v22 {
+        return Err(ParseError::InvalidFormat);
+    }
+    let (tag, report) = convert_tag(&read_tag(path)?, target, separator);
+    if report.is_empty() {
+        return Ok(report);
+    }
+
+    tag.write_to_path(path, target.into()).map_err(|e| {
+        log::error!("Error writing {} tag to {:?}: {}", target, path, e);
+        ParseError::IoError(e.to_string())
+    })?;
+    log::info!(
+        "Converted {:?} from {} to {}: {} frames mapped, {} dropped",
+        path,
+        report.from,
+        report.to,
+        report.mapped.len(),
+        report.dropped.len()
+    );
+    Ok(report)
+}
+
+fn read_tag(path: &Path) -> Result<Tag, ParseError> {
+    match Tag::read_from_path(path) {
+        Ok(tag) => Ok(tag),
+        Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => Err(ParseError::NoId3Tag),
+        Err(e) => {
+            log::error!("Error reading ID3 tag from {:?}: {}", path, e);
+            Err(ParseError::IoError(e.to_string()))
+        }
+    }
+}
+
+/// Build a copy of a tag for the target version
+///
+/// ID3v2.2 frames are read under their ID3v2.3 names, so ID3v2.2 converts like ID3v2.3.
+fn convert_tag(tag: &Tag, target: TagVersion, separator: ListSeparator) -> (Tag, ConversionReport) {
+    let mut report = ConversionReport {
+        from: tag.version().into(),
+        to: target,
+        mapped: Vec::new(),
+        dropped: Vec::new(),
+    };
+    if report.from == target {
+        return (tag.clone(), report);
+    }
+
+    let mut converted = Tag::with_version(target.into());
+    for frame in tag.frames() {
+        let id = frame.id();
+        if id.len() != 4 {
+            report.dropped.push(format!("{} (ID3v2.2 frame without a later equivalent)", id));
+        } else if target == TagVersion::Id3v24 && V23_ONLY_FRAMES.contains(&id) {
+            report.dropped.push(format!("{} (not part of ID3v2.4)", id));
+        } else if target == TagVersion::Id3v23 && V24_ONLY_FRAMES.contains(&id) {
+            report.dropped.push(format!("{} (not part of ID3v2.3)", id));
+        } else if !matches!(id, "TYER" | "TDAT" | "TIME" | "TORY" | "TDRC" | "TDOR" | "IPLS" | "TIPL" | "TMCL") {
+            converted.add_frame(match target {
+                TagVersion::Id3v23 => downgrade_frame(frame, separator, &mut report),
+                _ if LIST_FRAMES.contains(&id) => split_list_frame(frame, separator, &mut report),
+                _ => frame.clone(),
+            });
+        }
+    }
+
+    match target {
+        TagVersion::Id3v24 => convert_dates_to_v24(tag, &mut converted, &mut report),
+        _ => convert_dates_to_v23(tag, &mut converted, separator, &mut report),
+    }
+    convert_involved_people(tag, &mut converted, target, &mut report);
+
+    (converted, report)
+}
+
+/// Adapt a frame to what ID3v2.3 can store
+///
+/// ID3v2.3 has no UTF-8 text encoding and no multi-value frames, so multiple values are
+/// joined with the configured separator.
+fn downgrade_frame(frame: &Frame, separator: ListSeparator, report: &mut ConversionReport) -> Frame {
+    let frame = match frame.content() {
+        Content::Text(text) if text.contains('\0') => {
+            let separator = separator.as_str();
+            report.mapped.push(format!("{} (multiple values joined with \"{}\")", frame.id(), separator));
+            Frame::with_content(frame.id(), Content::Text(text.replace('\0', separator))).set_encoding(frame.encoding())
+        }
+        _ => frame.clone(),
+    };
+    if matches!(frame.encoding(), Some(Encoding::UTF8 | Encoding::UTF16BE)) {
+        frame.set_encoding(Some(Encoding::UTF16))
+    } else {
+        frame
+    }
+}
+
+/// Split an ID3v2.3 list (e.g. "Artist A/Artist B") into ID3v2.4 values
+fn split_list_frame(frame: &Frame, separator: ListSeparator, report: &mut ConversionReport) -> Frame {
+    let Content::Text(text) = frame.content() else {
+        return frame.clone();
+    };
+    // The id3 crate reads the "/" of ID3v2.3 TPE1/TCOM as a null character
+    let text = text.replace('\0', "/");
+    let values: Vec<&str> = if frame.id() == "TCON" {
+        split_legacy_genres(&text, separator)
+    } else {
+        separator.split(&text)
+    }
+    .into_iter()
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+    .collect();
+    if values.len() < 2 {
+        return frame.clone();
+    }
+    report.mapped.push(format!("{} (\"{}\" split into {} values)", frame.id(), separator.as_str().trim(), values.len()));
+    Frame::with_content(frame.id(), Content::new_text_values(values)).set_encoding(frame.encoding())
+}
+
+/// TYER + TDAT + TIME → TDRC and TORY → TDOR
+///
+/// Dates already stored in the ID3v2.4 frames are kept.
+fn convert_dates_to_v24(tag: &Tag, converted: &mut Tag, report: &mut ConversionReport) {
+    let text = |id: &str| tag.get(id).and_then(|frame| frame.content().text()).map(str::trim);
+    let legacy: Vec<&str> = ["TYER", "TDAT", "TIME"]
+        .into_iter()
+        .filter(|id| tag.get(id).is_some())
+        .collect();
+    if let Some(recorded) = tag.date_recorded() {
+        converted.set_date_recorded(recorded);
+        for id in legacy {
+            report.dropped.push(format!("{} (TDRC is already set)", id));
+        }
+    } else if let Some(year) = tag.year() {
+        // TDAT is DDMM, TIME is HHMM
+        let pair = |value: Option<&str>| -> Option<(u8, u8)> {
+            let value = value.filter(|value| value.len() == 4 && value.chars().all(|c| c.is_ascii_digit()))?;
+            Some((value[..2].parse().ok()?, value[2..].parse().ok()?))
+        };
+        let (day, month) = pair(text("TDAT")).unzip();
+        let (hour, minute) = pair(text("TIME")).unzip();
+        converted.set_date_recorded(Timestamp {
+            year,
+            month,
+            day,
+            hour: hour.filter(|_| month.is_some()),
+            minute: minute.filter(|_| month.is_some()),
+            second: None,
+        });
+        report.mapped.push(format!("{} → TDRC", legacy.join(" + ")));
+    } else {
+        for id in legacy {
+            report.dropped.push(format!("{} (no year to build TDRC from)", id));
+        }
+    }
+
+    if let Some(original) = tag.original_date_released() {
+        converted.set_original_date_released(original);
+        if tag.get("TORY").is_some() {
+            report.dropped.push("TORY (TDOR is already set)".to_string());
+        }
+    } else if let Some(year) = text("TORY").and_then(|year| year.parse().ok()) {
+        converted.set_original_date_released(Timestamp {
+            year,
+            month: None,
+            day: None,
+            hour: None,
+            minute: None,
+            second: None,
+        });
+        report.mapped.push("TORY → TDOR".to_string());
+    } else if tag.get("TORY").is_some() {
+        report.dropped.push("TORY (not a year)".to_string());
+    }
+}
+
+/// TDRC → TYER + TDAT + TIME and TDOR → TORY
+///
+/// Values already stored in the ID3v2.3 frames are kept.
+fn convert_dates_to_v23(tag: &Tag, converted: &mut Tag, separator: ListSeparator, report: &mut ConversionReport) {
+    for id in ["TYER", "TDAT", "TIME", "TORY"] {
+        if let Some(frame) = tag.get(id) {
+            converted.add_frame(downgrade_frame(frame, separator, report));
+        }
+    }
+
+    if let Some(recorded) = tag.date_recorded() {
+        let mut targets = Vec::new();
+        if converted.get("TYER").is_none() {
+            converted.set_text("TYER", format!("{:04}", recorded.year));
+            targets.push("TYER");
+        }
+        if let (Some(month), Some(day)) = (recorded.month, recorded.day) {
+            if converted.get("TDAT").is_none() {
+                converted.set_text("TDAT", format!("{:02}{:02}", day, month));
+                targets.push("TDAT");
+            }
+        }
+        if let (Some(hour), Some(minute)) = (recorded.hour, recorded.minute) {
+            if converted.get("TIME").is_none() {
+                converted.set_text("TIME", format!("{:02}{:02}", hour, minute));
+                targets.push("TIME");
+            }
+        }
+        if recorded.second.is_some() {
+            report.dropped.push("TDRC seconds (ID3v2.3 times have minute precision)".to_string());
+        }
+        if targets.is_empty() {
+            report.dropped.push("TDRC (TYER is already set)".to_string());
+        } else {
+            report.mapped.push(format!("TDRC → {}", targets.join(" + ")));
+        }
+    }
+
+    if let Some(original) = tag.original_date_released() {
+        if converted.get("TORY").is_none() {
+            converted.set_text("TORY", format!("{:04}", original.year));
+            report.mapped.push("TDOR → TORY".to_string());
+        } else {
+            report.dropped.push("TDOR (TORY is already set)".to_string());
+        }
+        if original.month.is_some() {
+            report.dropped.push("TDOR month and day (TORY only holds a year)".to_string());
+        }
+    }
+}
+
+/// IPLS ↔ TIPL (+ TMCL)
+///
+/// ID3v2.4 splits the involved people list into production credits (TIPL) and musician
+/// credits (TMCL); ID3v2.3 only has IPLS, so both are merged into it.
+fn convert_involved_people(tag: &Tag, converted: &mut Tag, target: TagVersion, report: &mut ConversionReport) {
+    let list = |id: &str| {
+        tag.get(id)
+            .and_then(|frame| frame.content().involved_people_list())
+            .cloned()
+    };
+    let (sources, destination): (&[&str], &str) = match target {
+        TagVersion::Id3v24 => (&["IPLS"], "TIPL"),
+        _ => (&["TIPL", "TMCL"], "IPLS"),
+    };
+    // Frames already in the target version's form are kept as they are
+    let kept: &[&str] = match target {
+        TagVersion::Id3v24 => &["TIPL", "TMCL"],
+        _ => &["IPLS"],
+    };
+    for id in kept {
+        if let Some(frame) = tag.get(id) {
+            converted.add_frame(frame.clone());
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut used = Vec::new();
+    for id in sources {
+        if let Some(people) = list(id) {
+            items.extend(people.items);
+            used.push(*id);
+        }
+    }
+    if used.is_empty() {
+        return;
+    }
+    if converted.get(destination).is_some() {
+        for id in used {
+            report.dropped.push(format!("{} ({} is already set)", id, destination));
+        }
+        return;
+    }
+    converted.add_frame(Frame::with_content(
+        destination,
+        Content::InvolvedPeopleList(InvolvedPeopleList { items }),
+    ));
+    report.mapped.push(format!("{} → {}", used.join(" + "), destination));
+}
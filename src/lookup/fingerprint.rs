@@ -0,0 +1,180 @@
+use super::LookupError;
+use crate::audio::fingerprint::{Fingerprint, DUPLICATE_THRESHOLD};
+use crate::id3_parser::fields::{split_list, MetadataField};
+use crate::id3_parser::AudioMetadata;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of suggested recordings
+const MAX_MATCHES: usize = 10;
+
+/// A source of recordings identified by acoustic fingerprint (local index, AcoustID, ...)
+pub trait FingerprintProvider {
+    /// Short name shown in the UI
+    fn name(&self) -> String;
+
+    /// Find recordings matching the fingerprint, best matches first
+    fn identify(&self, fingerprint: &Fingerprint) -> Result<Vec<RecordingMatch>, LookupError>;
+}
+
+/// A recording identified by its fingerprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingMatch {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub recording_id: Option<String>,
+    pub score: u32, // 0-100, fingerprint similarity
+}
+
+impl RecordingMatch {
+    /// Apply the known values of the recording to a file's metadata
+    pub fn apply_to(&self, metadata: &mut AudioMetadata) {
+        if self.title.is_some() {
+            metadata.title = self.title.clone();
+        }
+        if let Some(artist) = &self.artist {
+            metadata.artists = split_list(artist);
+        }
+        if self.album.is_some() {
+            metadata.album = self.album.clone();
+        }
+        if self.recording_id.is_some() {
+            metadata.musicbrainz_recording_id = self.recording_id.clone();
+        }
+    }
+}
+
+/// A fingerprinted track stored in the local index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub fingerprint: String, // Compressed, base64 encoded (as stored in the tag)
+    #[serde(default)]
+    pub duration: u32, // in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>, // File the entry was created from
+}
+
+impl IndexEntry {
+    /// Create an entry for a tagged file
+    pub fn from_metadata(path: &Path, metadata: &AudioMetadata, fingerprint: &Fingerprint) -> Self {
+        Self {
+            fingerprint: fingerprint.encode(),
+            duration: fingerprint.duration,
+            title: metadata.title.clone(),
+            artist: MetadataField::Artist.value(metadata),
+            album: metadata.album.clone(),
+            recording_id: metadata.musicbrainz_recording_id.clone(),
+            path: Some(path.to_string_lossy().to_string()),
+        }
+    }
+
+    /// Whether the entry carries anything worth suggesting
+    pub fn is_tagged(&self) -> bool {
+        self.title.is_some() || self.recording_id.is_some()
+    }
+}
+
+/// Fingerprint provider backed by a JSON file of known tracks
+///
+/// The index is built from already tagged files, so untagged copies of the same
+/// recordings can be identified offline.
+pub struct FingerprintIndex {
+    path: PathBuf,
+    entries: Vec<IndexEntry>,
+    fingerprints: Vec<Option<Fingerprint>>, // Decoded fingerprints of the entries
+}
+
+impl FingerprintIndex {
+    /// Load an index file; a missing file is an empty index
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LookupError> {
+        let path_ref = path.as_ref();
+
+        log::debug!("Loading fingerprint index from: {:?}", path_ref);
+
+        let entries: Vec<IndexEntry> = if path_ref.exists() {
+            let content = std::fs::read_to_string(path_ref).map_err(|e| LookupError::IoError(e.to_string()))?;
+            serde_json::from_str(&content).map_err(|e| LookupError::InvalidData(e.to_string()))?
+        } else {
+            log::debug!("Fingerprint index does not exist yet, starting empty");
+            Vec::new()
+        };
+
+        log::info!("Loaded {} entries from fingerprint index", entries.len());
+        Ok(Self {
+            path: path_ref.to_path_buf(),
+            fingerprints: entries
+                .iter()
+                .map(|entry| Fingerprint::decode(&entry.fingerprint, entry.duration))
+                .collect(),
+            entries,
+        })
+    }
+
+    /// Write the index back to its file
+    pub fn save(&self) -> Result<(), LookupError> {
+        let content = serde_json::to_string_pretty(&self.entries).map_err(|e| LookupError::InvalidData(e.to_string()))?;
+        std::fs::write(&self.path, content).map_err(|e| LookupError::IoError(e.to_string()))?;
+        log::info!("Saved {} entries to fingerprint index {:?}", self.entries.len(), self.path);
+        Ok(())
+    }
+
+    /// Add an entry, replacing any previous entry of the same file
+    pub fn add(&mut self, entry: IndexEntry) {
+        if let Some(index) = entry
+            .path
+            .as_ref()
+            .and_then(|path| self.entries.iter().position(|existing| existing.path.as_ref() == Some(path)))
+        {
+            self.entries.remove(index);
+            self.fingerprints.remove(index);
+        }
+        self.fingerprints.push(Fingerprint::decode(&entry.fingerprint, entry.duration));
+        self.entries.push(entry);
+    }
+
+    /// Number of indexed tracks
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl FingerprintProvider for FingerprintIndex {
+    fn name(&self) -> String {
+        format!("Fingerprint index ({})", self.path.to_string_lossy())
+    }
+
+    fn identify(&self, fingerprint: &Fingerprint) -> Result<Vec<RecordingMatch>, LookupError> {
+        let mut matches: Vec<RecordingMatch> = self
+            .entries
+            .iter()
+            .zip(&self.fingerprints)
+            .filter(|(entry, _)| entry.is_tagged())
+            .filter_map(|(entry, indexed)| {
+                let similarity = indexed.as_ref()?.similarity(fingerprint);
+                (similarity >= DUPLICATE_THRESHOLD).then(|| RecordingMatch {
+                    title: entry.title.clone(),
+                    artist: entry.artist.clone(),
+                    album: entry.album.clone(),
+                    recording_id: entry.recording_id.clone(),
+                    score: (similarity * 100.0).round() as u32,
+                })
+            })
+            .collect();
+        matches.sort_by_key(|candidate| std::cmp::Reverse(candidate.score));
+        // The same recording may be indexed from several files
+        matches.dedup_by(|a, b| a.title == b.title && a.artist == b.artist && a.recording_id == b.recording_id);
+        matches.truncate(MAX_MATCHES);
+
+        log::debug!("{} recordings matched in {}", matches.len(), self.name());
+        Ok(matches)
+    }
+}
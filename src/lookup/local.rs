@@ -0,0 +1,211 @@
+use super::{score_release, CandidateTrack, LookupError, LookupQuery, MetadataProvider, ReleaseCandidate};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Minimum score for a release to be suggested
+const MIN_SCORE: u32 = 20;
+
+/// Maximum number of suggested releases
+const MAX_CANDIDATES: usize = 10;
+
+/// A release in MusicBrainz JSON format (web service / JSON dump)
+#[derive(Debug, Deserialize)]
+struct MbRelease {
+    id: String,
+    title: String,
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default, rename = "artist-credit")]
+    artist_credit: Vec<MbArtistCredit>,
+    #[serde(default, rename = "release-group")]
+    release_group: Option<MbEntity>,
+    #[serde(default, rename = "label-info")]
+    label_info: Vec<MbLabelInfo>,
+    #[serde(default)]
+    media: Vec<MbMedium>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+    #[serde(default)]
+    artist: Option<MbEntity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbEntity {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbLabelInfo {
+    #[serde(default)]
+    label: Option<MbLabel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbMedium {
+    #[serde(default = "default_position")]
+    position: u32,
+    #[serde(default)]
+    tracks: Vec<MbTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbTrack {
+    position: u32,
+    title: String,
+    #[serde(default)]
+    length: Option<u32>, // in milliseconds
+    #[serde(default, rename = "artist-credit")]
+    artist_credit: Vec<MbArtistCredit>,
+    #[serde(default)]
+    recording: Option<MbRecording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRecording {
+    id: String,
+    #[serde(default)]
+    isrcs: Vec<String>,
+}
+
+fn default_position() -> u32 {
+    1
+}
+
+/// Join an artist credit into its display name ("A feat. B")
+fn credit_name(credits: &[MbArtistCredit]) -> String {
+    credits
+        .iter()
+        .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+impl MbRelease {
+    /// Convert to an (unscored) release candidate
+    fn into_candidate(self) -> ReleaseCandidate {
+        let artist = credit_name(&self.artist_credit);
+        let tracks = self
+            .media
+            .iter()
+            .flat_map(|medium| {
+                let release_artist = artist.clone();
+                medium.tracks.iter().map(move |track| {
+                    let track_artist = credit_name(&track.artist_credit);
+                    CandidateTrack {
+                        recording_id: track.recording.as_ref().map(|recording| recording.id.clone()),
+                        disc: medium.position,
+                        position: track.position,
+                        title: track.title.clone(),
+                        artist: (!track_artist.is_empty() && track_artist != release_artist).then_some(track_artist),
+                        duration: track.length.map(|length| (length + 500) / 1000),
+                        isrc: track
+                            .recording
+                            .as_ref()
+                            .and_then(|recording| recording.isrcs.first().cloned()),
+                    }
+                })
+            })
+            .collect();
+
+        ReleaseCandidate {
+            release_id: self.id,
+            release_group_id: self.release_group.map(|group| group.id),
+            title: self.title,
+            artist_id: self
+                .artist_credit
+                .first()
+                .and_then(|credit| credit.artist.as_ref())
+                .map(|artist| artist.id.clone()),
+            artist,
+            year: self
+                .date
+                .as_deref()
+                .and_then(|date| date.get(..4))
+                .and_then(|year| year.parse().ok()),
+            label: self
+                .label_info
+                .into_iter()
+                .find_map(|info| info.label.map(|label| label.name)),
+            tracks,
+            score: 0,
+        }
+    }
+}
+
+/// Metadata provider backed by a local MusicBrainz-style JSON file
+///
+/// The file holds either a JSON array of releases or one release per line, as in the
+/// MusicBrainz JSON data dumps.
+#[derive(Debug)]
+pub struct LocalDatabase {
+    path: PathBuf,
+    releases: Vec<ReleaseCandidate>,
+}
+
+impl LocalDatabase {
+    /// Load all releases of a database file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LookupError> {
+        let path_ref = path.as_ref();
+
+        log::debug!("Loading lookup database from: {:?}", path_ref);
+
+        if !path_ref.exists() {
+            log::error!("Lookup database not found: {:?}", path_ref);
+            return Err(LookupError::SourceNotFound);
+        }
+
+        let content = std::fs::read_to_string(path_ref).map_err(|e| LookupError::IoError(e.to_string()))?;
+        let releases: Vec<MbRelease> = if content.trim_start().starts_with('[') {
+            serde_json::from_str(&content).map_err(|e| LookupError::InvalidData(e.to_string()))?
+        } else {
+            content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|e| LookupError::InvalidData(e.to_string()))?
+        };
+
+        log::info!("Loaded {} releases from lookup database", releases.len());
+        Ok(Self {
+            path: path_ref.to_path_buf(),
+            releases: releases.into_iter().map(MbRelease::into_candidate).collect(),
+        })
+    }
+}
+
+impl MetadataProvider for LocalDatabase {
+    fn name(&self) -> String {
+        format!("Local database ({})", self.path.to_string_lossy())
+    }
+
+    fn search(&self, query: &LookupQuery) -> Result<Vec<ReleaseCandidate>, LookupError> {
+        let mut candidates: Vec<ReleaseCandidate> = self
+            .releases
+            .iter()
+            .filter_map(|release| {
+                let score = score_release(release, query);
+                (score >= MIN_SCORE).then(|| ReleaseCandidate {
+                    score,
+                    ..release.clone()
+                })
+            })
+            .collect();
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.score));
+        candidates.truncate(MAX_CANDIDATES);
+
+        log::debug!("{} candidates found in {}", candidates.len(), self.name());
+        Ok(candidates)
+    }
+}
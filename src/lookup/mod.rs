@@ -0,0 +1,337 @@
+pub mod fingerprint;
+pub mod local;
+
+use crate::id3_parser::timestamp::from_year;
+use crate::id3_parser::AudioMetadata;
+
+/// Errors that can occur while looking up metadata
+#[derive(Debug, Clone)]
+pub enum LookupError {
+    SourceNotFound,
+    InvalidData(String),
+    IoError(String),
+}
+
+impl std::fmt::Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::SourceNotFound => write!(f, "Metadata source not found"),
+            LookupError::InvalidData(msg) => write!(f, "Invalid metadata source: {}", msg),
+            LookupError::IoError(msg) => write!(f, "IO error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+/// A source of release metadata (local database dump, web service, ...)
+///
+/// Providers are shared with background tasks, so they must be thread safe.
+pub trait MetadataProvider: Send + Sync {
+    /// Short name shown in the UI
+    fn name(&self) -> String;
+
+    /// Find releases matching the query, best matches first
+    fn search(&self, query: &LookupQuery) -> Result<Vec<ReleaseCandidate>, LookupError>;
+}
+
+impl std::fmt::Debug for dyn MetadataProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MetadataProvider({})", self.name())
+    }
+}
+
+/// The values of a track that are used to find matching releases
+#[derive(Debug, Clone, Default)]
+pub struct LookupQuery {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub title: Option<String>,
+    pub duration: Option<u32>, // in seconds
+    pub isrc: Option<String>,
+}
+
+impl LookupQuery {
+    /// Build a query from the metadata of a track
+    pub fn from_metadata(metadata: &AudioMetadata) -> Self {
+        Self {
+            artist: metadata.album_artist.clone().or_else(|| metadata.artists.first().cloned()),
+            album: metadata.album.clone(),
+            title: metadata.title.clone(),
+            // TLEN is in milliseconds
+            duration: metadata.duration.map(|duration_ms| duration_ms / 1000),
+            isrc: metadata.isrc.clone(),
+        }
+    }
+
+    /// Whether the query has anything to match on
+    pub fn is_empty(&self) -> bool {
+        self.artist.is_none() && self.album.is_none() && self.title.is_none() && self.isrc.is_none()
+    }
+}
+
+/// A release suggested by a provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleaseCandidate {
+    pub release_id: String,
+    pub release_group_id: Option<String>,
+    pub title: String,
+    pub artist: String,
+    pub artist_id: Option<String>,
+    pub year: Option<i32>,
+    pub label: Option<String>,
+    pub tracks: Vec<CandidateTrack>,
+    pub score: u32, // 0-100, how well the release matches the query
+}
+
+/// A track of a suggested release
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateTrack {
+    pub recording_id: Option<String>,
+    pub disc: u32,
+    pub position: u32,
+    pub title: String,
+    pub artist: Option<String>, // Only set when it differs from the release artist
+    pub duration: Option<u32>,  // in seconds
+    pub isrc: Option<String>,
+}
+
+impl ReleaseCandidate {
+    /// Total number of tracks over all discs
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Find the track of this release that corresponds to a file's metadata
+    ///
+    /// ISRC and title matches are preferred; track/disc numbers are used as a fallback.
+    pub fn matching_track(&self, metadata: &AudioMetadata) -> Option<&CandidateTrack> {
+        if let Some(isrc) = metadata.isrc.as_ref().filter(|isrc| !isrc.is_empty()) {
+            if let Some(track) = self
+                .tracks
+                .iter()
+                .find(|track| track.isrc.as_ref().is_some_and(|track_isrc| track_isrc.eq_ignore_ascii_case(isrc)))
+            {
+                return Some(track);
+            }
+        }
+        if let Some(title) = metadata.title.as_deref().map(normalize).filter(|title| !title.is_empty()) {
+            if let Some(track) = self.tracks.iter().find(|track| normalize(&track.title) == title) {
+                return Some(track);
+            }
+        }
+        let position = metadata.track?;
+        let disc = metadata.disc.unwrap_or(1);
+        self.tracks
+            .iter()
+            .find(|track| track.position == position && track.disc == disc)
+    }
+
+    /// Apply the release and matching track values to a file's metadata
+    ///
+    /// Returns false (leaving the metadata untouched) if no track of the release matches.
+    pub fn apply_to(&self, metadata: &mut AudioMetadata) -> bool {
+        let Some(track) = self.matching_track(metadata).cloned() else {
+            return false;
+        };
+
+        metadata.album = Some(self.title.clone());
+        metadata.album_artist = Some(self.artist.clone());
+        metadata.artists = vec![track.artist.unwrap_or_else(|| self.artist.clone())];
+        metadata.title = Some(track.title);
+        metadata.track = Some(track.position);
+        metadata.total_tracks = Some(self.tracks.iter().filter(|other| other.disc == track.disc).count() as u32);
+        metadata.disc = Some(track.disc);
+        metadata.total_discs = self.tracks.iter().map(|other| other.disc).max();
+        // A full recording date of the same year is more precise than the release year
+        if let Some(year) = self.year.filter(|&year| metadata.recorded.map(|recorded| recorded.year) != Some(year)) {
+            metadata.recorded = Some(from_year(year));
+        }
+        if self.label.is_some() {
+            metadata.publisher = self.label.clone();
+        }
+        if track.isrc.is_some() {
+            metadata.isrc = track.isrc;
+        }
+        metadata.musicbrainz_release_id = Some(self.release_id.clone());
+        if self.release_group_id.is_some() {
+            metadata.musicbrainz_release_group_id = self.release_group_id.clone();
+        }
+        if self.artist_id.is_some() {
+            metadata.musicbrainz_artist_id = self.artist_id.clone();
+        }
+        if track.recording_id.is_some() {
+            metadata.musicbrainz_recording_id = track.recording_id;
+        }
+        true
+    }
+}
+
+/// Score how well a release matches a query (0-100)
+pub fn score_release(release: &ReleaseCandidate, query: &LookupQuery) -> u32 {
+    let mut score = 0;
+
+    score += text_score(query.album.as_deref(), &release.title, 30);
+    score += text_score(query.artist.as_deref(), &release.artist, 20);
+
+    // Best matching track: an ISRC match is decisive, otherwise title and duration
+    let best_track = release
+        .tracks
+        .iter()
+        .map(|track| {
+            let isrc_match = match (&query.isrc, &track.isrc) {
+                (Some(query_isrc), Some(track_isrc)) => query_isrc.eq_ignore_ascii_case(track_isrc),
+                _ => false,
+            };
+            if isrc_match {
+                return 50;
+            }
+            let mut track_score = text_score(query.title.as_deref(), &track.title, 35);
+            if let (Some(query_duration), Some(track_duration)) = (query.duration, track.duration) {
+                if track_score > 0 && query_duration.abs_diff(track_duration) <= 3 {
+                    track_score += 15;
+                }
+            }
+            track_score
+        })
+        .max()
+        .unwrap_or(0);
+
+    (score + best_track).min(100)
+}
+
+/// Award `weight` points for an exact (normalized) match and half for a partial match
+fn text_score(query: Option<&str>, value: &str, weight: u32) -> u32 {
+    let Some(query) = query.map(normalize).filter(|query| !query.is_empty()) else {
+        return 0;
+    };
+    let value = normalize(value);
+    if value == query {
+        weight
+    } else if !value.is_empty() && (value.contains(&query) || query.contains(&value)) {
+        weight / 2
+    } else {
+        0
+    }
+}
+
+/// Normalize text for comparisons: lowercase alphanumerics only
+pub fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Provider that scores a fixed set of releases
+    struct MockProvider {
+        releases: Vec<ReleaseCandidate>,
+    }
+
+    impl MetadataProvider for MockProvider {
+        fn name(&self) -> String {
+            "Mock".to_string()
+        }
+
+        fn search(&self, query: &LookupQuery) -> Result<Vec<ReleaseCandidate>, LookupError> {
+            Ok(self
+                .releases
+                .iter()
+                .map(|release| ReleaseCandidate {
+                    score: score_release(release, query),
+                    ..release.clone()
+                })
+                .filter(|release| release.score > 0)
+                .collect())
+        }
+    }
+
+    fn track(position: u32, title: &str, duration: Option<u32>) -> CandidateTrack {
+        CandidateTrack {
+            recording_id: Some(format!("recording-{}", position)),
+            disc: 1,
+            position,
+            title: title.to_string(),
+            artist: None,
+            duration,
+            isrc: None,
+        }
+    }
+
+    fn release() -> ReleaseCandidate {
+        ReleaseCandidate {
+            release_id: "release".to_string(),
+            release_group_id: Some("group".to_string()),
+            title: "Album".to_string(),
+            artist: "Artist".to_string(),
+            artist_id: None,
+            year: Some(1999),
+            label: Some("Label".to_string()),
+            tracks: vec![track(1, "First", Some(200)), track(2, "Second", Some(180))],
+            score: 0,
+        }
+    }
+
+    fn provider() -> Arc<dyn MetadataProvider> {
+        Arc::new(MockProvider { releases: vec![release()] })
+    }
+
+    #[test]
+    fn query_duration_is_in_seconds() {
+        let metadata = AudioMetadata {
+            duration: Some(180_400),
+            ..AudioMetadata::default()
+        };
+        assert_eq!(LookupQuery::from_metadata(&metadata).duration, Some(180));
+    }
+
+    #[test]
+    fn searches_through_a_shared_provider() {
+        let metadata = AudioMetadata {
+            artists: vec!["Artist".to_string()],
+            album: Some("Album".to_string()),
+            title: Some("Second".to_string()),
+            duration: Some(181_000),
+            ..AudioMetadata::default()
+        };
+        let provider = provider();
+        let candidates = provider.search(&LookupQuery::from_metadata(&metadata)).unwrap();
+        assert_eq!(candidates.len(), 1);
+        // Album, artist, title and duration all match
+        assert_eq!(candidates[0].score, 100);
+        assert_eq!(format!("{:?}", provider), "MetadataProvider(Mock)");
+    }
+
+    #[test]
+    fn unrelated_queries_find_nothing() {
+        let query = LookupQuery {
+            album: Some("Other".to_string()),
+            ..LookupQuery::default()
+        };
+        assert!(provider().search(&query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn applies_the_matching_track() {
+        let mut metadata = AudioMetadata {
+            title: Some("second".to_string()),
+            ..AudioMetadata::default()
+        };
+        assert!(release().apply_to(&mut metadata));
+        assert_eq!(metadata.track, Some(2));
+        assert_eq!(metadata.total_tracks, Some(2));
+        assert_eq!(metadata.album.as_deref(), Some("Album"));
+        assert_eq!(metadata.artists, vec!["Artist"]);
+        assert_eq!(metadata.musicbrainz_recording_id.as_deref(), Some("recording-2"));
+
+        let mut unmatched = AudioMetadata::default();
+        assert!(!release().apply_to(&mut unmatched));
+        assert!(unmatched.album.is_none());
+    }
+}
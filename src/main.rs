@@ -1,6 +1,11 @@
 mod app;
+mod audio;
 mod config;
+mod duplicates;
 mod id3_parser;
+mod lookup;
+mod rename;
+mod report;
 
 use app::{State, update, view};
 use env_logger::{Builder, Env};
@@ -0,0 +1,452 @@
+use crate::id3_parser::template::render_template;
+use crate::id3_parser::AudioMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest file or folder name written, in bytes (most file systems allow 255)
+const MAX_COMPONENT_LENGTH: usize = 200;
+
+/// Longest extension kept when a name is shortened, in bytes
+const MAX_EXTENSION_LENGTH: usize = 10;
+
+/// Names Windows reserves for devices, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Maximum number of rename batches kept in the undo log
+const MAX_UNDO_BATCHES: usize = 20;
+
+/// Sanitize a string to be used as a file or folder name on any common file system
+///
+/// Characters that are invalid on Windows, macOS or Linux (and control characters) become
+/// `_`, whitespace is collapsed, trailing dots and spaces are removed, reserved device
+/// names get a `_` suffix and the result is limited to `MAX_COMPONENT_LENGTH` bytes by
+/// shortening the part before the extension.
+pub fn sanitize_filename(name: &str) -> String {
+    let mut sanitized = replace_invalid_chars(name)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['.', ' '])
+        .to_string();
+
+    let stem = sanitized.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        sanitized.insert(stem.len(), '_');
+    }
+
+    if sanitized.len() > MAX_COMPONENT_LENGTH {
+        // A short last part without spaces is an extension and is kept
+        let (stem, extension) = match sanitized.rsplit_once('.') {
+            Some((stem, extension))
+                if !stem.is_empty() && extension.len() <= MAX_EXTENSION_LENGTH && !extension.contains(' ') =>
+            {
+                (stem.to_string(), Some(extension.to_string()))
+            }
+            _ => (sanitized.clone(), None),
+        };
+        let mut end = MAX_COMPONENT_LENGTH - extension.as_ref().map_or(0, |extension| extension.len() + 1);
+        while !stem.is_char_boundary(end) {
+            end -= 1;
+        }
+        let stem = stem[..end].trim_end_matches(['.', ' ']);
+        sanitized = match extension {
+            Some(extension) => format!("{}.{}", stem, extension),
+            None => stem.to_string(),
+        };
+    }
+    sanitized
+}
+
+/// Replace the characters that are invalid in file names (including `/`) with `_`
+fn replace_invalid_chars(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// What will happen to a file when the renames are applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameStatus {
+    Ready,
+    Unchanged,
+    Conflict(String),
+}
+
+/// A planned move of a file
+#[derive(Debug, Clone)]
+pub struct PlannedRename {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub status: RenameStatus,
+}
+
+/// Build the target path of every file from a template
+///
+/// Each `/`-separated part of the rendered template becomes a folder (the last one the file
+/// name), relative to `destination` or, if there is none, to the file's own folder. Values
+/// are sanitized so they can't introduce folders of their own.
+pub fn plan_renames(
+    files: &[(PathBuf, AudioMetadata)],
+    template: &str,
+    destination: Option<&Path>,
+) -> Result<Vec<PlannedRename>, String> {
+    let mut plan = Vec::new();
+    for (source, metadata) in files {
+        let rendered = render_template(template, metadata, source, replace_invalid_chars)?;
+        let base = destination
+            .map(Path::to_path_buf)
+            .or_else(|| source.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        let mut target = base;
+        for component in rendered.split('/') {
+            let component = sanitize_filename(component);
+            target.push(if component.is_empty() { "_".to_string() } else { component });
+        }
+        plan.push(PlannedRename {
+            source: source.clone(),
+            target,
+            status: RenameStatus::Ready,
+        });
+    }
+
+    // Detect conflicts: targets shared by several files (case-insensitively, for
+    // case-insensitive file systems) and existing files that aren't being moved away
+    let mut target_counts: HashMap<String, usize> = HashMap::new();
+    for entry in &plan {
+        *target_counts.entry(path_key(&entry.target)).or_default() += 1;
+    }
+    let moving_sources: Vec<String> = plan
+        .iter()
+        .filter(|entry| entry.source != entry.target)
+        .map(|entry| path_key(&entry.source))
+        .collect();
+    for entry in &mut plan {
+        entry.status = if entry.source == entry.target {
+            RenameStatus::Unchanged
+        } else if target_counts[&path_key(&entry.target)] > 1 {
+            RenameStatus::Conflict("Several files get this name".to_string())
+        } else if path_key(&entry.source) == path_key(&entry.target) {
+            // Only the case changes
+            RenameStatus::Ready
+        } else if entry.target.exists() && !moving_sources.contains(&path_key(&entry.target)) {
+            RenameStatus::Conflict("A file with this name already exists".to_string())
+        } else {
+            RenameStatus::Ready
+        };
+    }
+
+    Ok(plan)
+}
+
+/// Case-insensitive comparison key of a path
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// Outcome of moving files
+#[derive(Debug, Clone, Default)]
+pub struct MoveReport {
+    pub moves: Vec<(PathBuf, PathBuf)>, // (from, to) of every file moved
+    pub failed: Vec<String>,            // Files that couldn't be moved
+}
+
+/// Move the files of a plan that are ready
+pub fn apply_renames(plan: &[PlannedRename]) -> MoveReport {
+    let ready: Vec<&PlannedRename> = plan.iter().filter(|entry| entry.status == RenameStatus::Ready).collect();
+
+    // Move through temporary names first so files can swap names (a -> b, b -> a)
+    let mut staged = Vec::new();
+    let mut failed = Vec::new();
+    for (index, entry) in ready.iter().enumerate() {
+        let temporary = entry.source.with_file_name(format!(".id3-rename-{}-{}", std::process::id(), index));
+        match move_file(&entry.source, &temporary) {
+            Ok(()) => staged.push((temporary, *entry)),
+            Err(e) => {
+                log::warn!("Could not move {:?}: {}", entry.source, e);
+                failed.push(entry.source.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    let mut moves = Vec::new();
+    for (temporary, entry) in staged {
+        let result = match entry.target.parent() {
+            Some(parent) => fs::create_dir_all(parent).map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+        .and_then(|()| {
+            if entry.target.exists() {
+                Err("target already exists".to_string())
+            } else {
+                move_file(&temporary, &entry.target)
+            }
+        });
+        match result {
+            Ok(()) => {
+                log::info!("Moved {:?} to {:?}", entry.source, entry.target);
+                moves.push((entry.source.clone(), entry.target.clone()));
+            }
+            Err(e) => {
+                log::warn!("Could not move {:?} to {:?}: {}", entry.source, entry.target, e);
+                // Put the file back under its original name
+                if let Err(e) = move_file(&temporary, &entry.source) {
+                    log::error!("Could not restore {:?} from {:?}: {}", entry.source, temporary, e);
+                }
+                failed.push(entry.source.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    MoveReport { moves, failed }
+}
+
+/// Rename a file, copying it when it has to cross file systems
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| e.to_string())?;
+    fs::remove_file(from).map_err(|e| e.to_string())
+}
+
+/// A group of moves made by one rename operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RenameBatch {
+    time: u64, // Seconds since the Unix epoch
+    moves: Vec<RenameMove>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RenameMove {
+    from: String,
+    to: String,
+}
+
+/// Log of recent rename operations that can be undone
+pub struct UndoLog {
+    path: PathBuf,
+    batches: Vec<RenameBatch>,
+}
+
+impl UndoLog {
+    /// Load the undo log; a missing or unreadable file is an empty log
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let batches = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, batches }
+    }
+
+    /// Whether there is a rename to undo
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// Record the moves of a rename operation
+    pub fn record(&mut self, moves: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+        if moves.is_empty() {
+            return Ok(());
+        }
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        self.batches.push(RenameBatch {
+            time,
+            moves: moves
+                .iter()
+                .map(|(from, to)| RenameMove {
+                    from: from.to_string_lossy().to_string(),
+                    to: to.to_string_lossy().to_string(),
+                })
+                .collect(),
+        });
+        if self.batches.len() > MAX_UNDO_BATCHES {
+            self.batches.remove(0);
+        }
+        self.save()
+    }
+
+    /// Move the files of the most recent rename operation back
+    ///
+    /// Folders the operation created are removed again when they end up empty. Moves that
+    /// can't be undone stay in the log, so undoing can be tried again.
+    pub fn undo_last(&mut self) -> Result<MoveReport, String> {
+        let batch = self.batches.last().ok_or("Nothing to undo")?;
+        log::info!("Undoing rename of {} files from {}", batch.moves.len(), batch.time);
+
+        let mut moves = Vec::new();
+        let mut failed = Vec::new();
+        let mut remaining = Vec::new();
+        for entry in batch.moves.iter().rev() {
+            let (from, to) = (PathBuf::from(&entry.to), PathBuf::from(&entry.from));
+            if to.exists() {
+                log::warn!("Not undoing move of {:?}: {:?} exists", from, to);
+                failed.push(entry.to.clone());
+                remaining.push(entry.clone());
+                continue;
+            }
+            let result = match to.parent() {
+                Some(parent) => fs::create_dir_all(parent).map_err(|e| e.to_string()),
+                None => Ok(()),
+            }
+            .and_then(|()| move_file(&from, &to));
+            match result {
+                Ok(()) => {
+                    remove_empty_parents(&from);
+                    moves.push((from, to));
+                }
+                Err(e) => {
+                    log::warn!("Could not move {:?} back to {:?}: {}", from, to, e);
+                    failed.push(entry.to.clone());
+                    remaining.push(entry.clone());
+                }
+            }
+        }
+
+        if remaining.is_empty() {
+            self.batches.pop();
+        } else if let Some(batch) = self.batches.last_mut() {
+            remaining.reverse();
+            batch.moves = remaining;
+        }
+        self.save()?;
+        Ok(MoveReport { moves, failed })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.batches).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// Remove the folders above a moved-away file as long as they are empty
+fn remove_empty_parents(path: &Path) {
+    let mut current = path.parent();
+    while let Some(folder) = current {
+        if fs::remove_dir(folder).is_err() {
+            break;
+        }
+        log::debug!("Removed empty folder {:?}", folder);
+        current = folder.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty scratch folder for one test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("id3-rename-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn titled(title: &str) -> AudioMetadata {
+        AudioMetadata {
+            title: Some(title.to_string()),
+            ..AudioMetadata::default()
+        }
+    }
+
+    #[test]
+    fn sanitizes_invalid_characters_and_reserved_names() {
+        assert_eq!(sanitize_filename("AC/DC: Live?"), "AC_DC_ Live_");
+        assert_eq!(sanitize_filename("  a   b .. "), "a b");
+        assert_eq!(sanitize_filename("con.mp3"), "con_.mp3");
+        assert_eq!(sanitize_filename("Console"), "Console");
+    }
+
+    #[test]
+    fn shortens_long_names_before_the_extension() {
+        let name = format!("{}.mp3", "é".repeat(150));
+        let sanitized = sanitize_filename(&name);
+        assert!(sanitized.len() <= MAX_COMPONENT_LENGTH);
+        assert!(sanitized.ends_with("é.mp3"));
+
+        let folder = "a b ".repeat(100);
+        assert!(sanitize_filename(&folder).len() <= MAX_COMPONENT_LENGTH);
+    }
+
+    #[test]
+    fn plans_detect_conflicts() {
+        let dir = scratch_dir("plan");
+        let (a, b, c) = (dir.join("a.mp3"), dir.join("b.mp3"), dir.join("c.mp3"));
+        for path in [&a, &b, &c] {
+            fs::write(path, "").unwrap();
+        }
+        fs::write(dir.join("Taken.mp3"), "").unwrap();
+
+        let files = vec![(a, titled("Same")), (b, titled("Same")), (c, titled("Taken"))];
+        let plan = plan_renames(&files, "{title}.{ext}", None).unwrap();
+        assert!(matches!(plan[0].status, RenameStatus::Conflict(_)));
+        assert!(matches!(plan[1].status, RenameStatus::Conflict(_)));
+        assert!(matches!(plan[2].status, RenameStatus::Conflict(_)));
+
+        let plan = plan_renames(&files[..1], "{filename}.{ext}", None).unwrap();
+        assert_eq!(plan[0].status, RenameStatus::Unchanged);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn renames_can_swap_names_and_be_undone() {
+        let dir = scratch_dir("swap");
+        let (a, b) = (dir.join("a.mp3"), dir.join("b.mp3"));
+        fs::write(&a, "first").unwrap();
+        fs::write(&b, "second").unwrap();
+
+        let files = vec![(a.clone(), titled("b")), (b.clone(), titled("a"))];
+        let plan = plan_renames(&files, "{title}.{ext}", None).unwrap();
+        assert!(plan.iter().all(|entry| entry.status == RenameStatus::Ready));
+        let report = apply_renames(&plan);
+        assert!(report.failed.is_empty());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "second");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "first");
+
+        let mut log = UndoLog::load(dir.join("undo.json"));
+        log.record(&report.moves).unwrap();
+        // Both names are taken, so nothing can be moved back yet and the batch is kept
+        let report = log.undo_last().unwrap();
+        assert_eq!(report.failed.len(), 2);
+        assert!(!log.is_empty());
+
+        fs::rename(&a, dir.join("moved away.mp3")).unwrap();
+        let report = log.undo_last().unwrap();
+        assert_eq!(report.moves.len(), 1);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "first");
+        assert!(!log.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn undo_removes_created_folders() {
+        let dir = scratch_dir("undo");
+        let source = dir.join("song.mp3");
+        fs::write(&source, "").unwrap();
+
+        let plan = plan_renames(&[(source.clone(), titled("Song"))], "new/{title}.{ext}", None).unwrap();
+        let report = apply_renames(&plan);
+        assert!(dir.join("new").join("Song.mp3").exists());
+
+        let mut log = UndoLog::load(dir.join("undo.json"));
+        log.record(&report.moves).unwrap();
+        let report = UndoLog::load(dir.join("undo.json")).undo_last().unwrap();
+        assert!(report.failed.is_empty());
+        assert!(source.exists());
+        assert!(!dir.join("new").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
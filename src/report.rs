@@ -0,0 +1,486 @@
+use crate::id3_parser::fields::MetadataField;
+use crate::id3_parser::{has_id3v1, parse_id3, supports_id3, AudioMetadata, ListSeparator, ParseError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Options of the tag health report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportOptions {
+    pub required_fields: Vec<String>, // Field names (see `MetadataField::from_name`)
+    pub min_cover_size: u32,          // Shortest side of the cover art, in pixels
+    pub max_cover_size: u32,          // Longest side of the cover art, in pixels
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            required_fields: ["Title", "Artist", "Album", "Track"].map(String::from).to_vec(),
+            min_cover_size: 300,
+            max_cover_size: 3000,
+        }
+    }
+}
+
+impl ReportOptions {
+    /// The fields every file must have
+    pub fn required(&self) -> Vec<MetadataField> {
+        self.required_fields
+            .iter()
+            .filter_map(|name| MetadataField::from_name(name))
+            .collect()
+    }
+
+    /// Require a field or stop requiring it
+    pub fn set_required(&mut self, field: MetadataField, required: bool) {
+        self.required_fields
+            .retain(|name| MetadataField::from_name(name) != Some(field));
+        if required {
+            self.required_fields.push(field.label().to_string());
+        }
+    }
+}
+
+/// The kinds of problems the report finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProblemKind {
+    Unreadable,
+    NoTag,
+    Id3v1Only,
+    MissingFields,
+    NoCoverArt,
+    CoverTooSmall,
+    CoverTooLarge,
+    MixedVersions,
+    InconsistentAlbumArtist,
+    InconsistentYear,
+    DuplicateTrack,
+}
+
+impl ProblemKind {
+    /// Human readable label
+    pub fn label(self) -> &'static str {
+        match self {
+            ProblemKind::Unreadable => "Unreadable tag",
+            ProblemKind::NoTag => "No tag",
+            ProblemKind::Id3v1Only => "ID3v1 only",
+            ProblemKind::MissingFields => "Missing fields",
+            ProblemKind::NoCoverArt => "No cover art",
+            ProblemKind::CoverTooSmall => "Cover art too small",
+            ProblemKind::CoverTooLarge => "Cover art too large",
+            ProblemKind::MixedVersions => "Mixed ID3 versions",
+            ProblemKind::InconsistentAlbumArtist => "Inconsistent album artist",
+            ProblemKind::InconsistentYear => "Inconsistent year",
+            ProblemKind::DuplicateTrack => "Duplicate track number",
+        }
+    }
+}
+
+/// A problem found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub album: String,
+    pub kind: ProblemKind,
+    pub details: String,
+}
+
+/// The problems found in the files of a folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagReport {
+    pub folder: PathBuf,
+    pub files_scanned: usize,
+    pub files_skipped: usize, // Files in formats without ID3 tags, which aren't checked
+    pub problems: Vec<Problem>, // Sorted by path, then kind
+}
+
+impl TagReport {
+    /// One line summary of the problems found
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} problems in {} of {} files",
+            self.problems.len(),
+            self.files_with_problems(),
+            self.files_scanned
+        );
+        if self.files_skipped > 0 {
+            let _ = write!(summary, " ({} files in formats without ID3 tags skipped)", self.files_skipped);
+        }
+        summary
+    }
+
+    /// Number of files with at least one problem
+    pub fn files_with_problems(&self) -> usize {
+        self.problems.iter().map(|problem| &problem.path).collect::<BTreeSet<_>>().len()
+    }
+
+    /// Number of problems of each kind
+    pub fn counts(&self) -> BTreeMap<ProblemKind, usize> {
+        let mut counts = BTreeMap::new();
+        for problem in &self.problems {
+            *counts.entry(problem.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// The report as CSV, one problem per line
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("File,Album,Problem,Details\n");
+        for problem in &self.problems {
+            let fields = [
+                problem.path.to_string_lossy().to_string(),
+                problem.album.clone(),
+                problem.kind.label().to_string(),
+                problem.details.clone(),
+            ];
+            let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&line.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// The report as a standalone HTML page, with a summary and a table of the problems
+    pub fn to_html(&self) -> String {
+        let folder = escape_html(&self.folder.to_string_lossy());
+        let mut html = String::new();
+        let _ = writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(html, "<title>Tag report: {}</title>", folder);
+        let _ = writeln!(
+            html,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}</style>"
+        );
+        let _ = writeln!(html, "</head>\n<body>\n<h1>Tag report: {}</h1>", folder);
+        let _ = writeln!(html, "<p>{}.</p>", escape_html(&self.summary()));
+        let _ = writeln!(html, "<ul>");
+        for (kind, count) in self.counts() {
+            let _ = writeln!(html, "<li>{}: {}</li>", kind.label(), count);
+        }
+        let _ = writeln!(html, "</ul>");
+        let _ = writeln!(html, "<table>\n<tr><th>File</th><th>Album</th><th>Problem</th><th>Details</th></tr>");
+        for problem in &self.problems {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&problem.path.to_string_lossy()),
+                escape_html(&problem.album),
+                problem.kind.label(),
+                escape_html(&problem.details)
+            );
+        }
+        let _ = writeln!(html, "</table>\n</body>\n</html>");
+        html
+    }
+}
+
+/// A file whose tag was read, as grouped into albums
+struct TaggedFile<'a> {
+    path: &'a Path,
+    metadata: AudioMetadata,
+}
+
+/// Check the tags of files and of the albums they form
+///
+/// Files are grouped into albums by folder and album title; files without an album title
+/// aren't grouped. Album problems (mixed tag versions, differing album artists or years,
+/// duplicate track numbers) are listed for every file of the album involved. Files in
+/// formats that don't use ID3 tags (FLAC, Ogg, MP4) are skipped.
+pub fn build_report(folder: &Path, files: &[PathBuf], options: &ReportOptions, separator: ListSeparator) -> TagReport {
+    let required = options.required();
+    let mut problems = Vec::new();
+    let mut albums: BTreeMap<(PathBuf, String), Vec<TaggedFile>> = BTreeMap::new();
+    let (files, skipped): (Vec<&PathBuf>, Vec<&PathBuf>) = files.iter().partition(|path| supports_id3(path));
+
+    for path in files.iter().copied() {
+        let mut metadata = match parse_id3(path, separator) {
+            Ok(metadata) => metadata,
+            Err(ParseError::NoId3Tag) => {
+                let (kind, details) = if has_id3v1(path) {
+                    (ProblemKind::Id3v1Only, "Only an ID3v1 tag, convert it to ID3v2")
+                } else {
+                    (ProblemKind::NoTag, "No ID3 tag")
+                };
+                problems.push(problem(path, "", kind, details.to_string()));
+                continue;
+            }
+            Err(e) => {
+                problems.push(problem(path, "", ProblemKind::Unreadable, e.to_string()));
+                continue;
+            }
+        };
+        let album = metadata.album.clone().unwrap_or_default();
+
+        let missing: Vec<&str> = required
+            .iter()
+            .filter(|field| field.value(&metadata).is_none_or(|value| value.trim().is_empty()))
+            .map(|field| field.label())
+            .collect();
+        if !missing.is_empty() {
+            problems.push(problem(path, &album, ProblemKind::MissingFields, missing.join(", ")));
+        }
+
+        match metadata.cover_art.as_deref() {
+            None => problems.push(problem(path, &album, ProblemKind::NoCoverArt, String::new())),
+            Some(data) => match image_dimensions(data) {
+                Some((width, height)) if width.min(height) < options.min_cover_size => problems.push(problem(
+                    path,
+                    &album,
+                    ProblemKind::CoverTooSmall,
+                    format!("{}×{} px (minimum {} px)", width, height, options.min_cover_size),
+                )),
+                Some((width, height)) if width.max(height) > options.max_cover_size => problems.push(problem(
+                    path,
+                    &album,
+                    ProblemKind::CoverTooLarge,
+                    format!("{}×{} px (maximum {} px)", width, height, options.max_cover_size),
+                )),
+                Some(_) => {}
+                None => log::warn!("Could not decode the cover art of {:?}", path),
+            },
+        }
+
+        // The images were measured, so they don't need to be kept for the album checks
+        metadata.cover_art = None;
+        for chapter in &mut metadata.chapters {
+            chapter.image = None;
+        }
+
+        if album.trim().is_empty() {
+            continue;
+        }
+        let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        albums
+            .entry((parent, album.to_lowercase()))
+            .or_default()
+            .push(TaggedFile { path, metadata });
+    }
+
+    for tracks in albums.values().filter(|tracks| tracks.len() > 1) {
+        check_album(tracks, &mut problems);
+    }
+
+    problems.sort_by(|a, b| (&a.path, a.kind).cmp(&(&b.path, b.kind)));
+    TagReport {
+        folder: folder.to_path_buf(),
+        files_scanned: files.len(),
+        files_skipped: skipped.len(),
+        problems,
+    }
+}
+
+/// A value that all files of an album should share, as text
+type AlbumValue = fn(&AudioMetadata) -> String;
+
+/// Check that the files of an album agree with each other
+fn check_album(tracks: &[TaggedFile], problems: &mut Vec<Problem>) {
+    let album = |track: &TaggedFile| track.metadata.album.clone().unwrap_or_default();
+
+    let checks: [(ProblemKind, AlbumValue); 3] = [
+        (ProblemKind::MixedVersions, |metadata| {
+            metadata.tag_version.map_or_else(|| "none".to_string(), |version| version.to_string())
+        }),
+        (ProblemKind::InconsistentAlbumArtist, |metadata| {
+            metadata.album_artist.clone().unwrap_or_else(|| "(none)".to_string())
+        }),
+        (ProblemKind::InconsistentYear, |metadata| {
+            metadata.recorded.map_or_else(|| "(none)".to_string(), |recorded| recorded.year.to_string())
+        }),
+    ];
+    for (kind, value) in checks {
+        let values: BTreeSet<String> = tracks.iter().map(|track| value(&track.metadata)).collect();
+        if values.len() > 1 {
+            let all = values.into_iter().collect::<Vec<_>>().join(", ");
+            for track in tracks {
+                let details = format!("{} (album has {})", value(&track.metadata), all);
+                problems.push(problem(track.path, &album(track), kind, details));
+            }
+        }
+    }
+
+    let mut numbers: BTreeMap<(u32, u32), Vec<&Path>> = BTreeMap::new();
+    for track in tracks {
+        if let Some(number) = track.metadata.track {
+            numbers
+                .entry((track.metadata.disc.unwrap_or(1), number))
+                .or_default()
+                .push(track.path);
+        }
+    }
+    for ((disc, number), paths) in numbers.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        for track in tracks.iter().filter(|track| paths.contains(&track.path)) {
+            let others: Vec<String> = paths
+                .iter()
+                .filter(|path| **path != track.path)
+                .map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string())
+                .collect();
+            let details = format!("Track {} of disc {} is also on {}", number, disc, others.join(", "));
+            problems.push(problem(track.path, &album(track), ProblemKind::DuplicateTrack, details));
+        }
+    }
+}
+
+fn problem(path: &Path, album: &str, kind: ProblemKind, details: String) -> Problem {
+    Problem {
+        path: path.to_path_buf(),
+        album: album.to_string(),
+        kind,
+        details,
+    }
+}
+
+/// Width and height of an encoded image, without decoding the pixels
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Quote a CSV field if it contains a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape text for HTML element content and attribute values
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// File formats the report can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Html,
+}
+
+impl ReportFormat {
+    /// File name extension
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// The report in this format
+    pub fn render(self, report: &TagReport) -> String {
+        match self {
+            ReportFormat::Csv => report.to_csv(),
+            ReportFormat::Html => report.to_html(),
+        }
+    }
+}
+
+impl std::fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportFormat::Csv => write!(f, "CSV"),
+            ReportFormat::Html => write!(f, "HTML"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("id3-report-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn track(album: &str, number: u32, album_artist: &str) -> AudioMetadata {
+        AudioMetadata {
+            album: Some(album.to_string()),
+            track: Some(number),
+            album_artist: Some(album_artist.to_string()),
+            ..AudioMetadata::default()
+        }
+    }
+
+    fn kinds(problems: &[Problem]) -> Vec<ProblemKind> {
+        problems.iter().map(|problem| problem.kind).collect()
+    }
+
+    #[test]
+    fn required_fields_can_be_toggled() {
+        let mut options = ReportOptions::default();
+        options.set_required(MetadataField::Album, false);
+        options.set_required(MetadataField::Genre, true);
+        assert_eq!(
+            options.required(),
+            [MetadataField::Title, MetadataField::Artist, MetadataField::Track, MetadataField::Genre]
+        );
+    }
+
+    #[test]
+    fn album_checks_find_inconsistencies_and_duplicates() {
+        let (first, second) = (PathBuf::from("/music/a.mp3"), PathBuf::from("/music/b.mp3"));
+        let tracks = [
+            TaggedFile { path: &first, metadata: track("Album", 1, "Band") },
+            TaggedFile { path: &second, metadata: track("Album", 1, "The Band") },
+        ];
+        let mut problems = Vec::new();
+        check_album(&tracks, &mut problems);
+        assert_eq!(
+            kinds(&problems),
+            [
+                ProblemKind::InconsistentAlbumArtist,
+                ProblemKind::InconsistentAlbumArtist,
+                ProblemKind::DuplicateTrack,
+                ProblemKind::DuplicateTrack,
+            ]
+        );
+        assert_eq!(problems[2].details, "Track 1 of disc 1 is also on b.mp3");
+    }
+
+    #[test]
+    fn skips_formats_without_id3_tags() {
+        let dir = scratch_dir("formats");
+        let files = [dir.join("song.mp3"), dir.join("song.flac")];
+        for file in &files {
+            fs::write(file, b"").unwrap();
+        }
+        let report = build_report(&dir, &files, &ReportOptions::default(), ListSeparator::default());
+        assert_eq!((report.files_scanned, report.files_skipped), (1, 1));
+        assert!(report.problems.iter().all(|problem| problem.path == files[0]));
+        assert!(report.summary().ends_with("(1 files in formats without ID3 tags skipped)"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn exports_escape_their_fields() {
+        let report = TagReport {
+            folder: PathBuf::from("/music"),
+            files_scanned: 1,
+            files_skipped: 0,
+            problems: vec![problem(
+                Path::new("/music/a.mp3"),
+                "Rock, Paper & \"Scissors\"",
+                ProblemKind::MissingFields,
+                "Title".to_string(),
+            )],
+        };
+        assert_eq!(
+            report.to_csv(),
+            "File,Album,Problem,Details\n/music/a.mp3,\"Rock, Paper & \"\"Scissors\"\"\",Missing fields,Title\n"
+        );
+        assert!(report.to_html().contains("<td>Rock, Paper &amp; &quot;Scissors&quot;</td>"));
+        assert_eq!(report.summary(), "1 problems in 1 of 1 files");
+    }
+}